@@ -0,0 +1,145 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rendering of matched names with ANSI escape sequences.
+//!
+//! This module is only available with the `ansi` feature. It builds on
+//! `Pattern::match_spans` to style the parts of a name that were matched by
+//! wildcards differently from the parts that were matched literally, like
+//! `fd` and similar tools do when echoing results to a terminal.
+
+use std::path::Path;
+
+use {os_str_lossy, MatchOptions, Pattern};
+
+/// The styles used by `Highlighter`, as the parameters of an ANSI "Select
+/// Graphic Rendition" sequence, e.g. `"1;31"` for bold red.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Highlighter {
+    /// The style of text matched by `?`, `*`, `**` and `[...]`, or `None`
+    /// to leave it unstyled.
+    pub wildcard: Option<&'static str>,
+
+    /// The style of text matched by literal characters of the pattern, or
+    /// `None` to leave it unstyled.
+    pub literal: Option<&'static str>,
+}
+
+impl Highlighter {
+    /// Constructs a `Highlighter` that renders text matched by wildcards in
+    /// bold yellow and leaves the rest unstyled.
+    pub fn new() -> Self {
+        Self {
+            wildcard: Some("1;33"),
+            literal: None,
+        }
+    }
+
+    /// If `s` matches `pattern` using the specified match options, return it
+    /// with ANSI escape sequences applied to each matched region.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::ansi::Highlighter;
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let pattern = Pattern::new("*.rs").unwrap();
+    /// let rendered = Highlighter::new().render(&pattern, "main.rs", MatchOptions::new());
+    /// assert_eq!(rendered.unwrap(), "\x1b[1;33mmain\x1b[0m.rs");
+    /// ```
+    pub fn render(&self, pattern: &Pattern, s: &str, options: MatchOptions) -> Option<String> {
+        let spans = pattern.match_spans_with(s, options)?;
+
+        let mut rendered = String::with_capacity(s.len());
+        let mut i = 0;
+        while i < spans.len() {
+            // merge runs of spans of the same kind into one styled region
+            let literal = spans[i].literal;
+            let start = spans[i].range.start;
+            let mut end = spans[i].range.end;
+            while i < spans.len() && spans[i].literal == literal {
+                end = spans[i].range.end;
+                i += 1;
+            }
+            if start == end {
+                continue;
+            }
+
+            let style = if literal { self.literal } else { self.wildcard };
+            match style {
+                Some(style) => {
+                    rendered.push_str("\x1b[");
+                    rendered.push_str(style);
+                    rendered.push('m');
+                    rendered.push_str(&s[start..end]);
+                    rendered.push_str("\x1b[0m");
+                }
+                None => rendered.push_str(&s[start..end]),
+            }
+        }
+        Some(rendered)
+    }
+
+    /// If `path` matches `pattern` using the specified match options, return
+    /// it with ANSI escape sequences applied to each matched region. Like
+    /// `Pattern::matches_os`, each byte that isn't part of valid UTF-8 is
+    /// only matched by a wildcard, and is rendered as `U+FFFD REPLACEMENT
+    /// CHARACTER`.
+    pub fn render_path(
+        &self,
+        pattern: &Pattern,
+        path: &Path,
+        options: MatchOptions,
+    ) -> Option<String> {
+        self.render(pattern, &os_str_lossy(path.as_os_str()), options)
+    }
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Highlighter;
+    use {MatchOptions, Pattern};
+
+    #[test]
+    fn test_render() {
+        let highlighter = Highlighter {
+            wildcard: Some("1"),
+            literal: Some("2"),
+        };
+        let render = |pattern: &str, s: &str| {
+            highlighter.render(&Pattern::new(pattern).unwrap(), s, MatchOptions::new())
+        };
+        assert_eq!(
+            render("a*c?", "abbcd").unwrap(),
+            "\x1b[2ma\x1b[0m\x1b[1mbb\x1b[0m\x1b[2mc\x1b[0m\x1b[1md\x1b[0m"
+        );
+        assert_eq!(render("ab*", "ab").unwrap(), "\x1b[2mab\x1b[0m");
+        assert!(render("ab*", "b").is_none());
+
+        let plain = Highlighter {
+            wildcard: None,
+            literal: None,
+        };
+        let pattern = Pattern::new("*.[ch]").unwrap();
+        assert_eq!(
+            plain
+                .render(&pattern, "glob.c", MatchOptions::new())
+                .unwrap(),
+            "glob.c"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Brace expansion, see `ParseOptions::braces`.
+//!
+//! Braces are expanded textually before the pattern is compiled, the way
+//! bash does it, so `*.{rs,toml}` is compiled as the two alternatives `*.rs`
+//! and `*.toml`, and `{1..3}` as `1`, `2` and `3`.
+
+use std::cmp;
+use std::path;
+
+use {ParseOptions, PatternError, PatternErrorKind};
+
+// The most alternatives braces may expand to, whatever
+// `ParseOptions::max_alternatives` says.
+const MAX_ALTERNATIVES: usize = 100_000;
+
+const ERROR_TOO_MANY_ALTERNATIVES: &str = "braces expand to too many alternatives";
+const ERROR_SEPARATOR_IN_BRACES: &str = "braces can't contain a path separator when globbing";
+
+// Return the position of the `]` that closes the class starting at
+// `chars[i]`, if it's closed. As in `Pattern::new`, a `]` right after the `[`
+// or `[!` is part of the class.
+fn class_end(chars: &[char], i: usize) -> Option<usize> {
+    let mut start = i + 1;
+    if chars.get(start) == Some(&'!') {
+        start += 1;
+    }
+    chars
+        .iter()
+        .skip(start + 1)
+        .position(|&c| c == ']')
+        .map(|j| start + 1 + j)
+}
+
+// Parses the inside of a range of integers like `1..20` into its ends and the
+// width to zero-pad the numbers to, which is that of the longer end if either
+// of them has a leading zero, as in `01..20`.
+fn range(chars: &[char]) -> Option<(i64, i64, usize)> {
+    let s: String = chars.iter().collect();
+    let mut ends = s.splitn(2, "..");
+    let (start, end) = (ends.next()?, ends.next()?);
+    let mut width = 0;
+    for n in &[start, end] {
+        let digits = if n.starts_with('-') { &n[1..] } else { n };
+        if digits.is_empty() || !digits.chars().all(|c| c >= '0' && c <= '9') {
+            return None;
+        }
+        if digits.len() > 1 && digits.starts_with('0') {
+            width = cmp::max(start.len(), end.len());
+        }
+    }
+    Some((start.parse().ok()?, end.parse().ok()?, width))
+}
+
+// Finds the first brace expression in `chars` at or after `from`, and
+// returns the positions of its `{`, of the commas between its alternatives,
+// and of its `}`. There are no commas in a range like `{1..20}`. Other braces
+// without a comma between them are literal, and so are the ones within
+// `[...]` and, with `ParseOptions::backslash_escapes`, escaped ones.
+fn find(chars: &[char], from: usize, options: ParseOptions) -> Option<(usize, Vec<usize>, usize)> {
+    let mut i = from;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if options.backslash_escapes => {
+                i += 2;
+                continue;
+            }
+            '[' => {
+                i = class_end(chars, i).unwrap_or(i) + 1;
+                continue;
+            }
+            '{' => (),
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+
+        let open = i;
+        let mut depth = 0;
+        let mut commas = Vec::new();
+        let mut j = open + 1;
+        while j < chars.len() {
+            match chars[j] {
+                '\\' if options.backslash_escapes => {
+                    j += 2;
+                    continue;
+                }
+                '[' => {
+                    j = class_end(chars, j).unwrap_or(j) + 1;
+                    continue;
+                }
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                ',' if depth == 0 => commas.push(j),
+                _ => (),
+            }
+            j += 1;
+        }
+        if j < chars.len() && (!commas.is_empty() || range(&chars[open + 1..j]).is_some()) {
+            return Some((open, commas, j));
+        }
+        i = open + 1;
+    }
+    None
+}
+
+// Expands the braces in `pattern`, e.g. `a{b,c}d` becomes `abd` and `acd`,
+// failing if there would be more alternatives than `options` allow, or than
+// `MAX_ALTERNATIVES`.
+pub(crate) fn expand(pattern: &str, options: ParseOptions) -> Result<Vec<String>, PatternError> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let pos = find(&chars, 0, options).map_or(0, |(open, _, _)| open);
+    let mut expanded = Vec::new();
+    if !expand_into(chars, options, &mut expanded) {
+        return Err(PatternError {
+            pos,
+            msg: ERROR_TOO_MANY_ALTERNATIVES,
+            kind: PatternErrorKind::TooManyAlternatives,
+        });
+    }
+    Ok(expanded)
+}
+
+// Adds the expansions of `chars` to `expanded`, returning false if there are
+// too many of them.
+fn expand_into(chars: Vec<char>, options: ParseOptions, expanded: &mut Vec<String>) -> bool {
+    let (open, commas, close) = match find(&chars, 0, options) {
+        Some(expression) => expression,
+        None => {
+            let max = options
+                .max_alternatives
+                .map_or(MAX_ALTERNATIVES, |max| cmp::min(max, MAX_ALTERNATIVES));
+            if expanded.len() >= max {
+                return false;
+            }
+            expanded.push(chars.into_iter().collect());
+            return true;
+        }
+    };
+
+    if commas.is_empty() {
+        let (start, end, width) = range(&chars[open + 1..close]).unwrap();
+        let step = if start <= end { 1 } else { -1 };
+        let mut n = start;
+        loop {
+            let mut word = chars[..open].to_vec();
+            word.extend(format!("{:01$}", n, width).chars());
+            word.extend_from_slice(&chars[close + 1..]);
+            if !expand_into(word, options, expanded) {
+                return false;
+            }
+            if n == end {
+                return true;
+            }
+            n += step;
+        }
+    }
+
+    let mut bounds = vec![open];
+    bounds.extend(commas);
+    bounds.push(close);
+    for alternative in bounds.windows(2) {
+        let mut word = chars[..open].to_vec();
+        word.extend_from_slice(&chars[alternative[0] + 1..alternative[1]]);
+        word.extend_from_slice(&chars[close + 1..]);
+        if !expand_into(word, options, expanded) {
+            return false;
+        }
+    }
+    true
+}
+
+// Return an error if a brace expression in `pattern` contains a path
+// separator, since `glob` matches one path component at a time.
+pub(crate) fn check_components(pattern: &str, options: ParseOptions) -> Result<(), PatternError> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut from = 0;
+    while let Some((open, _, close)) = find(&chars, from, options) {
+        if chars[open..close].iter().any(|&c| path::is_separator(c)) {
+            return Err(PatternError {
+                pos: open,
+                msg: ERROR_SEPARATOR_IN_BRACES,
+                kind: PatternErrorKind::Syntax,
+            });
+        }
+        from = close + 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_components, expand};
+    use {ParseOptions, PatternErrorKind};
+
+    #[test]
+    fn test_expand() {
+        let expand = |p| expand(p, ParseOptions::new()).unwrap();
+        assert_eq!(expand("*.{rs,toml}"), vec!["*.rs", "*.toml"]);
+        assert_eq!(expand("{a,b}{1,2}"), vec!["a1", "a2", "b1", "b2"]);
+        assert_eq!(expand("a{b,{c,d}e}"), vec!["ab", "ace", "ade"]);
+        assert_eq!(expand("x{,y}"), vec!["x", "xy"]);
+        assert_eq!(expand("{a}"), vec!["{a}"]);
+        assert_eq!(expand("{a,b"), vec!["{a,b"]);
+        assert_eq!(expand("{x{a,b}}"), vec!["{xa}", "{xb}"]);
+        assert_eq!(expand("[{,}]"), vec!["[{,}]"]);
+        assert_eq!(expand("{[],],a}"), vec!["[],]", "a"]);
+        assert_eq!(expand("{[a,b}"), vec!["[a", "b"]);
+
+        assert_eq!(expand("{1..3}"), vec!["1", "2", "3"]);
+        assert_eq!(expand("{3..1}"), vec!["3", "2", "1"]);
+        assert_eq!(expand("{-1..1}"), vec!["-1", "0", "1"]);
+        assert_eq!(expand("f{08..10}"), vec!["f08", "f09", "f10"]);
+        assert_eq!(expand("{001..3}"), vec!["001", "002", "003"]);
+        assert_eq!(expand("{-2..02}"), vec!["-2", "-1", "00", "01", "02"]);
+        assert_eq!(expand("{a,{1..2}}"), vec!["a", "1", "2"]);
+        assert_eq!(expand("{7..7}"), vec!["7"]);
+        assert_eq!(expand("{1..}"), vec!["{1..}"]);
+        assert_eq!(expand("{1..2..3}"), vec!["{1..2..3}"]);
+        assert_eq!(expand("{a..c}"), vec!["{a..c}"]);
+        assert_eq!(expand("{1..99999999999999999999}").len(), 1);
+
+        let options = ParseOptions::new().backslash_escapes(true);
+        let expand_escaped = |p| super::expand(p, options).unwrap();
+        assert_eq!(expand_escaped("\\{a,b}"), vec!["\\{a,b}"]);
+        assert_eq!(expand_escaped("{a\\,b,c\\}}"), vec!["a\\,b", "c\\}"]);
+        assert_eq!(expand("{a\\,b}"), vec!["a\\", "b"]);
+
+        let options = ParseOptions::new().max_alternatives(Some(3));
+        assert!(super::expand("x{a,b,c}", options).is_ok());
+        let err = super::expand("x{a,b}{c,d}", options).unwrap_err();
+        assert_eq!(err.kind(), PatternErrorKind::TooManyAlternatives);
+        assert_eq!(err.pos, 1);
+        let err = super::expand("{1..1000000000}", ParseOptions::new()).unwrap_err();
+        assert_eq!(err.kind(), PatternErrorKind::TooManyAlternatives);
+    }
+
+    #[test]
+    fn test_check_components() {
+        let options = ParseOptions::new();
+        assert!(check_components("{src,tests}/**/*.{rs,toml}", options).is_ok());
+        assert!(check_components("{a/b}/c", options).is_ok());
+        assert_eq!(check_components("x/{a/b,c}", options).unwrap_err().pos, 2);
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers for Cargo build scripts.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use {glob, Paths, PatternError};
+
+/// Globs for `pattern` and prints a `cargo:rerun-if-changed` line for every
+/// match, returning the matches.
+///
+/// A line is also printed for every directory that had to be listed to find
+/// the matches, so that Cargo reruns the build script when a matching file is
+/// added as well as when one is modified or removed. Directories that can't
+/// be read are reported too, since they may become readable later.
+///
+/// Note that Cargo scans directories recursively for changes, so a pattern
+/// that lists the package root, like `*/assets/*`, will cause the build
+/// script to rerun whenever anything in the package changes.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// // build.rs
+/// extern crate glob;
+///
+/// fn main() {
+///     for asset in glob::build::rerun_if_changed("assets/**/*.svg").unwrap() {
+///         // embed `asset`
+///     }
+/// }
+/// ```
+pub fn rerun_if_changed(pattern: &str) -> Result<Vec<PathBuf>, PatternError> {
+    let paths = glob(pattern)?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    Ok(write_rerun_if_changed(paths, &mut out).expect("failed to write to stdout"))
+}
+
+fn write_rerun_if_changed<W: Write>(mut paths: Paths, out: &mut W) -> io::Result<Vec<PathBuf>> {
+    paths.state.listed = Some(Vec::new());
+
+    let mut matches = Vec::new();
+    let mut reported = 0;
+    loop {
+        let next = paths.next();
+
+        // report directories as they're listed, so that they're printed even
+        // if the build script panics on one of the matches
+        let listed = paths.state.listed.as_ref().unwrap();
+        for dir in &listed[reported..] {
+            writeln!(out, "cargo:rerun-if-changed={}", dir.display())?;
+        }
+        reported = listed.len();
+
+        match next {
+            Some(Ok(path)) => {
+                writeln!(out, "cargo:rerun-if-changed={}", path.display())?;
+                matches.push(path);
+            }
+            Some(Err(_)) => {}
+            None => return Ok(matches),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::write_rerun_if_changed;
+    use glob;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_write_rerun_if_changed() {
+        let mut out = Vec::new();
+        let matches = write_rerun_if_changed(glob("src/*.rs").unwrap(), &mut out).unwrap();
+        assert!(matches.contains(&PathBuf::from("src/build.rs")));
+
+        let out = String::from_utf8(out).unwrap();
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some("cargo:rerun-if-changed=src"));
+        assert!(lines.any(|l| l == "cargo:rerun-if-changed=src/build.rs"));
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::path;
+
+use {rewrite, token, ClassMember, ParseOptions, Pattern, PatternError};
+
+/// A builder for patterns that interpolate untrusted strings.
+///
+/// Pieces added with `literal` and `then_literal` are escaped, so they only
+/// ever match themselves, while pieces added with `glob` and `then_glob` are
+/// taken as pattern syntax. Each piece added with a `then_` method is a path
+/// of its own and is joined to the previous one with a separator, unless
+/// there already is one at the boundary, while the `append_` methods add
+/// typed pieces such as wildcards and character classes to the last path
+/// component without a separator.
+///
+/// Escaping doesn't change the meaning of separators or of `..` components
+/// in a literal piece; check for those separately if the input should only
+/// ever name a single file.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::PatternBuilder;
+///
+/// let user_input = "[draft] notes";
+/// let pattern = PatternBuilder::literal(user_input)
+///     .then_glob("**/*.log")
+///     .build()
+///     .unwrap();
+/// assert_eq!(pattern.as_str(), "[[]draft[]] notes/**/*.log");
+/// assert!(pattern.matches("[draft] notes/2024/jan.log"));
+/// assert!(!pattern.matches("d notes/2024/jan.log"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct PatternBuilder {
+    pattern: String,
+}
+
+impl PatternBuilder {
+    /// Constructs an empty `PatternBuilder`.
+    pub fn new() -> Self {
+        Self {
+            pattern: String::new(),
+        }
+    }
+
+    /// Constructs a `PatternBuilder` that starts with the given string,
+    /// matched literally.
+    pub fn literal(s: &str) -> Self {
+        Self::new().then_literal(s)
+    }
+
+    /// Constructs a `PatternBuilder` that starts with the given pattern.
+    pub fn glob(pattern: &str) -> Self {
+        Self::new().then_glob(pattern)
+    }
+
+    /// Appends the given string, matched literally, as a path.
+    pub fn then_literal(self, s: &str) -> Self {
+        self.then_glob(&Pattern::escape(s))
+    }
+
+    /// Appends the given pattern as a path.
+    pub fn then_glob(mut self, pattern: &str) -> Self {
+        if pattern.is_empty() {
+            return self;
+        }
+        let needs_separator = self
+            .pattern
+            .chars()
+            .next_back()
+            .map_or(false, |c| !path::is_separator(c))
+            && !pattern.starts_with(path::is_separator);
+        if needs_separator {
+            self.pattern.push('/');
+        }
+        self.pattern.push_str(pattern);
+        self
+    }
+
+    /// Appends `**`, any sequence of path components, as a path.
+    pub fn then_recursive_wildcard(self) -> Self {
+        self.then_glob("**")
+    }
+
+    /// Appends the given string, matched literally, to the last path
+    /// component.
+    pub fn append_literal(mut self, s: &str) -> Self {
+        self.pattern.push_str(&Pattern::escape(s));
+        self
+    }
+
+    /// Appends `?`, any single character.
+    pub fn append_any_char(mut self) -> Self {
+        self.pattern.push('?');
+        self
+    }
+
+    /// Appends `*`, any sequence of characters.
+    pub fn append_any_sequence(mut self) -> Self {
+        self.pattern.push('*');
+        self
+    }
+
+    /// Appends a character class, any one of the given characters. The
+    /// members are written with whatever escaping they need, so a `]`, `-`
+    /// or `!` among them is matched literally.
+    pub fn append_char_class(mut self, members: &[ClassMember]) -> Self {
+        rewrite::push_class(&mut self.pattern, &token::specifiers(members), false);
+        self
+    }
+
+    /// Appends a negated character class, any one character except the given
+    /// ones.
+    pub fn append_negated_char_class(mut self, members: &[ClassMember]) -> Self {
+        rewrite::push_class(&mut self.pattern, &token::specifiers(members), true);
+        self
+    }
+
+    /// The pattern built so far, as a string.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Compiles the pattern built so far.
+    pub fn build(&self) -> Result<Pattern, PatternError> {
+        Pattern::new(&self.pattern)
+    }
+
+    /// Compiles the pattern built so far using the specified parse options.
+    ///
+    /// Literal pieces are escaped with `Pattern::escape`, so a `\` in them
+    /// may not be matched literally if `options` gives it a meaning; see
+    /// `Pattern::escape_with`.
+    pub fn build_with(&self, options: ParseOptions) -> Result<Pattern, PatternError> {
+        Pattern::new_with(&self.pattern, options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PatternBuilder;
+    use ClassMember::{Char, Range};
+
+    #[test]
+    fn test_pattern_builder() {
+        assert_eq!(PatternBuilder::new().as_str(), "");
+        assert_eq!(
+            PatternBuilder::literal("a*")
+                .then_literal("")
+                .then_glob("*")
+                .as_str(),
+            "a[*]/*"
+        );
+        assert_eq!(
+            PatternBuilder::glob("src/")
+                .then_literal("?")
+                .then_glob("**")
+                .as_str(),
+            "src/[?]/**"
+        );
+
+        // an unbalanced bracket in the input can't break the pattern
+        let pattern = PatternBuilder::glob("*")
+            .then_literal("a[b")
+            .build()
+            .unwrap();
+        assert!(pattern.matches("x/a[b"));
+        assert!(PatternBuilder::glob("a[b").build().is_err());
+    }
+
+    #[test]
+    fn test_typed_pieces() {
+        let pattern = PatternBuilder::literal("src")
+            .then_recursive_wildcard()
+            .then_literal("mod")
+            .append_any_char()
+            .append_char_class(&[Range('0', '9'), Char(']'), Char('-')])
+            .append_literal(".*")
+            .append_any_sequence()
+            .build()
+            .unwrap();
+        assert_eq!(pattern.as_str(), "src/**/mod?[]0-9-].[*]*");
+        assert!(pattern.matches("src/a/b/mods].*.rs"));
+        assert!(pattern.matches("src/modx7.*"));
+        assert!(!pattern.matches("src/modx7.rs"));
+
+        let pattern = PatternBuilder::new()
+            .append_negated_char_class(&[Char('!'), Char('.')])
+            .append_char_class(&[Char('!')])
+            .append_char_class(&[])
+            .build()
+            .unwrap();
+        assert_eq!(pattern.as_str(), "[!!.]![b-a]");
+        assert!(!pattern.matches("a!"));
+        assert_eq!(
+            PatternBuilder::new()
+                .append_negated_char_class(&[])
+                .as_str(),
+            "?"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A persistent cache of directory listings.
+//!
+//! This module is only available with the `cache` feature. A `DirCache` can
+//! be handed to `GlobBuilder::dir_cache`, after which the listing of every
+//! directory that is read while globbing is looked up in the cache first.
+//! A cached listing is only used while the modification time of its
+//! directory is unchanged, so tools that glob the same, mostly unchanged
+//! tree on every run can skip re-reading it.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use glob::cache::DirCache;
+//! use glob::GlobBuilder;
+//!
+//! let cache = DirCache::open("target/.glob-cache").unwrap();
+//! for entry in GlobBuilder::new().dir_cache(Some(cache.clone())).glob("assets/**/*.png").unwrap() {
+//!     println!("{:?}", entry);
+//! }
+//! cache.save().unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "glob-dir-cache 1";
+
+// Listings of directories modified this recently aren't stored, since a
+// change made within the granularity of the filesystem's timestamps wouldn't
+// be noticed. This is the same problem git solves for its index.
+const RACY_WINDOW_SECS: u64 = 2;
+
+/// The type of a directory entry, as far as it matters for globbing.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum EntryKind {
+    Dir,
+    Other,
+    Symlink,
+    Unknown,
+}
+
+impl EntryKind {
+    fn from_file_type(file_type: io::Result<fs::FileType>) -> Self {
+        match file_type {
+            Ok(ref t) if t.is_symlink() => EntryKind::Symlink,
+            Ok(ref t) if t.is_dir() => EntryKind::Dir,
+            Ok(_) => EntryKind::Other,
+            Err(_) => EntryKind::Unknown,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            EntryKind::Dir => 'd',
+            EntryKind::Other => 'f',
+            EntryKind::Symlink => 'l',
+            EntryKind::Unknown => '?',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'd' => Some(EntryKind::Dir),
+            'f' => Some(EntryKind::Other),
+            'l' => Some(EntryKind::Symlink),
+            '?' => Some(EntryKind::Unknown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Listing {
+    mtime: (u64, u32),
+    entries: Vec<(String, EntryKind)>,
+}
+
+#[derive(Debug, Default)]
+struct Listings {
+    dirs: HashMap<PathBuf, Listing>,
+    dirty: bool,
+}
+
+/// An on-disk cache of directory listings, keyed by the absolute path and
+/// modification time of each directory.
+///
+/// Clones share the same underlying cache, so a clone can be given to
+/// `GlobBuilder::dir_cache` and the original used to `save` it afterwards.
+///
+/// Only directories whose path and entry names are valid Unicode are cached,
+/// and stale listings are replaced as soon as they're encountered. Changes
+/// that don't touch a directory's modification time, such as a symbolic link
+/// being retargeted, are still picked up since symlinks are always resolved
+/// anew.
+#[derive(Clone, Debug)]
+pub struct DirCache {
+    file: PathBuf,
+    listings: Arc<Mutex<Listings>>,
+}
+
+impl DirCache {
+    /// Loads the cache stored at `file`.
+    ///
+    /// A missing file, or one that wasn't written by a compatible version of
+    /// this crate, yields an empty cache rather than an error, since the
+    /// cache can always be rebuilt.
+    pub fn open<P: AsRef<Path>>(file: P) -> io::Result<DirCache> {
+        let file = file.as_ref().to_path_buf();
+        let dirs = match File::open(&file) {
+            Ok(f) => parse(BufReader::new(f))?.unwrap_or_default(),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(DirCache {
+            file,
+            listings: Arc::new(Mutex::new(Listings { dirs, dirty: false })),
+        })
+    }
+
+    /// Writes the cache back to the file it was loaded from, if anything in
+    /// it changed.
+    pub fn save(&self) -> io::Result<()> {
+        let mut listings = self.listings.lock().unwrap();
+        if !listings.dirty {
+            return Ok(());
+        }
+
+        let mut tmp = self.file.clone().into_os_string();
+        tmp.push(".tmp");
+        {
+            let mut out = BufWriter::new(File::create(&tmp)?);
+            writeln!(out, "{}", HEADER)?;
+            for (dir, listing) in &listings.dirs {
+                // keys are only inserted if they're valid Unicode
+                let dir = dir.to_str().unwrap();
+                writeln!(out, "> {} {} {}", listing.mtime.0, listing.mtime.1, dir)?;
+                for &(ref name, kind) in &listing.entries {
+                    writeln!(out, "{} {}", kind.to_char(), name)?;
+                }
+            }
+            out.flush()?;
+        }
+        fs::rename(&tmp, &self.file)?;
+        listings.dirty = false;
+        Ok(())
+    }
+
+    /// Forgets every cached listing.
+    pub fn clear(&self) {
+        let mut listings = self.listings.lock().unwrap();
+        listings.dirty |= !listings.dirs.is_empty();
+        listings.dirs.clear();
+    }
+
+    /// Lists the entries of `dir`, from the cache if its listing is still
+    /// fresh and by reading the directory otherwise.
+    pub(crate) fn list(&self, dir: &Path) -> io::Result<Vec<(OsString, EntryKind)>> {
+        let key = if dir.is_absolute() {
+            dir.to_path_buf()
+        } else {
+            env::current_dir()?.join(dir)
+        };
+        let mtime = fs::metadata(dir)?.modified().ok().and_then(to_secs_nanos);
+
+        if let Some(mtime) = mtime {
+            let listings = self.listings.lock().unwrap();
+            if let Some(listing) = listings.dirs.get(&key) {
+                if listing.mtime == mtime {
+                    return Ok(listing
+                        .entries
+                        .iter()
+                        .map(|&(ref name, kind)| (OsString::from(name.clone()), kind))
+                        .collect());
+                }
+            }
+        }
+
+        let started = SystemTime::now();
+        let mut entries = Vec::new();
+        for e in fs::read_dir(dir)? {
+            let e = e?;
+            entries.push((e.file_name(), EntryKind::from_file_type(e.file_type())));
+        }
+
+        let mut listings = self.listings.lock().unwrap();
+        let fresh = mtime.and_then(|mtime| {
+            let age = started.duration_since(UNIX_EPOCH + Duration::new(mtime.0, mtime.1));
+            match age {
+                Ok(age) if age >= Duration::from_secs(RACY_WINDOW_SECS) => Some(mtime),
+                _ => None,
+            }
+        });
+        let cached = match (fresh, key.to_str().map(is_storable)) {
+            (Some(mtime), Some(true)) => to_listing(mtime, &entries),
+            _ => None,
+        };
+        match cached {
+            Some(listing) => {
+                listings.dirs.insert(key, listing);
+                listings.dirty = true;
+            }
+            None => {
+                if listings.dirs.remove(&key).is_some() {
+                    listings.dirty = true;
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn to_secs_nanos(time: SystemTime) -> Option<(u64, u32)> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| (d.as_secs(), d.subsec_nanos()))
+}
+
+// Whether a name can be written on a line of the cache file.
+fn is_storable(s: &str) -> bool {
+    !s.contains('\n') && !s.contains('\r')
+}
+
+fn to_listing(mtime: (u64, u32), entries: &[(OsString, EntryKind)]) -> Option<Listing> {
+    let mut stored = Vec::with_capacity(entries.len());
+    for &(ref name, kind) in entries {
+        match OsStr::to_str(name) {
+            Some(name) if is_storable(name) => stored.push((name.to_string(), kind)),
+            _ => return None,
+        }
+    }
+    Some(Listing {
+        mtime,
+        entries: stored,
+    })
+}
+
+// Returns `None` if the contents aren't a cache in a format we understand.
+fn parse<R: BufRead>(reader: R) -> io::Result<Option<HashMap<PathBuf, Listing>>> {
+    let mut lines = reader.lines();
+    match lines.next() {
+        Some(line) => {
+            if line? != HEADER {
+                return Ok(None);
+            }
+        }
+        None => return Ok(None),
+    }
+
+    let mut dirs = HashMap::new();
+    let mut current: Option<(PathBuf, Listing)> = None;
+    for line in lines {
+        let line = line?;
+        if line.starts_with("> ") {
+            let mut fields = line[2..].splitn(3, ' ');
+            let secs = fields.next().and_then(|s| s.parse().ok());
+            let nanos = fields.next().and_then(|s| s.parse().ok());
+            let dir = fields.next();
+            let (secs, nanos, dir) = match (secs, nanos, dir) {
+                (Some(secs), Some(nanos), Some(dir)) => (secs, nanos, dir),
+                _ => return Ok(None),
+            };
+            if let Some((dir, listing)) = current.take() {
+                dirs.insert(dir, listing);
+            }
+            let listing = Listing {
+                mtime: (secs, nanos),
+                entries: Vec::new(),
+            };
+            current = Some((PathBuf::from(dir), listing));
+        } else {
+            let kind = line.chars().next().and_then(EntryKind::from_char);
+            match (kind, current.as_mut()) {
+                (Some(kind), Some(&mut (_, ref mut listing))) if line[1..].starts_with(' ') => {
+                    listing.entries.push((line[2..].to_string(), kind))
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+    if let Some((dir, listing)) = current.take() {
+        dirs.insert(dir, listing);
+    }
+    Ok(Some(dirs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, EntryKind};
+    use std::path::Path;
+
+    #[test]
+    fn test_parse() {
+        let dirs = parse("glob-dir-cache 1\n> 10 20 /a b\nd c\nf d e\n> 1 2 /x\n".as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(dirs.len(), 2);
+        let listing = &dirs[Path::new("/a b")];
+        assert_eq!(listing.mtime, (10, 20));
+        assert_eq!(
+            listing.entries,
+            vec![
+                ("c".to_string(), EntryKind::Dir),
+                ("d e".to_string(), EntryKind::Other)
+            ]
+        );
+        assert!(dirs[Path::new("/x")].entries.is_empty());
+
+        assert!(parse("".as_bytes()).unwrap().is_none());
+        assert!(parse("glob-dir-cache 0\n".as_bytes()).unwrap().is_none());
+        assert!(parse("glob-dir-cache 1\nd c\n".as_bytes())
+            .unwrap()
+            .is_none());
+        assert!(parse("glob-dir-cache 1\n> 1 /x\n".as_bytes())
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::borrow::Cow;
+use std::path::{self, Path};
+
+use glob_set::normalize;
+use os_str_lossy;
+
+/// A path prepared for being matched against many patterns.
+///
+/// Creating a `Candidate` works out the forms of the path that matching
+/// needs once, rather than for every pattern: the key a `GlobSet` looks its
+/// patterns up by, which ignores ASCII case and the kind of slash, as well as
+/// the file name and extension. Pass it to `Pattern::matches_candidate` or
+/// `GlobSet::matches_candidate` instead of the path itself.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::{Candidate, GlobSet, MatchOptions, Pattern};
+///
+/// let set = GlobSet::new(&["*.rs", "*.toml"]).unwrap();
+/// let pattern = Pattern::new("src/*").unwrap();
+/// let candidate = Candidate::new("src/lib.rs");
+/// assert_eq!(candidate.file_name(), "lib.rs");
+/// assert_eq!(candidate.extension(), Some("rs"));
+/// assert!(set.matches_candidate(&candidate, MatchOptions::new()));
+/// assert!(pattern.matches_candidate(&candidate, MatchOptions::new()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Candidate<'a> {
+    path: Cow<'a, str>,
+    // `path` as normalized by `glob_set::normalize`, which keeps every byte
+    // offset the same
+    folded: String,
+    // the byte offsets of the file name and extension in `path`
+    name: usize,
+    extension: Option<usize>,
+}
+
+impl<'a> Candidate<'a> {
+    /// Prepares `path` for matching.
+    pub fn new(path: &'a str) -> Self {
+        Self::from_cow(Cow::Borrowed(path))
+    }
+
+    /// Prepares `path` for matching. Like `Pattern::matches_os`, each byte
+    /// that isn't part of valid UTF-8 counts as a `U+FFFD REPLACEMENT
+    /// CHARACTER`, which only a wildcard matches.
+    pub fn from_path(path: &'a Path) -> Self {
+        Self::from_cow(os_str_lossy(path.as_os_str()))
+    }
+
+    fn from_cow(path: Cow<'a, str>) -> Self {
+        let folded = normalize(&path);
+        let name = path.rfind(path::is_separator).map_or(0, |i| i + 1);
+        // like `Path::extension`, a leading dot doesn't start one
+        let extension = match path[name..].rfind('.') {
+            Some(0) | None => None,
+            Some(i) => Some(name + i + 1),
+        };
+        Candidate {
+            path,
+            folded,
+            name,
+            extension,
+        }
+    }
+
+    /// The path as it will be matched.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// The part of the path after the last separator, which is empty if it
+    /// ends with one.
+    pub fn file_name(&self) -> &str {
+        &self.path[self.name..]
+    }
+
+    /// The part of the file name after its last `.`, if it has one that
+    /// isn't its first character.
+    pub fn extension(&self) -> Option<&str> {
+        self.extension.map(|i| &self.path[i..])
+    }
+
+    // The key a `GlobSet` looks up patterns by.
+    pub(crate) fn folded(&self) -> &str {
+        &self.folded
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Candidate;
+    use std::path::Path;
+
+    #[test]
+    fn test_candidate() {
+        let candidate = Candidate::new("Src/LIB.tar.GZ");
+        assert_eq!(candidate.as_str(), "Src/LIB.tar.GZ");
+        assert_eq!(candidate.folded(), "src/lib.tar.gz");
+        assert_eq!(candidate.file_name(), "LIB.tar.GZ");
+        assert_eq!(candidate.extension(), Some("GZ"));
+
+        assert_eq!(Candidate::new("a/.bashrc").extension(), None);
+        assert_eq!(Candidate::new("a.d/b").extension(), None);
+        assert_eq!(Candidate::new("a/").file_name(), "");
+        assert_eq!(Candidate::new("x.").extension(), Some(""));
+        assert_eq!(Candidate::new("").file_name(), "");
+
+        let candidate = Candidate::from_path(Path::new("é/Ä.rs"));
+        assert_eq!(candidate.folded(), "é/Ä.rs");
+        assert_eq!(candidate.file_name(), "Ä.rs");
+    }
+}
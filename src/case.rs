@@ -0,0 +1,225 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use filesystem::{FileSystem, StdFileSystem};
+
+/// Return whether file names in the directory `dir` are case sensitive.
+///
+/// This checks whether an existing entry of `dir` can be found under a name
+/// that differs only in case. If no entry is suitable, e.g. because `dir` is
+/// empty, a temporary file is created and removed again to find out.
+///
+/// Case sensitivity is a property of the volume, or on some systems of the
+/// individual directory, so neither the platform nor the volume of the
+/// current directory reliably tell how a given path behaves.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::{glob_with, is_case_sensitive, MatchOptions};
+///
+/// let options = MatchOptions {
+///     case_sensitive: is_case_sensitive("/media/usb").unwrap_or(true),
+///     ..MatchOptions::new()
+/// };
+/// for entry in glob_with("/media/usb/*.jpg", options).unwrap() {
+///     println!("{:?}", entry);
+/// }
+/// ```
+pub fn is_case_sensitive<P: AsRef<Path>>(dir: P) -> io::Result<bool> {
+    let dir = dir.as_ref();
+    if let Some(sensitive) = probe(&StdFileSystem, dir)? {
+        return Ok(sensitive);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let mut attempt = 0;
+    let (name, path) = loop {
+        let name = format!(".glob-case-probe-{}-{}", now, attempt);
+        let path = dir.join(&name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => break (name, path),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    };
+    let sensitive = fs::symlink_metadata(dir.join(name.to_uppercase())).is_err();
+    fs::remove_file(&path)?;
+    Ok(sensitive)
+}
+
+// Checks the case sensitivity of `dir` without modifying it, by looking up
+// one of its entries with the case of its ASCII letters swapped. Returns
+// `None` if there is no entry with such letters.
+pub(crate) fn probe<F: ?Sized + FileSystem>(fs: &F, dir: &Path) -> io::Result<Option<bool>> {
+    let names: HashSet<String> = fs
+        .read_dir(dir)?
+        .into_iter()
+        .filter_map(|(name, _)| name.into_string().ok())
+        .collect();
+
+    let swapped = names
+        .iter()
+        .map(|name| name.chars().map(swap_ascii_case).collect::<String>())
+        .find(|swapped| !names.contains(swapped));
+    let has_letters = names.iter().any(|name| name.chars().any(is_ascii_letter));
+    let swapped = match swapped {
+        Some(swapped) => swapped,
+        // every name also exists in the other case, which a case-insensitive
+        // directory couldn't hold
+        None if has_letters => return Ok(Some(true)),
+        None => return Ok(None),
+    };
+
+    match fs.symlink_metadata(&dir.join(swapped)) {
+        Ok(_) => Ok(Some(false)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Some(true)),
+        Err(e) => Err(e),
+    }
+}
+
+// Like `probe`, but if `dir` doesn't exist or doesn't tell, its nearest
+// ancestor that does is probed instead, which is usually on the same volume.
+pub(crate) fn probe_nearest<F: ?Sized + FileSystem>(fs: &F, dir: &Path) -> Option<bool> {
+    let mut dir = dir;
+    loop {
+        let is_current = dir.as_os_str().is_empty() || dir == Path::new(".");
+        let probed = probe(fs, if is_current { Path::new(".") } else { dir });
+        if let Ok(Some(case_sensitive)) = probed {
+            return Some(case_sensitive);
+        }
+        if is_current {
+            return None;
+        }
+        dir = dir.parent()?;
+    }
+}
+
+fn is_ascii_letter(c: char) -> bool {
+    (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z')
+}
+
+fn swap_ascii_case(c: char) -> char {
+    if c >= 'a' && c <= 'z' {
+        c.to_ascii_uppercase()
+    } else {
+        c.to_ascii_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{probe, probe_nearest};
+    use filesystem::{FileKind, FileSystem};
+    use std::ffi::OsString;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    // A flat directory of files, looked up with or without regard to case.
+    #[derive(Debug)]
+    struct Dir {
+        path: &'static str,
+        names: Vec<&'static str>,
+        case_sensitive: bool,
+    }
+
+    impl FileSystem for Dir {
+        fn metadata(&self, path: &Path) -> io::Result<FileKind> {
+            self.symlink_metadata(path)
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> io::Result<FileKind> {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let found = self.names.iter().any(|n| {
+                if self.case_sensitive {
+                    *n == name
+                } else {
+                    n.to_lowercase() == name.to_lowercase()
+                }
+            });
+            if found {
+                Ok(FileKind::File)
+            } else {
+                Err(io::Error::new(io::ErrorKind::NotFound, "not found"))
+            }
+        }
+
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<(OsString, FileKind)>> {
+            if path != Path::new(self.path) {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "not found"));
+            }
+            Ok(self
+                .names
+                .iter()
+                .map(|n| (OsString::from(n), FileKind::File))
+                .collect())
+        }
+
+        fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+            Ok(path.to_path_buf())
+        }
+
+        fn read_link(&self, _: &Path) -> io::Result<PathBuf> {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "not a link"))
+        }
+    }
+
+    #[test]
+    fn test_probe() {
+        let probe = |names: Vec<&'static str>, case_sensitive: bool| {
+            let dir = Dir {
+                path: "d",
+                names,
+                case_sensitive,
+            };
+            probe(&dir, Path::new("d")).unwrap()
+        };
+        assert_eq!(probe(vec!["1", "Readme.md"], true), Some(true));
+        assert_eq!(probe(vec!["1", "Readme.md"], false), Some(false));
+        assert_eq!(probe(vec!["a", "A"], true), Some(true));
+        assert_eq!(probe(vec!["a", "A", "b"], true), Some(true));
+        assert_eq!(probe(vec!["1", "_"], false), None);
+        assert_eq!(probe(vec![], true), None);
+    }
+
+    #[test]
+    fn test_probe_nearest() {
+        let dir = Dir {
+            path: "d",
+            names: vec!["Readme.md"],
+            case_sensitive: false,
+        };
+        assert_eq!(probe_nearest(&dir, Path::new("d")), Some(false));
+        assert_eq!(probe_nearest(&dir, Path::new("d/missing/x")), Some(false));
+        assert_eq!(probe_nearest(&dir, Path::new("e")), None);
+
+        let dir = Dir {
+            path: ".",
+            names: vec!["Readme.md"],
+            case_sensitive: true,
+        };
+        assert_eq!(probe_nearest(&dir, Path::new("missing")), Some(true));
+        let dir = Dir {
+            path: "d",
+            names: vec!["1"],
+            case_sensitive: true,
+        };
+        assert_eq!(probe_nearest(&dir, Path::new("/d/x")), None);
+    }
+}
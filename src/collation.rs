@@ -0,0 +1,108 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Locale-aware ordering of matches.
+//!
+//! This module is only available with the `collation` feature, which pulls
+//! in ICU's collation data. By default the entries of a directory are
+//! visited in byte-wise order of their names, which puts `Zebra` before
+//! `apple` and `été` after `zoo`. A `Collation` orders them the way a file
+//! manager would for the given locale instead.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use glob::collation::Collation;
+//! use glob::GlobBuilder;
+//!
+//! let collation = Collation::new("fr").unwrap();
+//! for entry in GlobBuilder::new().collate(collation).glob("docs/*").unwrap() {
+//!     println!("{}", entry.unwrap().display());
+//! }
+//! ```
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+
+use icu_collator::{Collator, CollatorOptions};
+use icu_provider::DataLocale;
+
+/// A locale-aware order of file names, see `GlobBuilder::collate`.
+pub struct Collation {
+    collator: Collator,
+}
+
+impl Collation {
+    /// Constructs the collation for `locale`, a BCP 47 language tag like
+    /// `"en"`, `"de-AT"` or `"sv"`. Locales without specific data fall back
+    /// to the root collation, which suits most languages.
+    pub fn new(locale: &str) -> Result<Self, CollationError> {
+        let locale = locale.parse::<DataLocale>().map_err(|e| CollationError {
+            msg: format!("invalid locale `{}`: {}", locale, e),
+        })?;
+        let collator =
+            Collator::try_new(&locale, CollatorOptions::new()).map_err(|e| CollationError {
+                msg: format!("no collation data for `{}`: {}", locale, e),
+            })?;
+        Ok(Self { collator })
+    }
+
+    /// Compares two file names. Names that aren't valid Unicode are compared
+    /// lossily, and names that collate equally are ordered byte-wise, so that
+    /// distinct names never compare equal.
+    pub fn compare(&self, a: &OsStr, b: &OsStr) -> Ordering {
+        self.collator
+            .compare(&a.to_string_lossy(), &b.to_string_lossy())
+            .then_with(|| a.cmp(b))
+    }
+}
+
+impl fmt::Debug for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Collation")
+    }
+}
+
+/// An error constructing a `Collation`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CollationError {
+    msg: String,
+}
+
+impl Error for CollationError {
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        &self.msg
+    }
+}
+
+impl fmt::Display for CollationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.msg.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Collation;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_collation() {
+        let collation = Collation::new("en").unwrap();
+        let mut names = vec!["zoo", "été", "Zebra", "apple", "etc", "Apple"];
+        names.sort_by(|a, b| collation.compare(OsStr::new(a), OsStr::new(b)));
+        assert_eq!(names, vec!["apple", "Apple", "etc", "été", "Zebra", "zoo"]);
+
+        assert!(Collation::new("not a locale").is_err());
+    }
+}
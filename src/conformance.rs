@@ -0,0 +1,216 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Comparison of this crate against the platform's libc.
+//!
+//! This module is only available on Unix with the `conformance` feature. It
+//! runs patterns through libc's `fnmatch(3)` and `glob(3)` as well as through
+//! this crate and reports where the two disagree, to quantify how closely
+//! this crate can stand in for libc on a given platform.
+//!
+//! libc is always called with `FNM_NOESCAPE`/`GLOB_NOESCAPE`, since patterns
+//! here have no backslash escapes. Differences in syntax that libc doesn't
+//! share, such as `**`, are reported like any other divergence.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use glob::conformance::check_match;
+//! use glob::MatchOptions;
+//!
+//! let options = MatchOptions {
+//!     require_literal_separator: true,
+//!     ..MatchOptions::new()
+//! };
+//! assert_eq!(check_match("*.rs", "lib.rs", options), None);
+//! // `**` is an extension of this crate
+//! assert!(check_match("a/**/b", "a/b", options).is_some());
+//! ```
+
+use libc;
+
+use std::ffi::{CStr, CString, OsStr};
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+use {glob_with, MatchOptions, Pattern};
+
+/// A case where libc and this crate disagree.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Divergence {
+    /// `fnmatch(3)` and `Pattern::matches_with` disagree on whether `input`
+    /// matches `pattern`. `libc` and `glob` hold their respective answers,
+    /// with `glob` being `None` if the pattern is invalid here.
+    Match {
+        /// The pattern that was checked.
+        pattern: String,
+        /// The string it was matched against.
+        input: String,
+        /// Whether `fnmatch(3)` reported a match.
+        libc: bool,
+        /// Whether this crate reported a match.
+        glob: Option<bool>,
+    },
+
+    /// `glob(3)` and `glob_with` found different paths for `pattern`. Both
+    /// lists of paths are sorted, and `glob` is `None` if the pattern is
+    /// invalid here.
+    Paths {
+        /// The pattern that was checked.
+        pattern: String,
+        /// The paths found by `glob(3)`.
+        libc: Vec<PathBuf>,
+        /// The paths found by this crate, ignoring errors.
+        glob: Option<Vec<PathBuf>>,
+    },
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// Matches `input` against `pattern` with both `fnmatch(3)` and
+/// `Pattern::matches_with`, returning how they differ, if at all.
+///
+/// `case_sensitive`, `require_literal_separator` and
+/// `require_literal_leading_dot` are translated to `FNM_CASEFOLD`,
+/// `FNM_PATHNAME` and `FNM_PERIOD` respectively; other options only affect
+/// this crate. Strings containing NUL can't be passed to libc and are never
+/// reported.
+pub fn check_match(pattern: &str, input: &str, options: MatchOptions) -> Option<Divergence> {
+    let (c_pattern, c_input) = match (CString::new(pattern), CString::new(input)) {
+        (Ok(p), Ok(i)) => (p, i),
+        _ => return None,
+    };
+
+    let mut flags = libc::FNM_NOESCAPE;
+    if !options.case_sensitive {
+        flags |= libc::FNM_CASEFOLD;
+    }
+    if options.require_literal_separator {
+        flags |= libc::FNM_PATHNAME;
+    }
+    if options.require_literal_leading_dot {
+        flags |= libc::FNM_PERIOD;
+    }
+    let libc_matches = unsafe { libc::fnmatch(c_pattern.as_ptr(), c_input.as_ptr(), flags) == 0 };
+
+    let glob_matches = Pattern::new(pattern)
+        .ok()
+        .map(|p| p.matches_with(input, options));
+    if glob_matches == Some(libc_matches) {
+        return None;
+    }
+    Some(Divergence::Match {
+        pattern: pattern.to_string(),
+        input: input.to_string(),
+        libc: libc_matches,
+        glob: glob_matches,
+    })
+}
+
+/// Runs `check_match` for every pair of pattern and input, returning all of
+/// the divergences found.
+pub fn check_matches(patterns: &[&str], inputs: &[&str], options: MatchOptions) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    for pattern in patterns {
+        for input in inputs {
+            divergences.extend(check_match(pattern, input, options));
+        }
+    }
+    divergences
+}
+
+/// Searches the filesystem for `pattern` with both `glob(3)` and
+/// `glob_with`, returning how the results differ, if at all.
+///
+/// `glob(3)` takes no options, and always behaves as if
+/// `require_literal_separator` and `require_literal_leading_dot` were set
+/// and `case_sensitive` wasn't cleared, so `options` should usually be chosen
+/// to match. Patterns containing NUL can't be passed to libc and are never
+/// reported.
+pub fn check_glob(pattern: &str, options: MatchOptions) -> Option<Divergence> {
+    let c_pattern = match CString::new(pattern) {
+        Ok(p) => p,
+        Err(_) => return None,
+    };
+
+    let mut libc_paths = Vec::new();
+    unsafe {
+        let mut results: libc::glob_t = mem::zeroed();
+        if libc::glob(c_pattern.as_ptr(), libc::GLOB_NOESCAPE, None, &mut results) == 0 {
+            for i in 0..results.gl_pathc as usize {
+                let path = *results.gl_pathv.offset(i as isize);
+                if !path.is_null() {
+                    let bytes = CStr::from_ptr(path).to_bytes();
+                    libc_paths.push(PathBuf::from(OsStr::from_bytes(bytes)));
+                }
+            }
+        }
+        libc::globfree(&mut results);
+    }
+    libc_paths.sort();
+
+    let glob_paths = glob_with(pattern, options).ok().map(|paths| {
+        let mut paths: Vec<_> = paths.filter_map(Result::ok).collect();
+        paths.sort();
+        paths
+    });
+    if glob_paths.as_ref() == Some(&libc_paths) {
+        return None;
+    }
+    Some(Divergence::Paths {
+        pattern: pattern.to_string(),
+        libc: libc_paths,
+        glob: glob_paths,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_glob, check_match, check_matches, Divergence};
+    use MatchOptions;
+
+    #[test]
+    fn test_check_match() {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            require_literal_leading_dot: true,
+            ..MatchOptions::new()
+        };
+        assert_eq!(check_match("a*[!x]?", "abcyz", options), None);
+        assert_eq!(check_match("*", ".hidden", options), None);
+        assert_eq!(check_match("a\u{0}", "a", options), None);
+        assert_eq!(
+            check_match("a/**/b", "a/b", options),
+            Some(Divergence::Match {
+                pattern: "a/**/b".to_string(),
+                input: "a/b".to_string(),
+                libc: false,
+                glob: Some(true),
+            })
+        );
+        assert_eq!(
+            check_matches(&["*.rs", "[a-c]*"], &["lib.rs", "build.rs", "x"], options),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_check_glob() {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            require_literal_leading_dot: true,
+            ..MatchOptions::new()
+        };
+        assert_eq!(check_glob("src/*.rs", options), None);
+        assert_eq!(check_glob("no-such-dir/*", options), None);
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path;
+
+use nfa::{accepts, initial, step};
+use CharSpecifier::{CharRange, SingleChar};
+use PatternToken::{AnyExcept, AnyWithin, Char};
+use {MatchOptions, Pattern};
+
+// The search gives up after visiting this many states, which is far more
+// than any realistic pair of patterns needs.
+const MAX_STATES: usize = 10_000;
+
+// A point in the search: the tokens of each pattern that may come next, by
+// alternative if it has braces, and whether the last character was a
+// separator.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    pattern: Vec<Vec<usize>>,
+    other: Vec<Vec<usize>>,
+    follows_separator: bool,
+}
+
+fn initial_all(patterns: &[&Pattern], options: MatchOptions) -> Vec<Vec<usize>> {
+    patterns.iter().map(|p| initial(p, options)).collect()
+}
+
+fn accepts_any(patterns: &[&Pattern], states: &[Vec<usize>], options: MatchOptions) -> bool {
+    patterns
+        .iter()
+        .zip(states)
+        .any(|(p, s)| accepts(p, s, options))
+}
+
+fn step_all(
+    patterns: &[&Pattern],
+    states: &[Vec<usize>],
+    c: char,
+    follows_separator: bool,
+    options: MatchOptions,
+) -> Vec<Vec<usize>> {
+    patterns
+        .iter()
+        .zip(states)
+        .map(|(p, s)| step(p, s, c, follows_separator, options))
+        .collect()
+}
+
+// Strings matched by `pattern` but not `other`, found by a breadth-first
+// search over both patterns at once, each treated as an NFA (see the `nfa`
+// module). The characters tried are those the patterns mention plus a few
+// neighbours, which covers every way a token can accept or reject a
+// character. Candidates are confirmed with `matches_with`, so an
+// approximation here can only lose examples, never produce wrong ones.
+pub(crate) fn examples(
+    pattern: &Pattern,
+    other: &Pattern,
+    options: MatchOptions,
+    limit: usize,
+) -> Vec<String> {
+    let patterns = pattern.branches();
+    let others = other.branches();
+    let mut branches = patterns.clone();
+    branches.extend(others.iter().cloned());
+    let alphabet = alphabet(&branches, options);
+    let start = State {
+        pattern: initial_all(&patterns, options),
+        other: initial_all(&others, options),
+        follows_separator: true,
+    };
+
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start.clone());
+    queue.push_back((start, String::new()));
+    while let Some((state, s)) = queue.pop_front() {
+        if found.len() >= limit {
+            break;
+        }
+        if accepts_any(&patterns, &state.pattern, options)
+            && !accepts_any(&others, &state.other, options)
+            && pattern.matches_with(&s, options)
+            && !other.matches_with(&s, options)
+        {
+            found.push(s.clone());
+        }
+
+        for &c in &alphabet {
+            if seen.len() >= MAX_STATES {
+                break;
+            }
+            let next = State {
+                pattern: step_all(
+                    &patterns,
+                    &state.pattern,
+                    c,
+                    state.follows_separator,
+                    options,
+                ),
+                other: step_all(&others, &state.other, c, state.follows_separator, options),
+                follows_separator: path::is_separator(c),
+            };
+            // nothing that starts this way can match `pattern`
+            if next.pattern.iter().all(|states| states.is_empty()) {
+                continue;
+            }
+            if seen.insert(next.clone()) {
+                let mut s = s.clone();
+                s.push(c);
+                queue.push_back((next, s));
+            }
+        }
+    }
+    found
+}
+
+// The characters worth trying: everything the patterns mention, the
+// characters just outside each range, and one the patterns don't mention.
+fn alphabet(patterns: &[&Pattern], options: MatchOptions) -> Vec<char> {
+    let mut chars = BTreeSet::new();
+    chars.insert('.');
+    chars.insert(path::MAIN_SEPARATOR);
+    for pattern in patterns {
+        for token in &pattern.tokens {
+            match *token {
+                Char(c) => {
+                    chars.insert(c);
+                }
+                AnyWithin(ref specifiers) | AnyExcept(ref specifiers) => {
+                    for specifier in specifiers {
+                        match *specifier {
+                            SingleChar(c) => {
+                                chars.insert(c);
+                            }
+                            CharRange(start, end) => {
+                                chars.insert(start);
+                                chars.insert(end);
+                                chars.extend(::std::char::from_u32(start as u32 - 1));
+                                chars.extend(::std::char::from_u32(end as u32 + 1));
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    if !options.case_sensitive {
+        let letters: Vec<char> = chars.iter().cloned().filter(|c| c.is_ascii()).collect();
+        for c in letters {
+            chars.insert(c.to_ascii_lowercase());
+            chars.insert(c.to_ascii_uppercase());
+        }
+    }
+    let unmentioned = (b'a'..b'z' + 1)
+        .chain(b'0'..b'9' + 1)
+        .map(|b| b as char)
+        .find(|c| !chars.contains(c) && !chars.contains(&c.to_ascii_uppercase()));
+    chars.extend(unmentioned);
+    chars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use {MatchOptions, ParseOptions, Pattern};
+
+    fn difference(a: &str, b: &str, options: MatchOptions) -> Vec<String> {
+        let a = Pattern::new(a).unwrap();
+        let b = Pattern::new(b).unwrap();
+        let examples = a.difference(&b, options, 5);
+        for example in &examples {
+            assert!(a.matches_with(example, options));
+            assert!(!b.matches_with(example, options));
+        }
+        examples
+    }
+
+    #[test]
+    fn test_difference() {
+        let options = MatchOptions::new();
+        assert!(difference("*.log", "*.lo?", options).is_empty());
+        assert!(difference("a[bc]", "a[b-c]", options).is_empty());
+        assert!(difference("*", "*", options).is_empty());
+        assert_eq!(difference("a", "b", options), vec!["a"]);
+        assert_eq!(difference("*.lo?", "*.log", options)[0].len(), 4);
+        assert_eq!(difference("[a-c]", "[!b]", options), vec!["b"]);
+        assert_eq!(difference("?", "[!x]", options), vec!["x"]);
+        assert_eq!(difference("*.rs", "*.rs", options).len(), 0);
+        assert_eq!(difference("**/*.rs", "*.rs", options).len(), 0);
+
+        let literal_separator = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let examples = difference("**/*.rs", "*.rs", literal_separator);
+        assert!(!examples.is_empty());
+        assert!(examples.iter().all(|e| e.contains('/')));
+
+        let literal_dot = MatchOptions {
+            require_literal_leading_dot: true,
+            ..MatchOptions::new()
+        };
+        assert_eq!(difference("*", "?*", literal_dot), vec![""]);
+        assert_eq!(difference(".*", "*", literal_dot)[0], ".");
+
+        let case_insensitive = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        assert!(difference("A", "a", case_insensitive).is_empty());
+        assert_eq!(difference("A", "a", options), vec!["A"]);
+
+        let braces = ParseOptions::new().braces(true);
+        let a = Pattern::new_with("*.{rs,toml}", braces).unwrap();
+        let b = Pattern::new_with("{lib,main}.rs", braces).unwrap();
+        assert_eq!(a.difference(&b, options, 5), vec![".rs", ".toml"]);
+        assert!(b.difference(&a, options, 5).is_empty());
+        assert!(a.difference(&Pattern::new("*.rs").unwrap(), options, 5)[0].ends_with(".toml"));
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::char;
+use std::collections::BTreeSet;
+
+use CharSpecifier::{CharRange, SingleChar};
+use PatternToken::{AnyChar, AnyExcept, AnyRecursiveSequence, AnySequence, AnyWithin, Char};
+use {in_char_specifiers, MatchOptions, Pattern};
+
+/// An iterator over every string a `Pattern` matches, in lexicographic
+/// order.
+///
+/// Created by `Pattern::enumerate`.
+#[derive(Clone, Debug)]
+pub struct Expansions {
+    // the strings matched by each alternative of a pattern with braces, or
+    // else by the pattern itself
+    products: Vec<Product>,
+    // the next string of each of `products`, which are merged
+    heads: Vec<Option<String>>,
+}
+
+// The strings matched by a pattern without braces.
+#[derive(Clone, Debug)]
+struct Product {
+    // the characters that can appear at each position, sorted
+    choices: Vec<Vec<char>>,
+    // the position within `choices` of the next string, or `None` once every
+    // string has been yielded
+    indices: Option<Vec<usize>>,
+}
+
+impl Expansions {
+    pub(crate) fn new(pattern: &Pattern, alphabet: &[char]) -> Option<Self> {
+        let mut products = pattern
+            .branches()
+            .into_iter()
+            .map(|p| Product::new(p, alphabet))
+            .collect::<Option<Vec<_>>>()?;
+        let heads = products.iter_mut().map(|p| p.next()).collect();
+        Some(Self { products, heads })
+    }
+}
+
+impl Iterator for Expansions {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let mut min: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            if let Some(ref s) = *head {
+                if min.map_or(true, |m| s < self.heads[m].as_ref().unwrap()) {
+                    min = Some(i);
+                }
+            }
+        }
+        let s = self.heads[min?].take().unwrap();
+
+        // alternatives may match the same string, which is only yielded once
+        for (head, product) in self.heads.iter_mut().zip(&mut self.products) {
+            if head.is_none() || head.as_ref() == Some(&s) {
+                *head = product.next();
+            }
+        }
+        Some(s)
+    }
+}
+
+impl Product {
+    fn new(pattern: &Pattern, alphabet: &[char]) -> Option<Self> {
+        let mut choices = Vec::new();
+        for token in &pattern.tokens {
+            let chars: BTreeSet<char> = match *token {
+                AnySequence | AnyRecursiveSequence => return None,
+                Char(c) => Some(c).into_iter().collect(),
+                AnyChar => alphabet.iter().cloned().collect(),
+                AnyWithin(ref specifiers) => specifiers
+                    .iter()
+                    .flat_map(|specifier| {
+                        let (start, end) = match *specifier {
+                            SingleChar(c) => (c, c),
+                            CharRange(start, end) => (start, end),
+                        };
+                        (start as u32..end as u32 + 1).filter_map(char::from_u32)
+                    })
+                    .collect(),
+                AnyExcept(ref specifiers) => alphabet
+                    .iter()
+                    .cloned()
+                    .filter(|&c| !in_char_specifiers(specifiers, c, MatchOptions::new()))
+                    .collect(),
+            };
+            choices.push(chars.into_iter().collect());
+        }
+
+        let indices = if choices.iter().any(|chars: &Vec<char>| chars.is_empty()) {
+            None
+        } else {
+            Some(vec![0; choices.len()])
+        };
+        Some(Self { choices, indices })
+    }
+}
+
+impl Iterator for Product {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let s = {
+            let indices = self.indices.as_ref()?;
+            indices
+                .iter()
+                .zip(&self.choices)
+                .map(|(&i, chars)| chars[i])
+                .collect()
+        };
+
+        // advance to the next string, rightmost position first
+        let mut exhausted = true;
+        if let Some(ref mut indices) = self.indices {
+            for (i, chars) in indices.iter_mut().zip(&self.choices).rev() {
+                *i += 1;
+                if *i < chars.len() {
+                    exhausted = false;
+                    break;
+                }
+                *i = 0;
+            }
+        }
+        if exhausted {
+            self.indices = None;
+        }
+        Some(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {ParseOptions, Pattern};
+
+    fn enumerate(pattern: &str, alphabet: &str) -> Option<Vec<String>> {
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        let pattern = Pattern::new(pattern).unwrap();
+        let expansions: Vec<String> = pattern.enumerate(&alphabet)?.collect();
+        for s in &expansions {
+            assert!(pattern.matches(s), "{} doesn't match {}", pattern, s);
+        }
+        Some(expansions)
+    }
+
+    #[test]
+    fn test_enumerate() {
+        assert_eq!(enumerate("abc", ""), Some(vec!["abc".to_string()]));
+        assert_eq!(enumerate("", ""), Some(vec!["".to_string()]));
+        assert_eq!(
+            enumerate("a[b-c]?", "xy").unwrap(),
+            vec!["abx", "aby", "acx", "acy"]
+        );
+        assert_eq!(enumerate("[!a]", "abc").unwrap(), vec!["b", "c"]);
+        assert_eq!(enumerate("[ba]", "").unwrap(), vec!["a", "b"]);
+        assert_eq!(enumerate("a?", "").unwrap(), Vec::<String>::new());
+        assert_eq!(enumerate("[z-a]", "").unwrap(), Vec::<String>::new());
+        assert_eq!(enumerate("[[]*", "abc"), None);
+        assert_eq!(enumerate("**/a", "abc"), None);
+        assert_eq!(enumerate("[0-9][0-9]", "").unwrap().len(), 100);
+
+        let braces = ParseOptions::new().braces(true);
+        let pattern = Pattern::new_with("{b,a?,a[bc]}", braces).unwrap();
+        let expansions: Vec<String> = pattern.enumerate(&['b', 'x']).unwrap().collect();
+        assert_eq!(expansions, vec!["ab", "ac", "ax", "b"]);
+        let pattern = Pattern::new_with("{a,*}", braces).unwrap();
+        assert!(pattern.enumerate(&[]).is_none());
+    }
+}
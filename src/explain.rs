@@ -0,0 +1,214 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use nfa::{accepts, initial, step};
+use rewrite::to_string;
+use token::{from_pattern_token, to_pattern_token};
+use {MatchOptions, Pattern, Token};
+
+/// Why a string doesn't match a `Pattern`, as returned by
+/// `Pattern::explain`.
+///
+/// Its `Display` implementation describes the failure in a sentence, like
+/// ``"`x` at byte 4 doesn't match `[a-c]`"``.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MatchFailure {
+    /// What went wrong.
+    pub kind: MatchFailureKind,
+
+    /// The byte offset in the string at which matching failed, which is its
+    /// length if it ended too soon.
+    pub pos: usize,
+
+    /// The character at `pos`, if the string didn't end there.
+    pub found: Option<char>,
+
+    /// The tokens of the pattern that could have come next at `pos`, in the
+    /// order they appear in the pattern. Since `*` may match nothing, the
+    /// tokens after one are listed too.
+    pub expected: Vec<Token>,
+}
+
+/// The kinds of `MatchFailure`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MatchFailureKind {
+    /// The character at `pos` isn't matched by any of the `expected` tokens,
+    /// e.g. because of `MatchOptions::require_literal_leading_dot`.
+    UnexpectedChar,
+
+    /// The string ended before the pattern did.
+    UnexpectedEnd,
+
+    /// The string would match, but the pattern only matches directories,
+    /// which have to end with a separator, see `Pattern::is_dir_only`.
+    NotDirectory,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl fmt::Display for MatchFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let expected: Vec<String> = self
+            .expected
+            .iter()
+            .map(|token| format!("`{}`", to_string(&[to_pattern_token(token)], false)))
+            .collect();
+        match (self.kind, self.found) {
+            (MatchFailureKind::UnexpectedChar, Some(c)) => write!(
+                f,
+                "`{}` at byte {} doesn't match {}",
+                c,
+                self.pos,
+                expected.join(" or ")
+            ),
+            (MatchFailureKind::UnexpectedEnd, _) => write!(
+                f,
+                "the string ends at byte {} but the pattern expects {}",
+                self.pos,
+                expected.join(" or ")
+            ),
+            (MatchFailureKind::NotDirectory, _) => {
+                write!(
+                    f,
+                    "the pattern only matches directories, ending in a separator"
+                )
+            }
+            _ => write!(f, "the string doesn't match at byte {}", self.pos),
+        }
+    }
+}
+
+// Return why `str` doesn't match `pattern`, if it doesn't, see
+// `Pattern::explain`. The alternatives of a pattern with braces are simulated
+// side by side as in `nfa::matches`, so the expected tokens may come from
+// any of them.
+pub(crate) fn explain(pattern: &Pattern, str: &str, options: MatchOptions) -> Option<MatchFailure> {
+    let patterns = pattern.branches();
+    let mut states: Vec<Vec<usize>> = patterns.iter().map(|p| initial(p, options)).collect();
+    let mut follows_separator = true;
+    for (pos, c) in str.char_indices() {
+        let next: Vec<Vec<usize>> = patterns
+            .iter()
+            .zip(&states)
+            .map(|(p, s)| step(p, s, c, follows_separator, options))
+            .collect();
+        if next.iter().all(|states| states.is_empty()) {
+            return Some(MatchFailure {
+                kind: MatchFailureKind::UnexpectedChar,
+                pos,
+                found: Some(c),
+                expected: expected(&patterns, &states),
+            });
+        }
+        states = next;
+        follows_separator = options.is_separator(c);
+    }
+
+    let mut accepted = false;
+    for (pattern, states) in patterns.iter().zip(&states) {
+        if accepts(pattern, states, options) {
+            if follows_separator || !pattern.is_dir_only() {
+                return None;
+            }
+            accepted = true;
+        }
+    }
+    Some(MatchFailure {
+        kind: if accepted {
+            MatchFailureKind::NotDirectory
+        } else {
+            MatchFailureKind::UnexpectedEnd
+        },
+        pos: str.len(),
+        found: None,
+        expected: expected(&patterns, &states),
+    })
+}
+
+// The tokens at `states`, without duplicates.
+fn expected(patterns: &[&Pattern], states: &[Vec<usize>]) -> Vec<Token> {
+    let mut expected = Vec::new();
+    for (pattern, states) in patterns.iter().zip(states) {
+        for &i in states {
+            if let Some(token) = pattern.tokens.get(i) {
+                let token = from_pattern_token(token);
+                if !expected.contains(&token) {
+                    expected.push(token);
+                }
+            }
+        }
+    }
+    expected
+}
+
+#[cfg(test)]
+mod test {
+    use super::MatchFailureKind;
+    use {ClassMember, MatchOptions, ParseOptions, Pattern, Token};
+
+    #[test]
+    fn test_explain() {
+        let options = MatchOptions::new();
+        let explain = |p: &str, s: &str| Pattern::new(p).unwrap().explain(s, options);
+        assert_eq!(explain("a?[b-c]*", "axbyz"), None);
+
+        let failure = explain("a/[!x-z]?", "a/x").unwrap();
+        assert_eq!(failure.kind, MatchFailureKind::UnexpectedChar);
+        assert_eq!(failure.pos, 2);
+        assert_eq!(failure.found, Some('x'));
+        assert_eq!(
+            failure.expected,
+            vec![Token::AnyExcept(vec![ClassMember::Range('x', 'z')])]
+        );
+        assert_eq!(failure.to_string(), "`x` at byte 2 doesn't match `[!x-z]`");
+
+        let failure = explain("*.rs", "a.r").unwrap();
+        assert_eq!(failure.kind, MatchFailureKind::UnexpectedEnd);
+        assert_eq!(failure.pos, 3);
+        assert_eq!(failure.found, None);
+        assert_eq!(
+            failure.expected,
+            vec![Token::AnySequence, Token::Char('.'), Token::Char('s')]
+        );
+        assert_eq!(
+            failure.to_string(),
+            "the string ends at byte 3 but the pattern expects `*` or `.` or `s`"
+        );
+
+        let failure = explain("é?b", "éxc").unwrap();
+        assert_eq!(failure.pos, 3);
+        assert_eq!(failure.found, Some('c'));
+
+        let failure = explain("a/**/", "a/b").unwrap();
+        assert_eq!(failure.kind, MatchFailureKind::NotDirectory);
+        assert_eq!(explain("a/**/", "a/b/"), None);
+
+        let literal_dot = MatchOptions {
+            require_literal_leading_dot: true,
+            ..MatchOptions::new()
+        };
+        let failure = Pattern::new("*")
+            .unwrap()
+            .explain(".git", literal_dot)
+            .unwrap();
+        assert_eq!(failure.pos, 0);
+        assert_eq!(failure.expected, vec![Token::AnySequence]);
+
+        let braces = ParseOptions::new().braces(true);
+        let pattern = Pattern::new_with("{src,lib}/*.rs", braces).unwrap();
+        assert_eq!(pattern.explain("lib/a.rs", options), None);
+        let failure = pattern.explain("bin/a.rs", options).unwrap();
+        assert_eq!(failure.pos, 0);
+        assert_eq!(failure.expected, vec![Token::Char('s'), Token::Char('l')]);
+    }
+}
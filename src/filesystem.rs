@@ -0,0 +1,165 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The type of a filesystem entry, as far as globbing is concerned.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum FileKind {
+    /// A regular file, or any other entry that is neither a directory nor a
+    /// symbolic link.
+    File,
+
+    /// A directory.
+    Dir,
+
+    /// A symbolic link.
+    Symlink,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl FileKind {
+    fn from_file_type(file_type: fs::FileType) -> Self {
+        if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else {
+            FileKind::File
+        }
+    }
+}
+
+/// The filesystem operations needed to search for matches.
+///
+/// By default `Paths` queries the real filesystem through `StdFileSystem`,
+/// but a different implementation can be passed to
+/// `GlobBuilder::filesystem`, e.g. to glob over an archive or, with the
+/// `testing` feature, over an in-memory fixture.
+pub trait FileSystem: fmt::Debug + Send + Sync {
+    /// Queries the kind of entry at `path`, following symbolic links. This
+    /// never returns `FileKind::Symlink`.
+    fn metadata(&self, path: &Path) -> io::Result<FileKind>;
+
+    /// Queries the kind of entry at `path` without following a symbolic link
+    /// at the end of it.
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FileKind>;
+
+    /// Lists the names of the entries of the directory at `path`, in any
+    /// order, along with their kind as `symlink_metadata` would report it.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(OsString, FileKind)>>;
+
+    /// Returns the absolute form of `path` with all symbolic links resolved.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Returns the target of the symbolic link at `path`, as stored in the
+    /// link.
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Returns whether `a` and `b` are the same entry once symbolic links are
+    /// followed, such as a directory and a link to it.
+    ///
+    /// This is used to detect cycles of symbolic links, see
+    /// `GlobBuilder::detect_link_cycles`. The default implementation compares
+    /// the results of `canonicalize`.
+    fn is_same_file(&self, a: &Path, b: &Path) -> io::Result<bool> {
+        Ok(self.canonicalize(a)? == self.canonicalize(b)?)
+    }
+
+    /// Returns the size in bytes and the last modification time of the entry
+    /// at `path`, following symbolic links.
+    ///
+    /// This is only used by the size and modification time filters of
+    /// `GlobBuilder` and by `Paths::manifest`. The default implementation
+    /// returns an error.
+    fn size_and_modified(&self, path: &Path) -> io::Result<(u64, SystemTime)> {
+        let _ = path;
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "size and modification time are not supported",
+        ))
+    }
+
+    /// Reads the file at `path` as UTF-8 text.
+    ///
+    /// This is only used to read ignore files, see
+    /// `GlobBuilder::ignore_files`. The default implementation returns an
+    /// error, which counts as the file not existing.
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let _ = path;
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "reading files is not supported",
+        ))
+    }
+}
+
+/// The real filesystem, accessed through `std::fs`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<FileKind> {
+        fs::metadata(path).map(|m| FileKind::from_file_type(m.file_type()))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FileKind> {
+        fs::symlink_metadata(path).map(|m| FileKind::from_file_type(m.file_type()))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(OsString, FileKind)>> {
+        let mut entries = Vec::new();
+        for e in fs::read_dir(path)? {
+            let e = e?;
+            let kind = match e.file_type() {
+                Ok(file_type) => FileKind::from_file_type(file_type),
+                Err(_) => self.symlink_metadata(&e.path())?,
+            };
+            entries.push((e.file_name(), kind));
+        }
+        Ok(entries)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let mut contents = String::new();
+        fs::File::open(path)?.read_to_string(&mut contents)?;
+        Ok(contents)
+    }
+
+    // compares device and inode numbers, which is cheaper than resolving
+    // both paths and can't be fooled by a path that changes meanwhile
+    #[cfg(unix)]
+    fn is_same_file(&self, a: &Path, b: &Path) -> io::Result<bool> {
+        use std::os::unix::fs::MetadataExt;
+
+        let (a, b) = (fs::metadata(a)?, fs::metadata(b)?);
+        Ok(a.dev() == b.dev() && a.ino() == b.ino())
+    }
+
+    fn size_and_modified(&self, path: &Path) -> io::Result<(u64, SystemTime)> {
+        let metadata = fs::metadata(path)?;
+        Ok((metadata.len(), metadata.modified()?))
+    }
+}
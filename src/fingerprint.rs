@@ -0,0 +1,174 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use {path_bytes, GlobResult, Paths};
+
+// FNV-1a, which is simple and, unlike `std`'s hashers, guaranteed to produce
+// the same result on every platform and in every release.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A digest of the paths yielded by a glob iterator along with the size and
+/// modification time of each of them.
+///
+/// Fingerprints are stable across runs, platforms and versions of this
+/// crate, so they can be persisted and compared later to tell whether the
+/// set of matching files, or any of the files themselves, changed. They are
+/// not cryptographically secure.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Fingerprint(u64);
+
+impl Fingerprint {
+    /// The digest as an integer.
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// An iterator that yields the same results as a `Paths` iterator, while
+/// computing a `Fingerprint` of the matches.
+///
+/// Matches are hashed in the order they're yielded, which is deterministic.
+/// Errors are passed through without affecting the fingerprint, and a match
+/// whose metadata can't be read contributes only its path.
+///
+/// Created by `Paths::fingerprinted`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::glob;
+///
+/// let mut paths = glob("src/**/*.rs").unwrap().fingerprinted();
+/// for entry in &mut paths {
+///     println!("{:?}", entry);
+/// }
+/// println!("fingerprint: {}", paths.fingerprint());
+/// ```
+#[derive(Debug)]
+pub struct FingerprintedPaths {
+    paths: Paths,
+    state: u64,
+}
+
+impl FingerprintedPaths {
+    pub(crate) fn new(paths: Paths) -> Self {
+        Self {
+            paths,
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// The fingerprint of the matches yielded so far.
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint(self.state)
+    }
+
+    /// Consumes the rest of the iterator and returns the fingerprint of all
+    /// of its matches.
+    pub fn finish(mut self) -> Fingerprint {
+        while self.next().is_some() {}
+        self.fingerprint()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.state ^= u64::from(b);
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        let mut bytes = [0; 8];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = (n >> (i * 8)) as u8;
+        }
+        self.write(&bytes);
+    }
+
+    fn write_path(&mut self, path: &Path) {
+        let bytes = path_bytes(path);
+        // prefix with the length so that consecutive records can't be
+        // confused with one another
+        self.write_u64(bytes.len() as u64);
+        self.write(&bytes);
+    }
+
+    fn add(&mut self, path: &Path) {
+        self.write_path(path);
+        match fs::metadata(path) {
+            Ok(metadata) => {
+                self.write(&[1]);
+                self.write_u64(metadata.len());
+                let mtime = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+                match mtime {
+                    Some(mtime) => {
+                        self.write(&[1]);
+                        self.write_u64(mtime.as_secs());
+                        self.write_u64(u64::from(mtime.subsec_nanos()));
+                    }
+                    None => self.write(&[0]),
+                }
+            }
+            Err(_) => self.write(&[0]),
+        }
+    }
+}
+
+impl Iterator for FingerprintedPaths {
+    type Item = GlobResult;
+
+    fn next(&mut self) -> Option<GlobResult> {
+        let result = self.paths.next();
+        if let Some(Ok(ref path)) = result {
+            self.add(path);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Fingerprint, FingerprintedPaths};
+    use glob;
+    use std::path::Path;
+
+    #[test]
+    fn test_fnv() {
+        // reference values of FNV-1a 64
+        let mut paths = FingerprintedPaths::new(glob("").unwrap());
+        assert_eq!(paths.fingerprint(), Fingerprint(0xcbf2_9ce4_8422_2325));
+        paths.write(b"a");
+        assert_eq!(paths.fingerprint().as_u64(), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(paths.fingerprint().to_string(), "af63dc4c8601ec8c");
+
+        let mut a = FingerprintedPaths::new(glob("").unwrap());
+        a.write_path(Path::new("ab"));
+        a.write_path(Path::new("c"));
+        let mut b = FingerprintedPaths::new(glob("").unwrap());
+        b.write_path(Path::new("a"));
+        b.write_path(Path::new("bc"));
+        assert!(a.fingerprint() != b.fingerprint());
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::path::Path;
+
+use PatternToken::Char;
+use {Candidate, MatchOptions, Pattern, PatternError};
+
+/// A collection of patterns that can be matched against a path all at once.
+///
+/// Matching a path against a `GlobSet` gives the same result as matching it
+/// against each of its patterns in turn, but patterns that can't match are
+/// mostly skipped without being tried: those that are a literal path, or
+/// that end in a literal extension like `*.rs` or `**/*.tar.gz`, are looked
+/// up by the path or its extension. The remaining patterns are only tried on
+/// paths that end with their literal suffix, if they have one.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::GlobSet;
+///
+/// let set = GlobSet::new(&["**/*.rs", "Cargo.toml", "src/**"]).unwrap();
+/// assert!(set.matches("src/lib.rs"));
+/// assert_eq!(set.matching("src/lib.rs"), vec![0, 2]);
+/// assert_eq!(set.matching("Cargo.toml"), vec![1]);
+/// assert!(!set.matches("README.md"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GlobSet {
+    patterns: Vec<Pattern>,
+    // the patterns that are a literal path, by that path
+    literals: HashMap<String, Vec<usize>>,
+    // the patterns ending in a literal extension, by that extension
+    extensions: HashMap<String, Vec<usize>>,
+    // the rest, along with the literal suffix of each
+    others: Vec<(usize, String)>,
+}
+
+impl GlobSet {
+    /// Compiles the given patterns into a `GlobSet`. A pattern's index in
+    /// the set is its position in `patterns`.
+    ///
+    /// This may return an error if any of the patterns is invalid.
+    pub fn new<I>(patterns: I) -> Result<Self, PatternError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        patterns
+            .into_iter()
+            .map(|p| Pattern::new(p.as_ref()))
+            .collect()
+    }
+
+    /// The patterns of the set, in order.
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// The number of patterns in the set.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Return if the set has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    fn push(&mut self, pattern: Pattern) {
+        let i = self.patterns.len();
+        for branch in pattern.branches() {
+            let mut suffix = Vec::new();
+            for token in branch.tokens.iter().rev() {
+                match *token {
+                    Char(c) => suffix.push(c),
+                    _ => break,
+                }
+            }
+            let literal = suffix.len() == branch.tokens.len();
+            suffix.reverse();
+            let suffix = normalize(&suffix.into_iter().collect::<String>());
+
+            if literal && !branch.is_dir_only() {
+                push_index(&mut self.literals, suffix, i);
+            } else if let Some(dot) = suffix.rfind('.') {
+                let extension = suffix[dot + 1..].to_string();
+                push_index(&mut self.extensions, extension, i);
+            } else {
+                self.others.push((i, suffix));
+            }
+        }
+        self.patterns.push(pattern);
+    }
+
+    // Return the indices of the patterns that could match the string with the
+    // normalized form `key`, in order and without duplicates, without trying
+    // any of them.
+    fn candidates(&self, key: &str) -> Vec<usize> {
+        let mut candidates = Vec::new();
+        if let Some(indices) = self.literals.get(key) {
+            candidates.extend(indices);
+        }
+        if let Some(dot) = key.rfind('.') {
+            if let Some(indices) = self.extensions.get(&key[dot + 1..]) {
+                candidates.extend(indices);
+            }
+        }
+        candidates.extend(
+            self.others
+                .iter()
+                .filter(|other| key.ends_with(&*other.1))
+                .map(|other| other.0),
+        );
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Return if the given `str` matches any pattern of the set using the
+    /// default match options (i.e. `MatchOptions::new()`).
+    pub fn matches(&self, str: &str) -> bool {
+        self.matches_with(str, MatchOptions::new())
+    }
+
+    /// Return if the given `Path` matches any pattern of the set using the
+    /// default match options. Like `Pattern::matches_os`, each byte that
+    /// isn't part of valid UTF-8 is only matched by a wildcard.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        self.matches_path_with(path, MatchOptions::new())
+    }
+
+    /// Return if the given `str` matches any pattern of the set using the
+    /// specified match options.
+    pub fn matches_with(&self, str: &str, options: MatchOptions) -> bool {
+        self.matches_candidate(&Candidate::new(str), options)
+    }
+
+    /// Return if the given `Candidate` matches any pattern of the set using
+    /// the specified match options. This saves normalizing the path again
+    /// when it's matched against several sets.
+    pub fn matches_candidate(&self, candidate: &Candidate, options: MatchOptions) -> bool {
+        self.candidates(candidate.folded())
+            .into_iter()
+            .any(|i| self.patterns[i].matches_with(candidate.as_str(), options))
+    }
+
+    /// Return if the given `Path` matches any pattern of the set using the
+    /// specified match options, see `matches_path`.
+    pub fn matches_path_with(&self, path: &Path, options: MatchOptions) -> bool {
+        self.matches_candidate(&Candidate::from_path(path), options)
+    }
+
+    /// Return the indices of the patterns that the given `str` matches, in
+    /// ascending order, using the default match options.
+    pub fn matching(&self, str: &str) -> Vec<usize> {
+        self.matching_with(str, MatchOptions::new())
+    }
+
+    /// Return the indices of the patterns that the given `Path` matches,
+    /// using the default match options, see `matches_path`.
+    pub fn matching_path(&self, path: &Path) -> Vec<usize> {
+        self.matching_path_with(path, MatchOptions::new())
+    }
+
+    /// Return the indices of the patterns that the given `str` matches, in
+    /// ascending order, using the specified match options.
+    pub fn matching_with(&self, str: &str, options: MatchOptions) -> Vec<usize> {
+        self.matching_candidate(&Candidate::new(str), options)
+    }
+
+    /// Return the indices of the patterns that the given `Candidate` matches,
+    /// in ascending order, using the specified match options.
+    pub fn matching_candidate(&self, candidate: &Candidate, options: MatchOptions) -> Vec<usize> {
+        self.candidates(candidate.folded())
+            .into_iter()
+            .filter(|&i| self.patterns[i].matches_with(candidate.as_str(), options))
+            .collect()
+    }
+
+    /// Return the indices of the patterns that the given `Path` matches,
+    /// using the specified match options, see `matches_path`.
+    pub fn matching_path_with(&self, path: &Path, options: MatchOptions) -> Vec<usize> {
+        self.matching_candidate(&Candidate::from_path(path), options)
+    }
+}
+
+impl FromIterator<Pattern> for GlobSet {
+    fn from_iter<I: IntoIterator<Item = Pattern>>(patterns: I) -> Self {
+        let mut set = Self::default();
+        for pattern in patterns {
+            set.push(pattern);
+        }
+        set
+    }
+}
+
+impl Extend<Pattern> for GlobSet {
+    fn extend<I: IntoIterator<Item = Pattern>>(&mut self, patterns: I) {
+        for pattern in patterns {
+            self.push(pattern);
+        }
+    }
+}
+
+fn push_index(index: &mut HashMap<String, Vec<usize>>, key: String, i: usize) {
+    if let Some(indices) = index.get_mut(&key) {
+        if indices.last() != Some(&i) {
+            indices.push(i);
+        }
+        return;
+    }
+    index.insert(key, vec![i]);
+}
+
+// Keys are compared regardless of ASCII case and of the kind of slash, which
+// is the most that `MatchOptions` can make equal, so that looking them up
+// never misses a pattern that would match.
+pub(crate) fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\\' => '/',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::GlobSet;
+    use {Candidate, MatchOptions, ParseOptions, PathFlavor, Pattern};
+
+    #[test]
+    fn test_glob_set() {
+        let patterns = [
+            "",
+            "a",
+            "a/b",
+            "A.RS",
+            "*.rs",
+            "**/*.rs",
+            "*.tar.gz",
+            "src/**",
+            "a/**/",
+            "a*",
+            "*a",
+            "a?c",
+            "[a-c]*",
+            "*/*",
+            ".*",
+            "**",
+            "x.{rs,md}",
+            "{a,*b}",
+            "{*.c,*}",
+            "a\\b",
+            "*.",
+        ];
+        let strings = [
+            "", "a", "A", "a/b", "a\\b", "a.rs", "x/a.rs", "x\\A.RS", ".rs", "a.tar.gz", "b.gz",
+            "src/x", "a/", "a/x/", "abc", "x.md", "b", "xb", "y.c", ".a", "x.",
+        ];
+        let set = GlobSet::new(&patterns).unwrap();
+        assert_eq!(set.len(), patterns.len());
+        for &case_sensitive in &[false, true] {
+            for &flavor in &[PathFlavor::Unix, PathFlavor::Windows] {
+                for &literal_leading_dot in &[false, true] {
+                    let options = MatchOptions {
+                        case_sensitive,
+                        require_literal_leading_dot: literal_leading_dot,
+                        path_flavor: flavor,
+                        ..MatchOptions::new()
+                    };
+                    let parse_options = ParseOptions::new().braces(true);
+                    let patterns: Vec<Pattern> = patterns
+                        .iter()
+                        .map(|p| Pattern::new_with(p, parse_options).unwrap())
+                        .collect();
+                    let set: GlobSet = patterns.iter().cloned().collect();
+                    for s in &strings {
+                        let expected: Vec<usize> = (0..patterns.len())
+                            .filter(|&i| patterns[i].matches_with(s, options))
+                            .collect();
+                        assert_eq!(
+                            set.matching_with(s, options),
+                            expected,
+                            "{:?} {:?}",
+                            s,
+                            options
+                        );
+                        assert_eq!(set.matches_with(s, options), !expected.is_empty());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut set = GlobSet::default();
+        assert!(set.is_empty());
+        assert!(!set.matches("a.rs"));
+        set.extend(vec![Pattern::new("*.rs").unwrap()]);
+        assert_eq!(set.matching("a.rs"), vec![0]);
+        assert_eq!(set.patterns()[0].as_str(), "*.rs");
+    }
+
+    #[test]
+    fn test_matches_candidate() {
+        let set = GlobSet::new(&["*.RS", "src/**", "Cargo.toml"]).unwrap();
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        let candidate = Candidate::new("src/main.rs");
+        assert!(set.matches_candidate(&candidate, options));
+        assert_eq!(set.matching_candidate(&candidate, options), vec![0, 1]);
+        assert_eq!(
+            set.matching_candidate(&candidate, MatchOptions::new()),
+            vec![1]
+        );
+        assert!(!set.matches_candidate(&Candidate::new("README.md"), options));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_matches_path_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let set = GlobSet::new(&["src/*.rs", "?.md"]).unwrap();
+        let path = Path::new(OsStr::from_bytes(b"src/\xff.rs"));
+        assert!(set.matches_path(path));
+        assert_eq!(set.matching_path(path), vec![0]);
+        assert!(set.matches_path(Path::new(OsStr::from_bytes(b"\xff.md"))));
+        assert!(!set.matches_path(Path::new(OsStr::from_bytes(b"\xff\xfe.md"))));
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading `.gitignore` and `.ignore` files, see `GlobBuilder::ignore_files`.
+
+use std::path::{Component, Path};
+
+use {FileSystem, MatchOptions, Pattern};
+
+// The ignore files of a directory, with the rules of later ones taking
+// precedence.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+// The rules of the ignore files in one directory, which apply to everything
+// below it.
+#[derive(Debug, Default)]
+pub(crate) struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    // Reads the ignore files of `dir`, treating those that can't be read as
+    // empty.
+    pub(crate) fn load<F: ?Sized + FileSystem>(fs: &F, dir: &Path) -> Self {
+        let mut rules = Self::default();
+        for name in &IGNORE_FILES {
+            if let Ok(contents) = fs.read_to_string(&dir.join(name)) {
+                rules.rules.extend(contents.lines().filter_map(parse));
+            }
+        }
+        rules
+    }
+
+    // Whether the rules ignore `path`, which is relative to their directory,
+    // or `None` if none of them match it. The last rule that matches wins.
+    pub(crate) fn ignores(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        if path.components().next() == Some(Component::ParentDir) {
+            return None;
+        }
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only) && rule.pattern.matches_path_with(path, options)
+            })
+            .map(|rule| !rule.negated)
+    }
+}
+
+// Parses a line of an ignore file, in the format of `gitignore`. Comments,
+// blank lines and invalid patterns yield nothing.
+fn parse(line: &str) -> Option<Rule> {
+    // `trim_end` requires Rust 1.30
+    #[allow(deprecated)]
+    let line = line.trim_right();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, line) = if line.starts_with('!') {
+        (true, &line[1..])
+    } else {
+        (false, line)
+    };
+    // a leading `\` allows for a literal `#` or `!`
+    let line = if line.starts_with('\\') {
+        &line[1..]
+    } else {
+        line
+    };
+    let (dir_only, line) = if line.ends_with('/') {
+        (true, &line[..line.len() - 1])
+    } else {
+        (false, line)
+    };
+    if line.is_empty() {
+        return None;
+    }
+    // a pattern with a separator is relative to the directory of the ignore
+    // file, while one without matches a name at any depth
+    let pattern = if line.contains('/') {
+        if line.starts_with('/') {
+            line[1..].to_string()
+        } else {
+            line.to_string()
+        }
+    } else {
+        format!("**/{}", line)
+    };
+    Pattern::new(&pattern).ok().map(|pattern| Rule {
+        pattern,
+        negated,
+        dir_only,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::IgnoreRules;
+    use std::path::Path;
+
+    fn rules(contents: &str) -> IgnoreRules {
+        IgnoreRules {
+            rules: contents.lines().filter_map(super::parse).collect(),
+        }
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        let rules = rules(
+            "# build output\n\
+             target/\n\
+             *.o   \n\
+             !keep.o\n\
+             /TODO\n\
+             doc/*.html\n\
+             \\#notes\n",
+        );
+        let ignores = |path: &str, is_dir: bool| rules.ignores(Path::new(path), is_dir);
+        assert_eq!(ignores("target", true), Some(true));
+        assert_eq!(ignores("a/target", true), Some(true));
+        assert_eq!(ignores("target", false), None);
+        assert_eq!(ignores("a/b/x.o", false), Some(true));
+        assert_eq!(ignores("a/keep.o", false), Some(false));
+        assert_eq!(ignores("TODO", false), Some(true));
+        assert_eq!(ignores("a/TODO", false), None);
+        assert_eq!(ignores("doc/index.html", false), Some(true));
+        assert_eq!(ignores("doc/api/index.html", false), None);
+        assert_eq!(ignores("#notes", false), Some(true));
+        assert_eq!(ignores("../x.o", false), None);
+        assert_eq!(ignores("src", true), None);
+    }
+}
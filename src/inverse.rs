@@ -0,0 +1,188 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::vec;
+
+use {literal_base, GlobError, GlobResult, MatchOptions, ParseOptions, Pattern, PatternError};
+
+/// An iterator that yields the `Path`s beneath the literal base directory of
+/// a pattern that do *not* match it.
+///
+/// Entries are visited depth first in alphabetical order. A directory is
+/// yielded after its contents, and only if neither it nor anything beneath it
+/// matches the pattern, so removing every yielded path leaves all matching
+/// paths (and the directories leading to them) in place. Symbolic links are
+/// yielded like files and never descended into.
+///
+/// The pattern is matched against whole paths with
+/// `require_literal_separator` set, so `**` can be used to cover entire
+/// subtrees, e.g. `keep/**`.
+///
+/// See the `glob_not` function for more details.
+#[derive(Debug)]
+pub struct InversePaths {
+    pattern: Pattern,
+    options: MatchOptions,
+    base: Option<PathBuf>,
+    stack: Vec<Frame>,
+}
+
+#[derive(Debug)]
+struct Frame {
+    dir: PathBuf,
+    children: vec::IntoIter<(PathBuf, bool)>,
+    // whether the directory itself or anything beneath it matched
+    keep: bool,
+}
+
+/// Return an iterator that produces all the `Path`s beneath the literal base
+/// directory of the given pattern that do not match it, using default match
+/// options.
+///
+/// This is useful for cleanup tools, e.g. "delete everything in `cache` except
+/// `*.keep` files" becomes `glob_not("cache/**/*.keep")`.
+///
+/// This may return an error if the pattern is invalid.
+///
+/// See the `InversePaths` documentation for more information.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::glob_not;
+///
+/// for entry in glob_not("cache/**/*.keep").unwrap() {
+///     match entry {
+///         Ok(path) => println!("would remove {}", path.display()),
+///         Err(e) => println!("{:?}", e),
+///     }
+/// }
+/// ```
+pub fn glob_not(pattern: &str) -> Result<InversePaths, PatternError> {
+    InversePaths::new(pattern, MatchOptions::new(), ParseOptions::new())
+}
+
+impl InversePaths {
+    pub(crate) fn new(
+        pattern: &str,
+        options: MatchOptions,
+        parse_options: ParseOptions,
+    ) -> Result<Self, PatternError> {
+        let compiled = Pattern::new_with(pattern, parse_options)?;
+        Ok(Self {
+            pattern: compiled,
+            options: MatchOptions {
+                require_literal_separator: true,
+                ..options
+            },
+            base: Some(literal_base(pattern, parse_options)),
+            stack: Vec::new(),
+        })
+    }
+
+    fn is_match(&self, path: &Path) -> bool {
+        self.pattern.matches_path_with(path, self.options)
+    }
+}
+
+fn read_children(dir: &Path) -> Result<Vec<(PathBuf, bool)>, GlobError> {
+    let curdir = dir == Path::new(".");
+    let read = fs::read_dir(dir).and_then(|entries| {
+        entries
+            .map(|entry| {
+                entry.map(|entry| {
+                    // don't descend into symlinks
+                    let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                    let path = if curdir {
+                        PathBuf::from(entry.file_name())
+                    } else {
+                        entry.path()
+                    };
+                    (path, is_dir)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    });
+    match read {
+        Ok(mut children) => {
+            children.sort_by(|a, b| a.0.file_name().cmp(&b.0.file_name()));
+            Ok(children)
+        }
+        Err(error) => Err(GlobError::new(dir.to_path_buf(), error)),
+    }
+}
+
+impl Iterator for InversePaths {
+    type Item = GlobResult;
+
+    fn next(&mut self) -> Option<GlobResult> {
+        if let Some(base) = self.base.take() {
+            if fs::metadata(&base).map(|m| m.is_dir()).unwrap_or(false) {
+                match read_children(&base) {
+                    Ok(children) => self.stack.push(Frame {
+                        dir: base,
+                        children: children.into_iter(),
+                        keep: true,
+                    }),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+        }
+
+        loop {
+            let child = match self.stack.last_mut() {
+                Some(frame) => frame.children.next(),
+                None => return None,
+            };
+
+            match child {
+                Some((path, true)) => {
+                    let keep = self.is_match(&path);
+                    match read_children(&path) {
+                        Ok(children) => self.stack.push(Frame {
+                            dir: path,
+                            children: children.into_iter(),
+                            keep,
+                        }),
+                        Err(e) => {
+                            // we can't tell what's inside, so don't offer
+                            // the directory or its parents for removal
+                            self.stack.last_mut().unwrap().keep = true;
+                            return Some(Err(e));
+                        }
+                    }
+                }
+                Some((path, false)) => {
+                    if self.is_match(&path) {
+                        self.stack.last_mut().unwrap().keep = true;
+                    } else {
+                        return Some(Ok(path));
+                    }
+                }
+                None => {
+                    let frame = self.stack.pop().unwrap();
+                    match self.stack.last_mut() {
+                        // the base directory itself is never yielded
+                        None => return None,
+                        Some(parent) => {
+                            if frame.keep {
+                                parent.keep = true;
+                            } else {
+                                return Some(Ok(frame.dir));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
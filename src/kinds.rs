@@ -0,0 +1,66 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::path::PathBuf;
+
+use {FileKind, GlobError, Paths};
+
+/// An iterator that yields the same matches as a `Paths` iterator, each
+/// paired with the kind of entry it is.
+///
+/// The kind is the one found while searching, usually from the directory
+/// listing itself, so no further system calls are made for it. Like
+/// `DirEntry::file_type`, it describes the entry rather than what it points
+/// to, so a symbolic link is `FileKind::Symlink` whatever its target is.
+///
+/// Created by `Paths::with_file_kinds`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::{glob, FileKind};
+///
+/// for entry in glob("src/**/*").unwrap().with_file_kinds() {
+///     if let Ok((path, FileKind::Dir)) = entry {
+///         println!("{}/", path.display());
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FileKinds {
+    paths: Paths,
+}
+
+impl FileKinds {
+    pub(crate) fn new(paths: Paths) -> Self {
+        Self { paths }
+    }
+}
+
+impl Iterator for FileKinds {
+    type Item = Result<(PathBuf, FileKind), GlobError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = match self.paths.next_match() {
+            Some(Ok(path)) => path,
+            Some(Err(e)) => return Some(Err(e)),
+            None => return None,
+        };
+
+        let kind = if path.is_symlink {
+            FileKind::Symlink
+        } else if path.is_directory {
+            FileKind::Dir
+        } else {
+            FileKind::File
+        };
+        Some(self.paths.finish(path).map(|path| (path, kind)))
+    }
+}
@@ -47,6 +47,7 @@
 //!     case_sensitive: false,
 //!     require_literal_separator: false,
 //!     require_literal_leading_dot: false,
+//!     ..MatchOptions::new()
 //! };
 //! for entry in glob_with("local/*a*", options).unwrap() {
 //!     if let Ok(path) = entry {
@@ -58,7 +59,7 @@
 #![doc(
     html_logo_url = "https://www.rust-lang.org/logos/rust-logo-128x128-blk-v2.png",
     html_favicon_url = "https://www.rust-lang.org/favicon.ico",
-    html_root_url = "https://docs.rs/glob/0.3.1"
+    html_root_url = "https://docs.rs/glob/0.4.0"
 )]
 #![deny(missing_docs)]
 
@@ -69,17 +70,114 @@ extern crate doc_comment;
 #[cfg(test)]
 doctest!("../README.md");
 
+#[cfg(feature = "camino")]
+extern crate camino;
+#[cfg(feature = "stream")]
+extern crate futures_core;
+#[cfg(feature = "collation")]
+extern crate icu_collator;
+#[cfg(feature = "collation")]
+extern crate icu_provider;
+#[cfg(all(unix, any(feature = "conformance", feature = "users")))]
+extern crate libc;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "unicode")]
+extern crate regex_syntax;
+#[cfg(feature = "stream")]
+extern crate tokio;
+#[cfg(all(windows, feature = "users"))]
+extern crate windows_sys;
+
+#[cfg(feature = "ansi")]
+pub mod ansi;
+mod braces;
+pub mod build;
+mod builder;
+#[cfg(feature = "cache")]
+pub mod cache;
+mod candidate;
+mod case;
+#[cfg(feature = "collation")]
+pub mod collation;
+#[cfg(all(unix, feature = "conformance"))]
+pub mod conformance;
+mod difference;
+mod expansions;
+mod explain;
+mod filesystem;
+mod fingerprint;
+mod glob_set;
+mod ignore;
+mod inverse;
+mod kinds;
+mod links;
+mod manifest;
+mod many;
+mod nfa;
+mod partition;
+mod rewrite;
+mod set;
+pub mod shell;
+mod specificity;
+#[macro_use]
+mod static_pattern;
+#[cfg(feature = "stream")]
+pub mod stream;
+mod tagged;
+mod token;
+mod unicode;
+#[cfg(feature = "testing")]
+#[macro_use]
+pub mod testing;
+#[cfg(feature = "users")]
+mod users;
+#[cfg(feature = "camino")]
+pub mod utf8;
+mod which;
+
+pub use builder::PatternBuilder;
+pub use candidate::Candidate;
+pub use case::is_case_sensitive;
+pub use expansions::Expansions;
+pub use explain::{MatchFailure, MatchFailureKind};
+pub use filesystem::{FileKind, FileSystem, StdFileSystem};
+pub use fingerprint::{Fingerprint, FingerprintedPaths};
+pub use glob_set::GlobSet;
+pub use inverse::{glob_not, InversePaths};
+pub use kinds::FileKinds;
+pub use links::LinkTargets;
+pub use manifest::{FileStamp, Manifest, ManifestDiff};
+pub use many::{glob_many, MultiPaths, PerPatternPaths};
+pub use partition::PartitionedPaths;
+pub use set::{PatternSet, PatternSetError, Precedence};
+pub use specificity::Specificity;
+pub use static_pattern::StaticPattern;
+pub use tagged::{SkipReason, TaggedPaths, Visit};
+pub use token::{ClassMember, Token};
+pub use which::{which_glob, which_glob_in, Executables};
+
+use std::borrow::Cow;
 use std::cmp;
 use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
+use std::ffi::OsStr;
 use std::fmt;
 use std::fs;
 use std::fs::DirEntry;
-use std::io;
-use std::ops::Deref;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::{Deref, Range};
 use std::path::{self, Component, Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
+use ignore::IgnoreRules;
 use CharSpecifier::{CharRange, SingleChar};
 use MatchResult::{EntirePatternDoesntMatch, Match, SubPatternDoesntMatch};
 use PatternToken::AnyExcept;
@@ -98,9 +196,168 @@ use PatternToken::{AnyChar, AnyRecursiveSequence, AnySequence, AnyWithin, Char};
 pub struct Paths {
     dir_patterns: Vec<Pattern>,
     require_dir: bool,
-    options: MatchOptions,
+    // the directory matches are yielded relative to, see `RelativeTo`
+    prefix: Option<PathBuf>,
+    config: GlobBuilder,
     todo: Vec<Result<(PathWrapper, usize), GlobError>>,
-    scope: Option<PathWrapper>,
+    // the directories the search starts from, until it has started
+    scopes: Vec<PathWrapper>,
+    state: WalkState,
+}
+
+// Bookkeeping shared by every `fill_todo` call made for one `Paths`.
+#[derive(Debug, Default)]
+struct WalkState {
+    // number of directory entries read so far
+    entries: usize,
+    // number of matches found so far
+    matches: usize,
+    // set once the search has ended early, e.g. because
+    // `GlobBuilder::max_entries` was exceeded
+    exhausted: bool,
+    // the canonicalized scope, when confined to it
+    root: Option<PathBuf>,
+    // the directory the search starts from
+    scope: PathBuf,
+    // the rules of the ignore files read so far, by directory
+    ignores: HashMap<PathBuf, IgnoreRules>,
+    // entries that were visited but not yielded, when tagging
+    skipped: Option<VecDeque<(PathBuf, SkipReason)>>,
+    // directories whose entries were listed, when requested
+    listed: Option<Vec<PathBuf>>,
+    // match results for the names in directories with pending entries
+    memo: MatchMemo,
+}
+
+// Remembers which entries of a directory matched which pattern components for
+// as long as some of its entries are still in the todo buffer. A directory that
+// is reached by several `**` states has its entries queued once for each of
+// them, and would otherwise have the same names matched against the same
+// components over and over.
+#[derive(Debug, Default)]
+struct MatchMemo {
+    // the directories with pending entries, by increasing `queued_at`
+    dirs: Vec<DirMemo>,
+}
+
+#[derive(Debug)]
+struct DirMemo {
+    dir: PathBuf,
+    // the length of the todo buffer when its entries were first queued
+    queued_at: usize,
+    // whether each name matched, by pattern component
+    results: HashMap<usize, HashMap<String, bool>>,
+}
+
+impl MatchMemo {
+    // Records that the entries of `dir` are about to be queued in a todo
+    // buffer of length `len`.
+    fn enter(&mut self, dir: &Path, len: usize) {
+        self.expire(len);
+        if !self.dirs.iter().any(|d| d.dir == dir) {
+            self.dirs.push(DirMemo {
+                dir: dir.to_path_buf(),
+                queued_at: len,
+                results: HashMap::new(),
+            });
+        }
+    }
+
+    // Forgets the directories whose entries were all taken out of the todo
+    // buffer, now of length `len`.
+    fn expire(&mut self, len: usize) {
+        while self.dirs.last().map_or(false, |d| d.queued_at >= len) {
+            self.dirs.pop();
+        }
+    }
+
+    // Return if `name`, the name of the entry `path` that was just taken out
+    // of the todo buffer, now of length `len`, matches `patterns[idx]`.
+    fn matches(
+        &mut self,
+        path: &Path,
+        name: &str,
+        len: usize,
+        patterns: &[Pattern],
+        idx: usize,
+        options: MatchOptions,
+    ) -> bool {
+        self.expire(len + 1);
+        let dir = match path.parent() {
+            Some(dir) => dir,
+            None => return patterns[idx].matches_with(name, options),
+        };
+        let results = match self.dirs.iter_mut().rev().find(|d| d.dir == dir) {
+            Some(memo) => memo.results.entry(idx).or_insert_with(HashMap::new),
+            None => return patterns[idx].matches_with(name, options),
+        };
+        if let Some(&matched) = results.get(name) {
+            return matched;
+        }
+        let matched = patterns[idx].matches_with(name, options);
+        results.insert(name.to_string(), matched);
+        matched
+    }
+}
+
+impl WalkState {
+    fn is_tagging(&self) -> bool {
+        self.skipped.is_some()
+    }
+
+    fn skip(&mut self, path: PathBuf, reason: SkipReason) {
+        if let Some(ref mut skipped) = self.skipped {
+            skipped.push_back((path, reason));
+        }
+    }
+
+    // Whether `path` matches one of the patterns given to
+    // `GlobBuilder::exclude`, relative to the scope.
+    fn is_excluded(&self, config: &GlobBuilder, path: &PathWrapper) -> bool {
+        if config.excludes.is_empty() {
+            return false;
+        }
+        let relative = path.strip_prefix(&self.scope).unwrap_or(path);
+        config
+            .excludes
+            .iter()
+            .any(|p| p.matches_path_is_dir(relative, path.is_directory, config.options))
+    }
+
+    // Reads the ignore files of the directory `dir`, unless that was already
+    // done.
+    fn load_ignores(&mut self, config: &GlobBuilder, dir: &Path) {
+        if config.ignore_files && !self.ignores.contains_key(dir) {
+            let rules = IgnoreRules::load(config.fs(), dir);
+            self.ignores.insert(dir.to_path_buf(), rules);
+        }
+    }
+
+    // Whether `path` is ignored by the ignore files of the directories above
+    // it, the deepest of which takes precedence.
+    fn is_ignored(&self, config: &GlobBuilder, path: &PathWrapper) -> bool {
+        if !config.ignore_files {
+            return false;
+        }
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            if let Some(rules) = self.ignores.get(d) {
+                let relative = path.strip_prefix(d).unwrap();
+                if let Some(ignored) = rules.ignores(relative, path.is_directory) {
+                    return ignored;
+                }
+            }
+            dir = d.parent();
+        }
+        false
+    }
+
+    fn is_within_root<F: ?Sized + FileSystem>(&self, fs: &F, path: &Path) -> bool {
+        match (self.root.as_ref(), fs.canonicalize(path)) {
+            (Some(root), Ok(path)) => path.starts_with(root),
+            _ => false,
+        }
+    }
 }
 
 /// Return an iterator that produces all the `Path`s that match the given
@@ -160,6 +417,18 @@ pub struct Paths {
 /// }
 /// ```
 /// Paths are yielded in alphabetical order.
+///
+/// On Windows, an absolute pattern may start with a verbatim prefix like
+/// `\\?\C:\` or `\\?\UNC\server\share\`, as returned by
+/// `fs::canonicalize`. The prefix is taken literally, so its `?` isn't a
+/// wildcard, and since Windows doesn't treat `/` as a separator in verbatim
+/// paths, neither does the rest of the pattern. Likewise, a UNC prefix like
+/// `\\server\share\` is where the search starts from. Since there's no
+/// listing the servers of a network or, portably, the shares of a server,
+/// wildcards in either are an error. A drive letter may have wildcards
+/// though: `[cd]:\Users\*` searches each of the drives `C:` and `D:` that
+/// exists, in turn. As the drives have no root in common, `RelativeTo::Root`
+/// yields their paths unchanged.
 pub fn glob(pattern: &str) -> Result<Paths, PatternError> {
     glob_with(pattern, MatchOptions::new())
 }
@@ -178,230 +447,1570 @@ pub fn glob(pattern: &str) -> Result<Paths, PatternError> {
 ///
 /// Paths are yielded in alphabetical order.
 pub fn glob_with(pattern: &str, options: MatchOptions) -> Result<Paths, PatternError> {
-    #[cfg(windows)]
-    fn check_windows_verbatim(p: &Path) -> bool {
-        match p.components().next() {
-            Some(Component::Prefix(ref p)) => {
-                // Allow VerbatimDisk paths. std canonicalize() generates them, and they work fine
-                p.kind().is_verbatim()
-                    && if let std::path::Prefix::VerbatimDisk(_) = p.kind() {
-                        false
-                    } else {
-                        true
-                    }
-            }
-            _ => false,
-        }
-    }
-    #[cfg(not(windows))]
-    fn check_windows_verbatim(_: &Path) -> bool {
-        false
-    }
+    GlobBuilder::new().match_options(options).glob(pattern)
+}
 
-    #[cfg(windows)]
-    fn to_scope(p: &Path) -> PathBuf {
-        // FIXME handle volume relative paths here
-        p.to_path_buf()
-    }
-    #[cfg(not(windows))]
-    fn to_scope(p: &Path) -> PathBuf {
-        p.to_path_buf()
-    }
+/// Return an iterator that produces all the `Path`s that match the given
+/// pattern using default match options, resolving a relative pattern against
+/// `base` rather than the current working directory.
+///
+/// This may return an error if the pattern is invalid.
+///
+/// The paths yielded for a relative pattern begin with `base`, as if the
+/// pattern had been joined to it, but without `base` itself being
+/// interpreted as a pattern, so it may contain metacharacters. An absolute
+/// pattern ignores `base`. Unlike changing the current directory, this is
+/// safe to do from several threads at once.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::glob_in;
+///
+/// for path in glob_in("/srv/www/[site]", "**/*.html").unwrap().filter_map(Result::ok) {
+///     // e.g. `/srv/www/[site]/index.html`
+///     println!("{}", path.display());
+/// }
+/// ```
+pub fn glob_in<P: AsRef<Path>>(base: P, pattern: &str) -> Result<Paths, PatternError> {
+    GlobBuilder::new().glob_in(base, pattern)
+}
 
-    // make sure that the pattern is valid first, else early return with error
-    let _ = Pattern::new(pattern)?;
+/// Return the deepest directory that every match of every given pattern lies
+/// beneath, using default parse options, so that a single walk from it finds
+/// all of them.
+///
+/// This is the longest common prefix of the leading literal components of the
+/// patterns, ignoring any `.` components, or `.` if there is none. `None` is
+/// returned if the patterns have no directory in common, e.g. if one of them
+/// is absolute and the other relative. Invalid patterns are only looked at up
+/// to their first component that isn't literal.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::common_base;
+/// use std::path::Path;
+///
+/// let base = common_base(&["src/**/*.rs", "src/bin/*.rs"]).unwrap();
+/// assert_eq!(base, Path::new("src"));
+/// assert_eq!(common_base(&["*.md", "src/*.rs"]).unwrap(), Path::new("."));
+/// assert_eq!(common_base(&["/etc/*", "src/*.rs"]), None);
+/// ```
+pub fn common_base<I>(patterns: I) -> Option<PathBuf>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    common_base_with(patterns, ParseOptions::new())
+}
 
-    let mut components = Path::new(pattern).components().peekable();
-    loop {
-        match components.peek() {
-            Some(&Component::Prefix(..)) | Some(&Component::RootDir) => {
-                components.next();
-            }
-            _ => break,
+/// Return the deepest directory that every match of every given pattern lies
+/// beneath, using the specified parse options. See `common_base`.
+pub fn common_base_with<I>(patterns: I, options: ParseOptions) -> Option<PathBuf>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut common: Option<Vec<PathBuf>> = None;
+    let mut rooted = false;
+    for pattern in patterns {
+        let base = literal_base(pattern.as_ref(), options);
+        let components: Vec<PathBuf> = base
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .map(|c| PathBuf::from(c.as_os_str()))
+            .collect();
+        match components.first().map(|c| Path::new(c).components().next()) {
+            Some(Some(Component::Normal(_))) | None => (),
+            // a root, a prefix or `..`, which `.` doesn't lie above
+            _ => rooted = true,
         }
+        common = Some(match common {
+            None => components,
+            Some(mut common) => {
+                let shared = common
+                    .iter()
+                    .zip(&components)
+                    .take_while(|&(a, b)| a == b)
+                    .count();
+                common.truncate(shared);
+                common
+            }
+        });
     }
-    let rest = components.map(|s| s.as_os_str()).collect::<PathBuf>();
-    let normalized_pattern = Path::new(pattern).iter().collect::<PathBuf>();
-    let root_len = normalized_pattern.to_str().unwrap().len() - rest.to_str().unwrap().len();
-    let root = if root_len > 0 {
-        Some(Path::new(&pattern[..root_len]))
-    } else {
-        None
-    };
 
-    if root_len > 0 && check_windows_verbatim(root.unwrap()) {
-        // FIXME: How do we want to handle verbatim paths? I'm inclined to
-        // return nothing, since we can't very well find all UNC shares with a
-        // 1-letter server name.
-        return Ok(Paths {
-            dir_patterns: Vec::new(),
-            require_dir: false,
-            options,
-            todo: Vec::new(),
-            scope: None,
-        });
+    let common = common.unwrap_or_default();
+    if common.is_empty() {
+        return if rooted {
+            None
+        } else {
+            Some(PathBuf::from("."))
+        };
     }
+    Some(common.iter().collect())
+}
 
-    let scope = root.map_or_else(|| PathBuf::from("."), to_scope);
-    let scope = PathWrapper::from_path(scope);
+/// How `Paths` treats symbolic links whose target doesn't exist, see
+/// `GlobBuilder::broken_symlinks`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum BrokenSymlinks {
+    /// Broken links are matched by name, like any other file.
+    Include,
 
-    let mut dir_patterns = Vec::new();
-    let components =
-        pattern[cmp::min(root_len, pattern.len())..].split_terminator(path::is_separator);
+    /// Broken links are never yielded.
+    Exclude,
 
-    for component in components {
-        dir_patterns.push(Pattern::new(component)?);
-    }
+    /// Broken links that match are yielded as a `GlobError` for the link,
+    /// with an error of kind `io::ErrorKind::NotFound`.
+    Error,
 
-    if root_len == pattern.len() {
-        dir_patterns.push(Pattern {
-            original: "".to_string(),
-            tokens: Vec::new(),
-            is_recursive: false,
-        });
-    }
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
 
-    let last_is_separator = pattern.chars().next_back().map(path::is_separator);
-    let require_dir = last_is_separator == Some(true);
-    let todo = Vec::new();
+/// What `Paths` does when a directory it needs to search can't be read, see
+/// `GlobBuilder::read_errors`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ReadErrors {
+    /// The error is yielded as a `GlobError` for the directory, and the
+    /// search carries on with the next entry.
+    Yield,
 
-    Ok(Paths {
-        dir_patterns,
-        require_dir,
-        options,
-        todo,
-        scope: Some(scope),
-    })
+    /// The directory is left out as if it were empty.
+    Skip,
+
+    /// The error is yielded as a `GlobError` for the directory, and nothing
+    /// is yielded after it.
+    Abort,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
 }
 
-/// A glob iteration error.
-///
-/// This is typically returned when a particular path cannot be read
-/// to determine if its contents match the glob pattern. This is possible
-/// if the program lacks the appropriate permissions, for example.
-#[derive(Debug)]
-pub struct GlobError {
-    path: PathBuf,
-    error: io::Error,
+/// What the paths yielded by `Paths` are relative to, see
+/// `GlobBuilder::relative_to`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum RelativeTo {
+    /// Paths are yielded as they're found, beginning with the root of an
+    /// absolute pattern, the directory given to `glob_in`, or whatever
+    /// leading `.` or `..` components a relative pattern has.
+    Unchanged,
+
+    /// Paths are relative to the directory the search starts from: the root
+    /// of an absolute pattern, the directory given to `glob_in`, or else the
+    /// current directory, so `./src/*.rs` yields `src/lib.rs`.
+    Root,
+
+    /// Paths are relative to the literal base of the pattern, which is its
+    /// leading components without metacharacters, so `./src/**/*.rs` yields
+    /// `lib.rs` and `bin/main.rs`.
+    LiteralBase,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
 }
 
-impl GlobError {
-    /// The Path that the error corresponds to.
-    pub fn path(&self) -> &Path {
-        &self.path
-    }
+/// The order in which the entries of each directory are visited, see
+/// `GlobBuilder::sort_order`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SortOrder {
+    /// By file name, using the comparison given to `GlobBuilder::sort_by` if
+    /// any. This is the default.
+    Name,
 
-    /// The error in question.
-    pub fn error(&self) -> &io::Error {
-        &self.error
-    }
+    /// In whatever order the directory lists its entries, which saves sorting
+    /// huge directories when the order doesn't matter.
+    Unsorted,
 
-    /// Consumes self, returning the _raw_ underlying `io::Error`
-    pub fn into_error(self) -> io::Error {
-        self.error
-    }
-}
+    /// By last modification time, oldest first.
+    Modified,
 
-impl Error for GlobError {
-    #[allow(deprecated)]
-    fn description(&self) -> &str {
-        self.error.description()
-    }
+    /// By size in bytes, smallest first.
+    Size,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
 
+/// A builder for glob iterators, exposing settings that control how the
+/// filesystem is traversed rather than how individual names are matched.
+///
+/// `glob_with(pattern, options)` is equivalent to
+/// `GlobBuilder::new().match_options(options).glob(pattern)`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::GlobBuilder;
+///
+/// // Like bash's `globstar`, don't descend into symlinked directories that
+/// // are only reached through `**`.
+/// for entry in GlobBuilder::new()
+///     .recursive_follow_links(false)
+///     .glob("src/**/*.rs")
+///     .unwrap()
+/// {
+///     println!("{:?}", entry);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct GlobBuilder {
+    options: MatchOptions,
+    parse_options: ParseOptions,
+    recursive_follow_links: bool,
+    descend_hidden: Option<bool>,
+    follow_symlinks: bool,
+    detect_link_cycles: bool,
+    max_link_depth: Option<usize>,
+    max_entries: Option<usize>,
+    max_results: Option<usize>,
+    min_depth: Option<usize>,
+    max_depth: Option<usize>,
+    confine: bool,
+    todo_capacity: usize,
+    read_batch_size: usize,
+    shrink_threshold: Option<usize>,
+    read_timeout: Option<Duration>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    auto_case_sensitive: bool,
+    resolve_symlinks: bool,
+    broken_symlinks: BrokenSymlinks,
+    read_errors: ReadErrors,
+    file_kind: Option<FileKind>,
+    relative_to: RelativeTo,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_since: Option<SystemTime>,
+    excludes: Vec<Pattern>,
+    ignore_files: bool,
+    #[cfg(feature = "cache")]
+    dir_cache: Option<cache::DirCache>,
     #[allow(unknown_lints, bare_trait_objects)]
-    fn cause(&self) -> Option<&Error> {
-        Some(&self.error)
-    }
+    filesystem: Option<Arc<FileSystem>>,
+    sort_by: Option<Comparator>,
+    sort_order: SortOrder,
+    listings: Option<SharedListings>,
 }
 
-impl fmt::Display for GlobError {
+// Directory listings shared by the iterators of a `MultiPaths`, so that each
+// directory is only read once however many of its patterns search it.
+#[derive(Clone, Debug, Default)]
+struct SharedListings(Arc<Mutex<HashMap<PathBuf, Vec<PathWrapper>>>>);
+
+// A comparator for the names of directory entries, see
+// `GlobBuilder::sort_by`.
+#[derive(Clone)]
+struct Comparator(Arc<CompareNames>);
+
+#[allow(unknown_lints, bare_trait_objects)]
+type CompareNames = Fn(&OsStr, &OsStr) -> Ordering + Send + Sync;
+
+impl fmt::Debug for Comparator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "attempting to read `{}` resulted in an error: {}",
-            self.path.display(),
-            self.error
-        )
+        f.write_str("Comparator")
     }
 }
 
-#[derive(Debug)]
-struct PathWrapper {
-    path: PathBuf,
-    is_directory: bool,
-}
+impl GlobBuilder {
+    /// Constructs a new `GlobBuilder` using `MatchOptions::new()` and the
+    /// traversal behaviour of `glob`.
+    pub fn new() -> Self {
+        Self {
+            options: MatchOptions::new(),
+            parse_options: ParseOptions::new(),
+            recursive_follow_links: true,
+            descend_hidden: None,
+            follow_symlinks: true,
+            detect_link_cycles: false,
+            max_link_depth: None,
+            max_entries: None,
+            max_results: None,
+            min_depth: None,
+            max_depth: None,
+            confine: false,
+            todo_capacity: 0,
+            read_batch_size: 0,
+            shrink_threshold: None,
+            read_timeout: None,
+            cancel_flag: None,
+            auto_case_sensitive: false,
+            resolve_symlinks: false,
+            broken_symlinks: BrokenSymlinks::Include,
+            read_errors: ReadErrors::Yield,
+            file_kind: None,
+            relative_to: RelativeTo::Unchanged,
+            min_size: None,
+            max_size: None,
+            modified_since: None,
+            excludes: Vec::new(),
+            ignore_files: false,
+            #[cfg(feature = "cache")]
+            dir_cache: None,
+            filesystem: None,
+            sort_by: None,
+            sort_order: SortOrder::Name,
+            listings: None,
+        }
+    }
 
-impl PathWrapper {
-    fn from_dir_entry(path: PathBuf, e: DirEntry) -> Self {
-        let is_directory = e
-            .file_type()
-            .ok()
-            .and_then(|file_type| {
-                // We need to use fs::metadata to resolve the actual path
-                // if it's a symlink.
-                if file_type.is_symlink() {
-                    None
-                } else {
-                    Some(file_type.is_dir())
-                }
-            })
-            .or_else(|| fs::metadata(&path).map(|m| m.is_dir()).ok())
-            .unwrap_or(false);
-        Self { path, is_directory }
+    /// Constructs a `GlobBuilder` suitable for patterns that come from
+    /// untrusted input.
+    ///
+    /// This bundles `MatchOptions::untrusted()` and `ParseOptions::untrusted()`
+    /// with a budget of 100,000 directory entries, confinement to the current
+    /// directory (see `confine`), `recursive_follow_links(false)`,
+    /// `detect_link_cycles(true)` and a `max_link_depth` of 40. Each setting
+    /// can still be adjusted afterwards.
+    pub fn hardened() -> Self {
+        Self {
+            options: MatchOptions::untrusted(),
+            parse_options: ParseOptions::untrusted(),
+            recursive_follow_links: false,
+            detect_link_cycles: true,
+            max_link_depth: Some(40),
+            max_entries: Some(100_000),
+            confine: true,
+            ..Self::new()
+        }
     }
-    fn from_path(path: PathBuf) -> Self {
-        let is_directory = fs::metadata(&path).map(|m| m.is_dir()).unwrap_or(false);
-        Self { path, is_directory }
+
+    /// Sets the options used to match each path component.
+    pub fn match_options(&mut self, options: MatchOptions) -> &mut Self {
+        self.options = options;
+        self
     }
 
-    fn into_path(self) -> PathBuf {
-        self.path
+    /// Sets the options used to compile the pattern, such as limits on its
+    /// complexity.
+    pub fn parse_options(&mut self, options: ParseOptions) -> &mut Self {
+        self.parse_options = options;
+        self
     }
-}
 
-impl Deref for PathWrapper {
-    type Target = Path;
+    /// Sets whether `**` descends into symbolic links to directories that it
+    /// encounters while recursing. Defaults to `true`.
+    ///
+    /// When disabled, a symlinked directory found by `**` can still be matched
+    /// itself, but its contents are not searched. Symlinks named by other
+    /// components of the pattern, e.g. `link/**` or `*/**`, are always
+    /// followed, which mirrors the behaviour of bash's `globstar` option. With
+    /// the default, `**` follows every link the way git's pathspecs do.
+    pub fn recursive_follow_links(&mut self, follow: bool) -> &mut Self {
+        self.recursive_follow_links = follow;
+        self
+    }
 
-    fn deref(&self) -> &Self::Target {
-        self.path.deref()
+    /// Sets whether `**` descends into directories whose names start with a
+    /// `.`, such as `.git`, or `None` to leave it to the match options.
+    /// Defaults to `None`.
+    ///
+    /// By default, `MatchOptions::require_literal_leading_dot` decides both
+    /// whether hidden entries match and whether `**` searches hidden
+    /// directories. Setting this separates the two: with `Some(false)`,
+    /// `**/*.rs` skips `.git` entirely while still matching `.hidden.rs`,
+    /// and with `Some(true)` it searches `.git` even though hidden entries,
+    /// like `.git` itself, don't match. Hidden directories named by other
+    /// components of the pattern, e.g. `.git/**`, are unaffected.
+    pub fn descend_hidden(&mut self, descend: Option<bool>) -> &mut Self {
+        self.descend_hidden = descend;
+        self
     }
-}
 
-impl AsRef<Path> for PathWrapper {
-    fn as_ref(&self) -> &Path {
-        self.path.as_ref()
+    /// Sets whether the search descends into symbolic links to directories
+    /// at all. Defaults to `true`.
+    ///
+    /// When disabled, no link is followed, whether it's found by `**`, by a
+    /// wildcard, or named literally like `link/*`. A link to a directory can
+    /// still be matched itself, as by `*`, but never has its contents
+    /// searched. A link given as the root of an absolute pattern, or to
+    /// `glob_in`, is always followed.
+    pub fn follow_symlinks(&mut self, follow: bool) -> &mut Self {
+        self.follow_symlinks = follow;
+        self
     }
-}
 
-/// An alias for a glob iteration result.
-///
-/// This represents either a matched path or a glob iteration error,
-/// such as failing to read a particular directory's contents.
-pub type GlobResult = Result<PathBuf, GlobError>;
+    /// Sets whether the search refuses to descend into a symbolic link that
+    /// leads back to one of the directories it's in, which would otherwise
+    /// have `**` go round the cycle until `max_link_depth` is reached.
+    /// Defaults to `false`.
+    ///
+    /// Each link to a directory that's about to be searched is compared with
+    /// the directories above it, by device and inode number on Unix, or else
+    /// by their canonical paths (see `FileSystem::is_same_file`). Instead of
+    /// searching a link that forms a cycle, a `GlobError` of kind
+    /// `GlobErrorKind::Loop` is yielded for it. The link itself can still be
+    /// matched.
+    pub fn detect_link_cycles(&mut self, detect: bool) -> &mut Self {
+        self.detect_link_cycles = detect;
+        self
+    }
 
-impl Iterator for Paths {
-    type Item = GlobResult;
+    /// Sets the maximum number of symbolic links to directories that `**` may
+    /// pass through on the way to a directory it descends into, or `None` for
+    /// no limit. Defaults to `None`.
+    ///
+    /// This bounds the work done by chains of links, which don't necessarily
+    /// form a cycle, much like `ELOOP` does for resolving a single path. The
+    /// links named by the scope or by other components of the pattern count
+    /// towards the limit too. A directory beyond it can still be matched
+    /// itself, but instead of searching its contents a `GlobError` of kind
+    /// `GlobErrorKind::TooManyLinks` is yielded for it.
+    pub fn max_link_depth(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_link_depth = max;
+        self
+    }
 
-    fn next(&mut self) -> Option<GlobResult> {
-        // the todo buffer hasn't been initialized yet, so it's done at this
-        // point rather than in glob() so that the errors are unified that is,
-        // failing to fill the buffer is an iteration error construction of the
-        // iterator (i.e. glob()) only fails if it fails to compile the Pattern
-        if let Some(scope) = self.scope.take() {
-            if !self.dir_patterns.is_empty() {
-                // Shouldn't happen, but we're using -1 as a special index.
-                assert!(self.dir_patterns.len() < std::usize::MAX);
+    /// Sets the maximum number of directory entries that may be read while
+    /// iterating, or `None` for no limit. Defaults to `None`.
+    ///
+    /// Once the budget is exceeded the iterator yields a single `GlobError`
+    /// for the directory being read and then stops.
+    pub fn max_entries(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_entries = max;
+        self
+    }
 
-                fill_todo(&mut self.todo, &self.dir_patterns, 0, &scope, self.options);
-            }
-        }
+    /// Sets the maximum number of matches to find, or `None` for no limit.
+    /// Defaults to `None`.
+    ///
+    /// Once that many matches have been yielded, the search ends: the paths
+    /// still waiting to be visited are dropped and no more directories are
+    /// read. Unlike a budget set with `max_entries`, reaching the limit isn't
+    /// an error. Paired with `SortOrder::Unsorted`, finding the first few
+    /// matches doesn't even sort the directories they're found in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::GlobBuilder;
+    ///
+    /// let any_logs = GlobBuilder::new()
+    ///     .max_results(Some(1))
+    ///     .glob("/var/log/**/*.log")
+    ///     .unwrap()
+    ///     .any(|r| r.is_ok());
+    /// ```
+    pub fn max_results(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_results = max;
+        self
+    }
 
-        loop {
-            if self.dir_patterns.is_empty() || self.todo.is_empty() {
-                return None;
-            }
+    /// Sets the minimum depth of a match, or `None` for no minimum. Defaults
+    /// to `None`.
+    ///
+    /// The depth of a path is the number of components it has below the
+    /// directory the search starts from, which is the current directory, the
+    /// directory given to `glob_in`, or the root of an absolute pattern. So
+    /// with a minimum of 2, `**/*.rs` yields `src/lib.rs` but not `build.rs`.
+    /// Shallower directories are still searched.
+    pub fn min_depth(&mut self, depth: Option<usize>) -> &mut Self {
+        self.min_depth = depth;
+        self
+    }
 
-            let (path, mut idx) = match self.todo.pop().unwrap() {
+    /// Sets the maximum depth of a match, or `None` for no maximum. Defaults
+    /// to `None`.
+    ///
+    /// See `min_depth` for how depth is counted. Unlike filtering the
+    /// matches afterwards, this stops the search from descending any deeper,
+    /// so `GlobBuilder::new().max_depth(Some(3)).glob("**/*")` only reads
+    /// the directories up to 2 levels below the current one.
+    pub fn max_depth(&mut self, depth: Option<usize>) -> &mut Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets whether iteration is confined to the directory the pattern is
+    /// relative to. Defaults to `false`.
+    ///
+    /// When enabled, absolute patterns and patterns with `..` components are
+    /// rejected with `PatternErrorKind::EscapesRoot`, `.` and `..` are never
+    /// matched by wildcards, and symbolic links are only followed or yielded
+    /// if they resolve to somewhere inside the current directory.
+    pub fn confine(&mut self, confine: bool) -> &mut Self {
+        self.confine = confine;
+        self
+    }
+
+    /// Sets the number of pending paths to allocate space for up front.
+    /// Defaults to `0`, which allocates on demand.
+    ///
+    /// Every entry of a directory that still has to be matched is kept until
+    /// the iterator gets to it, so a pattern like `Maildir/*/cur/*` over very
+    /// wide directories benefits from a larger initial capacity.
+    pub fn todo_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.todo_capacity = capacity;
+        self
+    }
+
+    /// Sets whether `case_sensitive` in the match options is replaced by
+    /// the case sensitivity of the filesystem being searched. Defaults to
+    /// `false`.
+    ///
+    /// When enabled, the deepest directory that is named literally by the
+    /// pattern, e.g. `/media/usb` for `/media/usb/*.jpg`, is probed by looking
+    /// up one of its entries under a different case. If it doesn't exist or
+    /// that is inconclusive, e.g. because it's empty, its nearest ancestor
+    /// that tells is probed instead. On Windows, a drive letter with
+    /// wildcards has each of the drives it matches probed. The match options
+    /// are left alone if that is inconclusive, or if the drives disagree;
+    /// unlike `is_case_sensitive`, no temporary file is created.
+    pub fn auto_case_sensitive(&mut self, auto: bool) -> &mut Self {
+        self.auto_case_sensitive = auto;
+        self
+    }
+
+    /// Sets whether matches are yielded with all symbolic links in them
+    /// resolved, i.e. as the real location of each file. Defaults to `false`.
+    ///
+    /// Matches are canonicalized after they're found, so they're yielded as
+    /// absolute paths and two matches may resolve to the same path. A match
+    /// that can't be resolved, such as a broken symbolic link, is yielded as
+    /// a `GlobError` for the path that was matched.
+    pub fn resolve_symlinks(&mut self, resolve: bool) -> &mut Self {
+        self.resolve_symlinks = resolve;
+        self
+    }
+
+    /// Sets how symbolic links whose target doesn't exist are treated when
+    /// they match. Defaults to `BrokenSymlinks::Include`.
+    ///
+    /// Broken links never match a pattern that ends in a separator, since
+    /// they aren't directories.
+    pub fn broken_symlinks(&mut self, policy: BrokenSymlinks) -> &mut Self {
+        self.broken_symlinks = policy;
+        self
+    }
+
+    /// Sets what happens when a directory that has to be listed can't be
+    /// read, e.g. for lack of permission. Defaults to `ReadErrors::Yield`.
+    ///
+    /// This also covers directories that take longer than `read_timeout` to
+    /// list.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::{GlobBuilder, ReadErrors};
+    ///
+    /// // every result is a match, since unreadable directories are left out
+    /// for path in GlobBuilder::new()
+    ///     .read_errors(ReadErrors::Skip)
+    ///     .glob("/home/*/.profile")
+    ///     .unwrap()
+    /// {
+    ///     println!("{}", path.unwrap().display());
+    /// }
+    /// ```
+    pub fn read_errors(&mut self, policy: ReadErrors) -> &mut Self {
+        self.read_errors = policy;
+        self
+    }
+
+    /// Sets the kind of entry that matches are restricted to, or `None` for
+    /// any kind. Defaults to `None`.
+    ///
+    /// `FileKind::Dir` matches directories, including symbolic links to
+    /// them, just like a pattern ending in a separator. `FileKind::File`
+    /// matches every other entry that isn't a symbolic link, and
+    /// `FileKind::Symlink` matches symbolic links whatever they point to.
+    /// The kind is known from reading the directory, so checking it usually
+    /// costs nothing, unlike filtering the matches afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::{FileKind, GlobBuilder};
+    ///
+    /// // only the directories, without their contents
+    /// for path in GlobBuilder::new()
+    ///     .file_kind(Some(FileKind::Dir))
+    ///     .glob("src/**/*")
+    ///     .unwrap()
+    /// {
+    ///     println!("{}", path.unwrap().display());
+    /// }
+    /// ```
+    pub fn file_kind(&mut self, kind: Option<FileKind>) -> &mut Self {
+        self.file_kind = kind;
+        self
+    }
+
+    /// Sets what matches are yielded relative to. Defaults to
+    /// `RelativeTo::Unchanged`.
+    ///
+    /// A match that is the directory they're relative to is yielded as `.`.
+    /// This has no effect on the paths of errors, or on matches made
+    /// absolute by `resolve_symlinks`, and a match outside the directory,
+    /// like one reached through `..`, is yielded unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::{GlobBuilder, RelativeTo};
+    ///
+    /// // `lib.rs`, `bin/main.rs`, ...
+    /// for path in GlobBuilder::new()
+    ///     .relative_to(RelativeTo::LiteralBase)
+    ///     .glob("./src/**/*.rs")
+    ///     .unwrap()
+    /// {
+    ///     println!("{}", path.unwrap().display());
+    /// }
+    /// ```
+    pub fn relative_to(&mut self, relative_to: RelativeTo) -> &mut Self {
+        self.relative_to = relative_to;
+        self
+    }
+
+    /// Sets the minimum size in bytes of a match, or `None` for no minimum.
+    /// Defaults to `None`.
+    ///
+    /// Like `max_size` and `modified_since`, this applies to every match,
+    /// directories included, following symbolic links. The metadata of a
+    /// match is only queried once however many of these filters are set, and
+    /// a match whose metadata can't be queried is yielded as a `GlobError`.
+    pub fn min_size(&mut self, size: Option<u64>) -> &mut Self {
+        self.min_size = size;
+        self
+    }
+
+    /// Sets the maximum size in bytes of a match, or `None` for no maximum.
+    /// Defaults to `None`.
+    ///
+    /// See `min_size` for details.
+    pub fn max_size(&mut self, size: Option<u64>) -> &mut Self {
+        self.max_size = size;
+        self
+    }
+
+    /// Sets the time a match must have been modified at or after, or `None`
+    /// to match regardless of modification time. Defaults to `None`.
+    ///
+    /// See `min_size` for details.
+    pub fn modified_since(&mut self, time: Option<SystemTime>) -> &mut Self {
+        self.modified_since = time;
+        self
+    }
+
+    /// Adds a pattern for entries to leave out, along with everything below
+    /// them. Nothing is excluded by default.
+    ///
+    /// Unlike filtering the results afterwards, this prunes the search: an
+    /// excluded directory is never read. Exclusions are matched with the
+    /// builder's match options against each entry's path relative to the
+    /// directory the search starts from, as yielded with `RelativeTo::Root`,
+    /// so `**/node_modules` leaves out every `node_modules` directory. A
+    /// pattern that ends in a separator only excludes directories.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::{GlobBuilder, Pattern};
+    ///
+    /// for path in GlobBuilder::new()
+    ///     .exclude(Pattern::new("**/node_modules").unwrap())
+    ///     .exclude(Pattern::new("**/.git/").unwrap())
+    ///     .glob("**/*.js")
+    ///     .unwrap()
+    /// {
+    ///     println!("{}", path.unwrap().display());
+    /// }
+    /// ```
+    pub fn exclude(&mut self, pattern: Pattern) -> &mut Self {
+        self.excludes.push(pattern);
+        self
+    }
+
+    /// Sets whether `.gitignore` and `.ignore` files are honored. Defaults to
+    /// `false`.
+    ///
+    /// When enabled, the ignore files of each directory the search visits
+    /// are read, and the entries they ignore are left out along with
+    /// everything below them, so ignored directories are never read. The
+    /// rules follow the `gitignore` format: `!` re-includes what an earlier
+    /// rule ignored, a trailing `/` only matches directories, and a rule
+    /// with a separator elsewhere is relative to the directory of its file,
+    /// while one without matches a name at any depth. The rules of a deeper
+    /// directory take precedence, as do those of `.ignore` over
+    /// `.gitignore`.
+    ///
+    /// Only the ignore files of the directory the search starts from and
+    /// those below it are read, and ones that can't be read are treated as
+    /// empty. Git's global and per-repository exclude files aren't read.
+    pub fn ignore_files(&mut self, honor: bool) -> &mut Self {
+        self.ignore_files = honor;
+        self
+    }
+
+    /// Sets how many entries to allocate space for at a time while reading a
+    /// directory. Defaults to `0`, which lets the buffer grow geometrically.
+    ///
+    /// A fixed batch size avoids overshooting by up to twice the size of the
+    /// directory, at the cost of more reallocations for large directories.
+    pub fn read_batch_size(&mut self, batch_size: usize) -> &mut Self {
+        self.read_batch_size = batch_size;
+        self
+    }
+
+    /// Sets how much unused capacity the buffer of pending paths may keep
+    /// before it is released, or `None` to never release it. Defaults to
+    /// `None`.
+    ///
+    /// Reading a single very wide directory grows the buffer to fit all of its
+    /// entries; with a threshold set the memory is given back once most of
+    /// them have been consumed, instead of being held until iteration ends.
+    pub fn shrink_threshold(&mut self, threshold: Option<usize>) -> &mut Self {
+        self.shrink_threshold = threshold;
+        self
+    }
+
+    /// Sets how long reading a single directory may take, or `None` for no
+    /// limit. Defaults to `None`.
+    ///
+    /// With a timeout set, each directory is read on a separate thread. If it
+    /// doesn't finish in time, e.g. because a network mount is unresponsive,
+    /// a `GlobError` of kind `io::ErrorKind::TimedOut` is yielded for the
+    /// directory and iteration carries on without its contents. The thread
+    /// is left to finish, or hang, in the background.
+    pub fn read_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Sets a flag that cancels the search once it's set, or `None` for
+    /// searches that can't be cancelled. Defaults to `None`.
+    ///
+    /// The flag is checked before each entry is visited and each directory
+    /// is read, so another thread can stop a long search promptly even while
+    /// the iterating thread is busy in `next`. Once it's noticed, a
+    /// `GlobError` of kind `GlobErrorKind::Cancelled` is yielded and the
+    /// iteration ends. A directory that's already being read isn't
+    /// interrupted, though `read_timeout` can bound how long that takes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::GlobBuilder;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let paths = GlobBuilder::new()
+    ///     .cancel_flag(Some(cancel.clone()))
+    ///     .glob("/**/*.log")
+    ///     .unwrap();
+    /// let search = thread::spawn(move || paths.count());
+    /// thread::sleep(Duration::from_secs(1));
+    /// cancel.store(true, Ordering::Relaxed);
+    /// search.join().unwrap();
+    /// ```
+    pub fn cancel_flag(&mut self, flag: Option<Arc<AtomicBool>>) -> &mut Self {
+        self.cancel_flag = flag;
+        self
+    }
+
+    /// Sets a cache of directory listings to consult before reading a
+    /// directory. Defaults to `None`.
+    ///
+    /// This is only available with the `cache` feature; see the `cache`
+    /// module for details.
+    #[cfg(feature = "cache")]
+    pub fn dir_cache(&mut self, cache: Option<cache::DirCache>) -> &mut Self {
+        self.dir_cache = cache;
+        self
+    }
+
+    /// Sets the filesystem to search for matches. Defaults to
+    /// `StdFileSystem`.
+    ///
+    /// A directory cache set with `dir_cache` is only consulted for
+    /// `StdFileSystem`, and only when no filesystem has been set explicitly.
+    pub fn filesystem<F: FileSystem + 'static>(&mut self, fs: F) -> &mut Self {
+        self.filesystem = Some(Arc::new(fs));
+        self
+    }
+
+    /// Sets the order in which the entries of each directory are visited, and
+    /// therefore the order in which matches are yielded, by comparing their
+    /// file names. Defaults to comparing them byte-wise, i.e. alphabetically
+    /// for ASCII names. This also breaks ties when ordering by another
+    /// `SortOrder`, and has no effect with `SortOrder::Unsorted`.
+    ///
+    /// This allows e.g. a locale-aware or version-aware order without sorting
+    /// all of the matches afterwards. Since a directory's entries are visited
+    /// before its contents, `a/b` is still yielded before `a-c/d`. Note that
+    /// `MultiPaths::sorted` merges using the default order regardless.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::GlobBuilder;
+    ///
+    /// // newest release first, assuming names like `v1.2.3`
+    /// let releases = GlobBuilder::new()
+    ///     .sort_by(|a, b| b.len().cmp(&a.len()).then(b.cmp(a)))
+    ///     .glob("releases/v*")
+    ///     .unwrap();
+    /// ```
+    pub fn sort_by<F>(&mut self, compare: F) -> &mut Self
+    where
+        F: Fn(&OsStr, &OsStr) -> Ordering + Send + Sync + 'static,
+    {
+        self.sort_by = Some(Comparator(Arc::new(compare)));
+        self
+    }
+
+    /// Sets the order in which the entries of each directory are visited, and
+    /// therefore the order in which matches are yielded. Defaults to
+    /// `SortOrder::Name`.
+    ///
+    /// Ordering by modification time or size reads the metadata of every
+    /// entry of the directories that are listed, through
+    /// `FileSystem::size_and_modified`. Entries whose metadata can't be read
+    /// come last, and ties are broken by name. As with `sort_by`, a
+    /// directory's entries are visited before its contents.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::{GlobBuilder, SortOrder};
+    ///
+    /// // the order doesn't matter when deleting everything
+    /// for path in GlobBuilder::new()
+    ///     .sort_order(SortOrder::Unsorted)
+    ///     .glob("cache/**/*.tmp")
+    ///     .unwrap()
+    /// {
+    ///     println!("{}", path.unwrap().display());
+    /// }
+    /// ```
+    pub fn sort_order(&mut self, order: SortOrder) -> &mut Self {
+        self.sort_order = order;
+        self
+    }
+
+    /// Orders the entries of each directory with a locale-aware collation,
+    /// like `sort_by` with `Collation::compare`.
+    ///
+    /// This is only available with the `collation` feature; see the
+    /// `collation` module for details.
+    #[cfg(feature = "collation")]
+    pub fn collate(&mut self, collation: collation::Collation) -> &mut Self {
+        self.sort_by(move |a, b| collation.compare(a, b))
+    }
+
+    #[allow(unknown_lints, bare_trait_objects)]
+    fn fs(&self) -> &FileSystem {
+        match self.filesystem {
+            Some(ref fs) => &**fs,
+            None => &StdFileSystem,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel_flag
+            .as_ref()
+            .map_or(false, |flag| flag.load(atomic::Ordering::Relaxed))
+    }
+
+    // Whether the search may go on into the directory `path`, given how
+    // symbolic links are to be followed, or the error to yield instead. Only
+    // `**` can go round a cycle forever, so only its descents are checked.
+    fn may_descend(&self, path: &PathWrapper, recursive: bool) -> Result<bool, GlobError> {
+        if !path.is_symlink {
+            return Ok(true);
+        }
+        if !self.follow_symlinks {
+            return Ok(false);
+        }
+        if recursive && self.detect_link_cycles && is_link_cycle(self.fs(), path) {
+            return Err(GlobError::with_kind(
+                path.to_path_buf(),
+                GlobErrorKind::Loop,
+                io::Error::new(io::ErrorKind::Other, "cycle of symbolic links"),
+            ));
+        }
+        Ok(true)
+    }
+
+    // Whether `path` passes the size and modification time filters.
+    fn filter_metadata(&self, path: &Path) -> io::Result<bool> {
+        if self.min_size.is_none() && self.max_size.is_none() && self.modified_since.is_none() {
+            return Ok(true);
+        }
+        let (size, modified) = self.fs().size_and_modified(path)?;
+        Ok(self.min_size.map_or(true, |min| size >= min)
+            && self.max_size.map_or(true, |max| size <= max)
+            && self.modified_since.map_or(true, |since| modified >= since))
+    }
+
+    /// Return an iterator that produces all the `Path`s that match the given
+    /// pattern using the settings of this builder.
+    ///
+    /// This may return an error if the pattern is invalid.
+    pub fn glob(&self, pattern: &str) -> Result<Paths, PatternError> {
+        glob_with_config(pattern, None, self.clone())
+    }
+
+    /// Return an iterator that produces all the `Path`s that match the given
+    /// pattern using the settings of this builder, resolving a relative
+    /// pattern against `base` rather than the current working directory.
+    ///
+    /// See `glob_in` for more details. With `confine`, iteration is confined
+    /// to `base`.
+    pub fn glob_in<P: AsRef<Path>>(&self, base: P, pattern: &str) -> Result<Paths, PatternError> {
+        glob_with_config(pattern, Some(base.as_ref()), self.clone())
+    }
+
+    /// Return an iterator that produces all the `Path`s that match any of the
+    /// given patterns using the settings of this builder.
+    ///
+    /// This may return an error if any of the patterns is invalid.
+    ///
+    /// See `glob_many` for more details.
+    pub fn glob_many<I>(&self, patterns: I) -> Result<MultiPaths, PatternError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        MultiPaths::new(self, patterns)
+    }
+
+    /// Return an iterator that produces all the `Path`s beneath the literal
+    /// base directory of the given pattern that do not match it, using the
+    /// match and parse options of this builder.
+    ///
+    /// See `glob_not` for more details.
+    pub fn glob_not(&self, pattern: &str) -> Result<InversePaths, PatternError> {
+        InversePaths::new(pattern, self.options, self.parse_options)
+    }
+}
+
+impl Default for GlobBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Sets up the iteration for `pattern`, which is relative to `base` if given,
+// or else to the current directory.
+fn glob_with_config(
+    pattern: &str,
+    base: Option<&Path>,
+    mut config: GlobBuilder,
+) -> Result<Paths, PatternError> {
+    #[cfg(windows)]
+    fn is_verbatim(p: &Path) -> bool {
+        match p.components().next() {
+            Some(Component::Prefix(ref p)) => p.kind().is_verbatim(),
+            _ => false,
+        }
+    }
+    #[cfg(not(windows))]
+    fn is_verbatim(_: &Path) -> bool {
+        false
+    }
+
+    // The offset in `root` of a metacharacter in the server or share name of
+    // a UNC path, which can't be listed, so they have to be literal.
+    #[cfg(windows)]
+    fn share_wildcard(root: &Path) -> Option<usize> {
+        use std::path::Prefix;
+
+        let (prefix, start) = match root.components().next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::UNC(..) => (prefix, 2),
+                Prefix::VerbatimUNC(..) => (prefix, 8),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let prefix = prefix.as_os_str().to_str()?;
+        prefix[start..]
+            .find(|c| c == '*' || c == '?' || c == '[')
+            .map(|i| start + i)
+    }
+    #[cfg(not(windows))]
+    fn share_wildcard(_: &Path) -> Option<usize> {
+        None
+    }
+
+    // The roots of the drives that a drive letter with wildcards like the
+    // `[cd]:` of `[cd]:\*` matches, and the length of the pattern up to the
+    // separator after it, or `None` if the pattern doesn't start with one.
+    #[cfg(windows)]
+    fn drive_wildcard(
+        pattern: &str,
+        config: &GlobBuilder,
+    ) -> Result<Option<(Vec<PathBuf>, usize)>, PatternError> {
+        let colon = match pattern.find(':') {
+            Some(colon) => colon,
+            None => return Ok(None),
+        };
+        let letter = &pattern[..colon];
+        let rooted = pattern[colon + 1..]
+            .chars()
+            .next()
+            .map_or(false, path::is_separator);
+        let is_wildcard = letter.contains(|c| c == '*' || c == '?' || c == '[');
+        if !rooted || !is_wildcard || letter.contains(path::is_separator) {
+            return Ok(None);
+        }
+        let letter = Pattern::new_with(letter, config.parse_options)?;
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..config.options
+        };
+        let drives = (b'A'..b'Z' + 1)
+            .map(|c| PathBuf::from(format!("{}:\\", c as char)))
+            .filter(|root| {
+                letter.matches_path_with(Path::new(&root.to_str().unwrap()[..1]), options)
+                    && config.fs().metadata(root).ok() == Some(FileKind::Dir)
+            })
+            .collect();
+        Ok(Some((drives, colon + 2)))
+    }
+    #[cfg(not(windows))]
+    fn drive_wildcard(
+        _: &str,
+        _: &GlobBuilder,
+    ) -> Result<Option<(Vec<PathBuf>, usize)>, PatternError> {
+        Ok(None)
+    }
+
+    #[cfg(windows)]
+    fn to_scope(p: &Path) -> PathBuf {
+        // FIXME handle volume relative paths here
+        p.to_path_buf()
+    }
+    #[cfg(not(windows))]
+    fn to_scope(p: &Path) -> PathBuf {
+        p.to_path_buf()
+    }
+
+    // make sure that the pattern is valid first, else early return with error
+    let _ = Pattern::new_with(pattern, config.parse_options)?;
+    if config.parse_options.braces {
+        braces::check_components(pattern, config.parse_options)?;
+    }
+
+    // an empty base is the current directory, as for `Path::join`
+    let base = match base {
+        Some(base) if base.as_os_str().is_empty() => None,
+        base => base,
+    };
+    let mut components = Path::new(pattern).components().peekable();
+    while let Some(&component) = components.peek() {
+        match component {
+            Component::Prefix(..) | Component::RootDir => {
+                components.next();
+            }
+            _ => break,
+        }
+    }
+    let rest = components.map(|s| s.as_os_str()).collect::<PathBuf>();
+    let normalized_pattern = Path::new(pattern).iter().collect::<PathBuf>();
+    let root_len = normalized_pattern.to_str().unwrap().len() - rest.to_str().unwrap().len();
+    let root = if root_len > 0 {
+        Some(Path::new(&pattern[..root_len]))
+    } else {
+        None
+    };
+
+    if let Some(pos) = root.and_then(share_wildcard) {
+        return Err(PatternError {
+            pos: pattern[..pos].chars().count(),
+            msg: ERROR_WILDCARD_SHARE,
+            kind: PatternErrorKind::Syntax,
+        });
+    }
+
+    // a drive letter with wildcards isn't a prefix, so the search starts
+    // from each of the drives it matches instead
+    let drives = drive_wildcard(pattern, &config)?;
+    let root_len = drives.as_ref().map_or(root_len, |&(_, len)| len);
+
+    if config.auto_case_sensitive {
+        // each drive is probed, and if they disagree the options are left
+        // alone
+        let dirs = match drives {
+            Some((ref drives, _)) => drives.clone(),
+            None => {
+                let literal = literal_base(pattern, config.parse_options);
+                vec![match base {
+                    Some(base) => base.join(literal),
+                    None => literal,
+                }]
+            }
+        };
+        let probed: Vec<Option<bool>> = dirs
+            .iter()
+            .map(|dir| case::probe_nearest(config.fs(), dir))
+            .collect();
+        if let Some(&Some(case_sensitive)) = probed.first() {
+            if probed.iter().all(|&p| p == Some(case_sensitive)) {
+                config.options.case_sensitive = case_sensitive;
+            }
+        }
+    }
+
+    if config.confine {
+        if root_len > 0 {
+            return Err(PatternError {
+                pos: 0,
+                msg: ERROR_ESCAPES_ROOT,
+                kind: PatternErrorKind::EscapesRoot,
+            });
+        }
+        let mut offset = 0;
+        for component in pattern.split(path::is_separator) {
+            if component == ".." {
+                return Err(PatternError {
+                    pos: pattern[..offset].chars().count(),
+                    msg: ERROR_ESCAPES_ROOT,
+                    kind: PatternErrorKind::EscapesRoot,
+                });
+            }
+            offset += component.len() + 1;
+        }
+    }
+
+    let scope = match (root, base) {
+        (Some(root), _) => to_scope(root),
+        (None, Some(base)) => base.to_path_buf(),
+        (None, None) => PathBuf::from("."),
+    };
+    let prefix = match config.relative_to {
+        // paths on different drives have no root in common
+        RelativeTo::Root if drives.is_some() => None,
+        RelativeTo::Root => Some(scope.clone()),
+        RelativeTo::LiteralBase => {
+            let literal = literal_base(pattern, config.parse_options);
+            Some(match base {
+                Some(base) => base.join(literal),
+                None => literal,
+            })
+        }
+        _ => None,
+    };
+    let scopes = match drives {
+        Some((drives, _)) => drives,
+        None => vec![scope],
+    };
+    let scopes = scopes
+        .into_iter()
+        .map(|scope| PathWrapper::from_path(scope, config.fs()))
+        .collect();
+
+    let mut dir_patterns = Vec::new();
+    // the root of a verbatim path like `\\?\C:\` is taken literally, and
+    // only `\` separates the components after it
+    let verbatim = root.map_or(false, is_verbatim);
+    let is_separator = |c: char| {
+        if verbatim {
+            c == '\\'
+        } else {
+            path::is_separator(c)
+        }
+    };
+    let mut offset = cmp::min(root_len, pattern.len());
+    let mut components = pattern[offset..].split_terminator(is_separator).peekable();
+
+    while let Some(component) = components.next() {
+        let compiled = Pattern::new_with(component, config.parse_options)?;
+        if compiled.alternatives.iter().any(|p| p.is_recursive) {
+            return Err(PatternError {
+                pos: pattern[..offset].chars().count(),
+                msg: ERROR_RECURSIVE_ALTERNATIVE,
+                kind: PatternErrorKind::Syntax,
+            });
+        }
+        // a `**` that has to match a component is a `*/**`, though a trailing
+        // one never yields the directory it starts from anyway
+        let follows_recursive = dir_patterns
+            .last()
+            .map_or(false, |p: &Pattern| p.is_recursive);
+        if compiled.is_recursive
+            && config.options.require_recursive_component
+            && !follows_recursive
+            && components.peek().is_some()
+        {
+            dir_patterns.push(Pattern::new("*").unwrap());
+        }
+        dir_patterns.push(compiled);
+        offset += component.len() + 1;
+    }
+
+    if root_len == pattern.len() {
+        dir_patterns.push(Pattern {
+            original: "".to_string(),
+            tokens: Vec::new(),
+            is_recursive: false,
+            alternatives: Vec::new(),
+            options: None,
+        });
+    }
+
+    let last_is_separator = pattern.chars().next_back().map(path::is_separator);
+    let require_dir = last_is_separator == Some(true);
+    let todo = Vec::with_capacity(config.todo_capacity);
+
+    Ok(Paths {
+        dir_patterns,
+        require_dir,
+        prefix,
+        config,
+        todo,
+        scopes,
+        state: WalkState::default(),
+    })
+}
+
+/// A glob iteration error.
+///
+/// This is typically returned when a particular path cannot be read
+/// to determine if its contents match the glob pattern. This is possible
+/// if the program lacks the appropriate permissions, for example.
+#[derive(Debug)]
+pub struct GlobError {
+    path: PathBuf,
+    error: io::Error,
+    kind: GlobErrorKind,
+}
+
+/// The category of a `GlobError`, for deciding how to handle it without
+/// inspecting its message.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum GlobErrorKind {
+    /// The path couldn't be accessed due to a lack of permissions.
+    PermissionDenied,
+
+    /// The path was expected to be a directory but isn't one.
+    NotADirectory,
+
+    /// Resolving the path ran into too many levels of symbolic links,
+    /// usually because of a cycle.
+    Loop,
+
+    /// `**` reached a directory through more symbolic links than allowed by
+    /// `GlobBuilder::max_link_depth`.
+    TooManyLinks,
+
+    /// Reading the directory took longer than allowed by
+    /// `GlobBuilder::read_timeout`.
+    Timeout,
+
+    /// The budget set by `GlobBuilder::max_entries` was exceeded. This is
+    /// always the last item yielded.
+    TooManyResults,
+
+    /// The path is a symbolic link whose target doesn't exist and
+    /// `BrokenSymlinks::Error` is in effect.
+    BrokenSymlink,
+
+    /// The search was cancelled through `GlobBuilder::cancel_flag` before it
+    /// got to the path. This is always the last item yielded.
+    Cancelled,
+
+    /// Any other I/O error, of the given kind.
+    Io(io::ErrorKind),
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl GlobErrorKind {
+    fn classify(error: &io::Error) -> Self {
+        // std has no `ErrorKind`s for these that are usable here
+        #[cfg(unix)]
+        const NOT_A_DIRECTORY: i32 = 20; // ENOTDIR
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        const LOOP: i32 = 40; // ELOOP
+        #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+        const LOOP: i32 = 62; // ELOOP on the BSDs and macOS
+        #[cfg(windows)]
+        const NOT_A_DIRECTORY: i32 = 267; // ERROR_DIRECTORY
+        #[cfg(windows)]
+        const LOOP: i32 = 1921; // ERROR_CANT_RESOLVE_FILENAME
+        #[cfg(not(any(unix, windows)))]
+        const NOT_A_DIRECTORY: i32 = -1;
+        #[cfg(not(any(unix, windows)))]
+        const LOOP: i32 = -1;
+
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => return GlobErrorKind::PermissionDenied,
+            io::ErrorKind::TimedOut => return GlobErrorKind::Timeout,
+            _ => (),
+        }
+        match error.raw_os_error() {
+            Some(code) if code == NOT_A_DIRECTORY => GlobErrorKind::NotADirectory,
+            Some(code) if code == LOOP => GlobErrorKind::Loop,
+            _ => GlobErrorKind::Io(error.kind()),
+        }
+    }
+}
+
+impl GlobError {
+    // Constructs an error of the kind `error` implies.
+    fn new(path: PathBuf, error: io::Error) -> Self {
+        let kind = GlobErrorKind::classify(&error);
+        Self { path, error, kind }
+    }
+
+    fn with_kind(path: PathBuf, kind: GlobErrorKind, error: io::Error) -> Self {
+        Self { path, error, kind }
+    }
+
+    /// The Path that the error corresponds to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The category of the error.
+    pub fn kind(&self) -> GlobErrorKind {
+        self.kind
+    }
+
+    /// The error in question.
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Consumes self, returning the _raw_ underlying `io::Error`
+    pub fn into_error(self) -> io::Error {
+        self.error
+    }
+}
+
+impl Error for GlobError {
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        self.error.description()
+    }
+
+    #[allow(unknown_lints, bare_trait_objects)]
+    fn cause(&self) -> Option<&Error> {
+        Some(&self.error)
+    }
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "attempting to read `{}` resulted in an error: {}",
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PathWrapper {
+    path: PathBuf,
+    is_directory: bool,
+    is_symlink: bool,
+    // number of symbolic links to directories above this path
+    links: usize,
+    // number of components below the directory the search starts from
+    depth: usize,
+}
+
+impl PathWrapper {
+    fn from_dir_entry(path: PathBuf, e: DirEntry) -> Self {
+        let file_type = e.file_type().ok();
+        let is_symlink = file_type.map_or(false, |file_type| file_type.is_symlink());
+        let is_directory = file_type
+            .and_then(|file_type| {
+                // We need to use fs::metadata to resolve the actual path
+                // if it's a symlink.
+                if file_type.is_symlink() {
+                    None
+                } else {
+                    Some(file_type.is_dir())
+                }
+            })
+            .or_else(|| fs::metadata(&path).map(|m| m.is_dir()).ok())
+            .unwrap_or(false);
+        Self {
+            path,
+            is_directory,
+            is_symlink,
+            links: 0,
+            depth: 0,
+        }
+    }
+    fn from_path<F: ?Sized + FileSystem>(path: PathBuf, fs: &F) -> Self {
+        let is_directory = fs
+            .metadata(&path)
+            .map(|k| k == FileKind::Dir)
+            .unwrap_or(false);
+        let is_symlink = fs
+            .symlink_metadata(&path)
+            .map(|k| k == FileKind::Symlink)
+            .unwrap_or(false);
+        Self {
+            path,
+            is_directory,
+            is_symlink,
+            links: 0,
+            depth: 0,
+        }
+    }
+
+    fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+impl Deref for PathWrapper {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        self.path.deref()
+    }
+}
+
+impl AsRef<Path> for PathWrapper {
+    fn as_ref(&self) -> &Path {
+        self.path.as_ref()
+    }
+}
+
+/// An alias for a glob iteration result.
+///
+/// This represents either a matched path or a glob iteration error,
+/// such as failing to read a particular directory's contents.
+pub type GlobResult = Result<PathBuf, GlobError>;
+
+impl Iterator for Paths {
+    type Item = GlobResult;
+
+    fn next(&mut self) -> Option<GlobResult> {
+        match self.next_match() {
+            Some(Ok(path)) => Some(self.finish(path)),
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+}
+
+impl Paths {
+    // Finds the next match, without turning it into a result yet.
+    fn next_match(&mut self) -> Option<Result<PathWrapper, GlobError>> {
+        let limit = self.config.max_results;
+        if limit.map_or(false, |max| self.state.matches >= max) {
+            return None;
+        }
+        let next = self.next_filtered();
+        if let Some(Ok(_)) = next {
+            self.state.matches += 1;
+            if limit == Some(self.state.matches) {
+                // everything that's left is of no use
+                self.state.exhausted = true;
+                self.todo = Vec::new();
+            }
+        }
+        next
+    }
+
+    // Finds the next match that isn't filtered out by the configuration.
+    fn next_filtered(&mut self) -> Option<Result<PathWrapper, GlobError>> {
+        loop {
+            let path = match self.next_candidate() {
+                Some(Ok(path)) => path,
+                other => return other,
+            };
+
+            let excluded = self.config.min_depth.map_or(false, |min| path.depth < min)
+                || match self.config.file_kind {
+                    Some(FileKind::Dir) => !path.is_directory,
+                    Some(FileKind::File) => path.is_directory || path.is_symlink,
+                    Some(FileKind::Symlink) => !path.is_symlink,
+                    _ => false,
+                };
+            if excluded {
+                self.state.skip(path.into_path(), SkipReason::Excluded);
+                continue;
+            }
+
+            let policy = self.config.broken_symlinks;
+            if policy != BrokenSymlinks::Include
+                && path.is_symlink
+                && !path.is_directory
+                && self.config.fs().metadata(&path).is_err()
+            {
+                if policy == BrokenSymlinks::Error {
+                    return Some(Err(GlobError::with_kind(
+                        path.into_path(),
+                        GlobErrorKind::BrokenSymlink,
+                        io::Error::new(io::ErrorKind::NotFound, "broken symbolic link"),
+                    )));
+                }
+                self.state.skip(path.into_path(), SkipReason::BrokenSymlink);
+                continue;
+            }
+
+            match self.config.filter_metadata(&path) {
+                Ok(true) => return Some(Ok(path)),
+                Ok(false) => self.state.skip(path.into_path(), SkipReason::Excluded),
+                Err(error) => return Some(Err(GlobError::new(path.into_path(), error))),
+            }
+        }
+    }
+
+    // Finds the next path that matches the pattern.
+    fn next_candidate(&mut self) -> Option<Result<PathWrapper, GlobError>> {
+        // the todo buffer hasn't been initialized yet, so it's done at this
+        // point rather than in glob() so that the errors are unified that is,
+        // failing to fill the buffer is an iteration error construction of the
+        // iterator (i.e. glob()) only fails if it fails to compile the Pattern
+        let scopes = mem::replace(&mut self.scopes, Vec::new());
+        if !scopes.is_empty() && !self.dir_patterns.is_empty() {
+            // Shouldn't happen, but we're using -1 as a special index.
+            assert!(self.dir_patterns.len() < std::usize::MAX);
+
+            // the todo buffer is a stack, so the first scope goes in last
+            for scope in scopes.into_iter().rev() {
+                if self.config.confine {
+                    self.state.root = self.config.fs().canonicalize(&scope).ok();
+                }
+                self.state.scope = scope.to_path_buf();
+                fill_todo(
+                    &mut self.todo,
+                    &self.dir_patterns,
+                    0,
+                    &scope,
+                    &self.config,
+                    &mut self.state,
+                );
+            }
+        }
+
+        loop {
+            if self.dir_patterns.is_empty() || self.todo.is_empty() {
+                return None;
+            }
+
+            let next = self.todo.pop().unwrap();
+            if self.config.is_cancelled() && !self.state.exhausted {
+                let path = match next {
+                    Ok((path, _)) => path.into_path(),
+                    Err(e) => e.path,
+                };
+                cancel(&mut self.todo, &mut self.state, path);
+                continue;
+            }
+            if let Some(threshold) = self.config.shrink_threshold {
+                if self.todo.capacity() - self.todo.len() > threshold {
+                    self.todo.shrink_to_fit();
+                }
+            }
+            let (path, mut idx) = match next {
                 Ok(pair) => pair,
                 Err(e) => return Some(Err(e)),
             };
@@ -410,9 +2019,10 @@ impl Iterator for Paths {
             // '..' that we can't match here because of normalization.
             if idx == std::usize::MAX {
                 if self.require_dir && !path.is_directory {
+                    self.state.skip(path.into_path(), SkipReason::NotADirectory);
                     continue;
                 }
-                return Some(Ok(path.into_path()));
+                return Some(Ok(path));
             }
 
             if self.dir_patterns[idx].is_recursive {
@@ -427,27 +2037,65 @@ impl Iterator for Paths {
 
                 if path.is_directory {
                     // the path is a directory, so it's a match
-
-                    // push this directory's contents
-                    fill_todo(
-                        &mut self.todo,
-                        &self.dir_patterns,
-                        next,
-                        &path,
-                        self.options,
-                    );
+                    let hidden = path
+                        .file_name()
+                        .map_or(false, |name| os_str_lossy(name).starts_with('.'));
+
+                    // push this directory's contents, unless it was reached
+                    // through a link we were asked not to follow, or through
+                    // too many links
+                    let too_many_links = path.is_symlink
+                        && self
+                            .config
+                            .max_link_depth
+                            .map_or(false, |max| path.links >= max);
+                    if too_many_links {
+                        self.todo.push(Err(GlobError::with_kind(
+                            path.to_path_buf(),
+                            GlobErrorKind::TooManyLinks,
+                            io::Error::new(
+                                io::ErrorKind::Other,
+                                "too many levels of symbolic links",
+                            ),
+                        )));
+                    } else if (self.config.recursive_follow_links || !path.is_symlink)
+                        && !(hidden && self.config.descend_hidden == Some(false))
+                    {
+                        match self.config.may_descend(&path, true) {
+                            Ok(true) => fill_todo(
+                                &mut self.todo,
+                                &self.dir_patterns,
+                                next,
+                                &path,
+                                &self.config,
+                                &mut self.state,
+                            ),
+                            Ok(false) => (),
+                            Err(e) => self.todo.push(Err(e)),
+                        }
+                    }
 
                     if next == self.dir_patterns.len() - 1 {
                         // pattern ends in recursive pattern, so return this
-                        // directory as a result
-                        return Some(Ok(path.into_path()));
+                        // directory as a result, unless it's a hidden one
+                        // that was only searched
+                        if hidden && self.config.options.require_literal_leading_dot {
+                            self.state.skip(path.into_path(), SkipReason::Hidden);
+                            continue;
+                        }
+                        return Some(Ok(path));
                     } else {
                         // advanced to the next pattern for this path
                         idx = next + 1;
                     }
                 } else if next == self.dir_patterns.len() - 1 {
                     // not a directory and it's the last pattern, meaning no
-                    // match
+                    // match unless we were asked to yield files too
+                    if self.config.options.trailing_recursive_matches_files && !self.require_dir {
+                        return Some(Ok(path));
+                    }
+                    self.state
+                        .skip(path.into_path(), SkipReason::Mismatch(next));
                     continue;
                 } else {
                     // advanced to the next pattern for this path
@@ -456,37 +2104,199 @@ impl Iterator for Paths {
             }
 
             // not recursive, so match normally
-            if self.dir_patterns[idx].matches_with(
-                {
-                    match path.file_name().and_then(|s| s.to_str()) {
-                        // FIXME (#9639): How do we handle non-utf8 filenames?
-                        // Ignore them for now; ideally we'd still match them
-                        // against a *
-                        None => continue,
-                        Some(x) => x,
-                    }
-                },
-                self.options,
-            ) {
+            let matched = match path.file_name().and_then(|s| s.to_str()) {
+                // FIXME (#9639): How do we handle non-utf8 filenames?
+                // Ignore them for now; ideally we'd still match them
+                // against a *
+                None => {
+                    self.state
+                        .skip(path.to_path_buf(), SkipReason::Mismatch(idx));
+                    continue;
+                }
+                Some(name) => self.state.memo.matches(
+                    &path,
+                    name,
+                    self.todo.len(),
+                    &self.dir_patterns,
+                    idx,
+                    self.config.options,
+                ),
+            };
+            if matched {
                 if idx == self.dir_patterns.len() - 1 {
                     // it is not possible for a pattern to match a directory
                     // *AND* its children so we don't need to check the
                     // children
 
                     if !self.require_dir || path.is_directory {
-                        return Some(Ok(path.into_path()));
+                        return Some(Ok(path));
                     }
+                    self.state.skip(path.into_path(), SkipReason::NotADirectory);
                 } else {
-                    fill_todo(
-                        &mut self.todo,
-                        &self.dir_patterns,
-                        idx + 1,
-                        &path,
-                        self.options,
-                    );
+                    match self.config.may_descend(&path, false) {
+                        Ok(true) => fill_todo(
+                            &mut self.todo,
+                            &self.dir_patterns,
+                            idx + 1,
+                            &path,
+                            &self.config,
+                            &mut self.state,
+                        ),
+                        Ok(false) => self.state.skip(path.into_path(), SkipReason::Excluded),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+            } else {
+                self.state.skip(path.into_path(), SkipReason::Mismatch(idx));
+            }
+        }
+    }
+    // Turns a match into the result that is yielded for it.
+    fn finish(&self, path: PathWrapper) -> GlobResult {
+        if !self.config.resolve_symlinks {
+            let path = path.into_path();
+            return Ok(match self.prefix {
+                Some(ref prefix) => relative_path(path, prefix),
+                None => path,
+            });
+        }
+        self.config
+            .fs()
+            .canonicalize(&path)
+            .map_err(|error| GlobError::new(path.into_path(), error))
+    }
+
+    /// Converts this iterator into one that yields every entry visited while
+    /// searching for matches, tagged with whether it matched or why it was
+    /// skipped. This should be called before iteration starts; entries that
+    /// were already visited are not reported.
+    ///
+    /// See `TaggedPaths` for more details.
+    pub fn tagged(mut self) -> TaggedPaths {
+        self.state.skipped = Some(VecDeque::new());
+        TaggedPaths::new(self)
+    }
+
+    /// Consumes this iterator, returning all of the matches and all of the
+    /// errors separately, each in the order they were yielded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::glob;
+    ///
+    /// let (paths, errors) = glob("**/*.md").unwrap().partition_results();
+    /// println!("{} matches, {} unreadable", paths.len(), errors.len());
+    /// ```
+    pub fn partition_results(self) -> (Vec<PathBuf>, Vec<GlobError>) {
+        let mut errors = Vec::new();
+        let paths = self.partition_into(&mut errors).collect();
+        (paths, errors)
+    }
+
+    /// Converts this iterator into one that only yields matches, pushing
+    /// errors onto `errors` as they are encountered.
+    ///
+    /// See `PartitionedPaths` for more details.
+    pub fn partition_into<'a>(self, errors: &'a mut Vec<GlobError>) -> PartitionedPaths<'a> {
+        PartitionedPaths::new(self, errors)
+    }
+
+    /// Converts this iterator into one that also yields the target of each
+    /// match that is a symbolic link.
+    ///
+    /// See `LinkTargets` for more details.
+    pub fn with_link_targets(self) -> LinkTargets {
+        LinkTargets::new(self)
+    }
+
+    /// Converts this iterator into one that also yields the kind of each
+    /// match, as found while searching, sparing callers from reading its
+    /// metadata again.
+    ///
+    /// See `FileKinds` for more details.
+    pub fn with_file_kinds(self) -> FileKinds {
+        FileKinds::new(self)
+    }
+
+    /// Converts this iterator into one that also computes a fingerprint of
+    /// the matches it yields, so that callers can cheaply tell whether the
+    /// set of matching files changed since a previous run.
+    ///
+    /// See `FingerprintedPaths` for more details.
+    pub fn fingerprinted(self) -> FingerprintedPaths {
+        FingerprintedPaths::new(self)
+    }
+
+    /// Consumes this iterator and records the size and modification time of
+    /// each match in a `Manifest`, so it can be diffed against the one of a
+    /// later run.
+    ///
+    /// The first error encountered, whether while iterating or while reading
+    /// the metadata of a match, is returned instead.
+    pub fn manifest(self) -> Result<Manifest, GlobError> {
+        Manifest::from_paths(self)
+    }
+
+    /// Converts this iterator into one that yields `camino`'s `Utf8PathBuf`s.
+    ///
+    /// This is only available with the `camino` feature; see
+    /// `utf8::Utf8Paths` for details.
+    #[cfg(feature = "camino")]
+    pub fn utf8(self) -> utf8::Utf8Paths {
+        utf8::Utf8Paths::new(self)
+    }
+
+    /// Converts this iterator into a stream that searches on `tokio`'s
+    /// blocking thread pool, and has to be called from within a `tokio`
+    /// runtime.
+    ///
+    /// This is only available with the `stream` feature; see
+    /// `stream::PathStream` for details.
+    #[cfg(feature = "stream")]
+    pub fn into_stream(self) -> stream::PathStream {
+        stream::PathStream::new(self)
+    }
+
+    /// Consumes this iterator, writing each match to `out` followed by
+    /// `terminator`, and returns the errors that were encountered along the
+    /// way.
+    ///
+    /// Paths are written as the raw bytes of their platform representation on
+    /// Unix, and as UTF-8 elsewhere, with unpaired surrogates replaced.
+    /// Terminating them with `b'\0'` rather than `b'\n'` keeps the output
+    /// unambiguous even for names that contain newlines, as with `find
+    /// -print0`. The output is buffered, and flushed before returning.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::glob;
+    /// use std::io;
+    ///
+    /// let stdout = io::stdout();
+    /// let errors = glob("**/*.rs")
+    ///     .unwrap()
+    ///     .write_matches_to(stdout.lock(), b'\0')
+    ///     .unwrap();
+    /// for error in errors {
+    ///     eprintln!("{}", error);
+    /// }
+    /// ```
+    pub fn write_matches_to<W: Write>(self, out: W, terminator: u8) -> io::Result<Vec<GlobError>> {
+        let mut out = io::BufWriter::new(out);
+        let mut errors = Vec::new();
+        for result in self {
+            match result {
+                Ok(path) => {
+                    out.write_all(&path_bytes(&path))?;
+                    out.write_all(&[terminator])?;
                 }
+                Err(error) => errors.push(error),
             }
         }
+        out.flush()?;
+        Ok(errors)
     }
 }
 
@@ -499,6 +2309,48 @@ pub struct PatternError {
 
     /// A message describing the error.
     pub msg: &'static str,
+
+    kind: PatternErrorKind,
+}
+
+impl PatternError {
+    /// The category of the error.
+    pub fn kind(&self) -> PatternErrorKind {
+        self.kind
+    }
+}
+
+/// The category of a `PatternError`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PatternErrorKind {
+    /// The pattern is not valid glob syntax.
+    Syntax,
+
+    /// The pattern is longer than `ParseOptions::max_len` allows.
+    TooLong,
+
+    /// The pattern contains more tokens than `ParseOptions::max_tokens`
+    /// allows.
+    TooManyTokens,
+
+    /// A `[...]` expression contains more entries than
+    /// `ParseOptions::max_class_len` allows.
+    ClassTooLarge,
+
+    /// The pattern contains more wildcards than `ParseOptions::max_wildcards`
+    /// allows.
+    TooManyWildcards,
+
+    /// The braces in the pattern expand to more alternatives than
+    /// `ParseOptions::max_alternatives`, or the limit of 100,000, allows.
+    TooManyAlternatives,
+
+    /// The pattern could refer to paths outside the directory that
+    /// `GlobBuilder::confine` restricts it to.
+    EscapesRoot,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
 }
 
 impl Error for PatternError {
@@ -525,7 +2377,9 @@ impl fmt::Display for PatternError {
 ///
 /// - `**` matches the current directory and arbitrary
 ///   subdirectories. To match files in arbitrary subdiretories, use
-///   `**/*`.
+///   `**/*`. A pattern ending in `**/` only matches directories, i.e.
+///   paths ending in a separator, so `src/**/` matches `src/a/` but not
+///   `src/a.rs`.
 ///
 ///   This sequence **must** form a single path component, so both
 ///   `**a` and `b**` are invalid and will result in an error.  A
@@ -540,6 +2394,9 @@ impl fmt::Display for PatternError {
 /// - `[!...]` is the negation of `[...]`, i.e. it matches any characters
 ///   **not** in the brackets.
 ///
+/// - `{a,b}` matches either `a` or `b`, if braces are enabled with
+///   `ParseOptions::braces`. Otherwise braces are literal.
+///
 /// - The metacharacters `?`, `*`, `[`, `]` can be matched by using brackets
 ///   (e.g. `[?]`).  When a `]` occurs immediately following `[` or `[!` then it
 ///   is interpreted as being part of, rather then ending, the character set, so
@@ -551,6 +2408,10 @@ pub struct Pattern {
     original: String,
     tokens: Vec<PatternToken>,
     is_recursive: bool,
+    // the patterns that braces expanded to, in which case `tokens` is empty
+    alternatives: Vec<Pattern>,
+    // the options given to `with_options`, if any
+    options: Option<MatchOptions>,
 }
 
 /// Show the original glob pattern.
@@ -591,43 +2452,199 @@ enum MatchResult {
     EntirePatternDoesntMatch,
 }
 
+// How far `Pattern::run_from` got: to the end of a match attempt, or to a
+// wildcard, along with the rest of the input and whether it follows a
+// separator.
+enum Run<'a> {
+    Done(MatchResult),
+    Wildcard(usize, std::str::Chars<'a>, bool),
+}
+
 const ERROR_WILDCARDS: &str = "wildcards are either regular `*` or recursive `**`";
 const ERROR_RECURSIVE_WILDCARDS: &str = "recursive wildcards must form a single path \
                                          component";
 const ERROR_INVALID_RANGE: &str = "invalid range pattern";
+const ERROR_TOO_LONG: &str = "pattern exceeds the maximum length";
+const ERROR_TOO_MANY_TOKENS: &str = "pattern has too many tokens";
+const ERROR_TOO_MANY_WILDCARDS: &str = "pattern has too many wildcards";
+const ERROR_CLASS_TOO_LARGE: &str = "range pattern has too many entries";
+const ERROR_RECURSIVE_ALTERNATIVE: &str = "recursive wildcards can't be alternatives when globbing";
+const ERROR_WILDCARD_SHARE: &str = "the server and share of a UNC path can't contain wildcards";
+const ERROR_ESCAPES_ROOT: &str = "pattern must be relative and must not contain `..`";
 
 impl Pattern {
     /// This function compiles Unix shell style patterns.
     ///
     /// An invalid glob pattern will yield a `PatternError`.
     pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        Self::new_with(pattern, ParseOptions::new())
+    }
+
+    /// This function compiles Unix shell style patterns using the specified
+    /// parse options.
+    ///
+    /// An invalid glob pattern, or one that exceeds a limit set in `options`,
+    /// will yield a `PatternError`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{ParseOptions, Pattern, PatternErrorKind};
+    ///
+    /// let options = ParseOptions::new().max_len(Some(8));
+    /// assert!(Pattern::new_with("*.txt", options).is_ok());
+    ///
+    /// let err = Pattern::new_with("**/*.tar.gz", options).unwrap_err();
+    /// assert_eq!(err.kind(), PatternErrorKind::TooLong);
+    /// ```
+    pub fn new_with(pattern: &str, options: ParseOptions) -> Result<Self, PatternError> {
+        Self::parse(pattern, options, false)
+    }
+
+    /// This function compiles Unix shell style patterns like `new`, except
+    /// that a `**` that isn't a path component of its own, or a run of more
+    /// than two `*`s, is treated as a single `*` the way bash does without
+    /// `globstar`, rather than being an error.
+    ///
+    /// This is a shorthand for `new_with` with
+    /// `ParseOptions::lenient_recursive_wildcards` set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let pattern = Pattern::new_lenient("foo**bar").unwrap();
+    /// assert!(pattern.matches("foo-bar"));
+    /// assert!(Pattern::new("foo**bar").is_err());
+    /// ```
+    pub fn new_lenient(pattern: &str) -> Result<Self, PatternError> {
+        Self::new_with(
+            pattern,
+            ParseOptions::new().lenient_recursive_wildcards(true),
+        )
+    }
+
+    /// This function compiles Unix shell style patterns like `new`, except
+    /// that it never fails: constructs that aren't valid are matched
+    /// literally instead, the way many shells treat them.
+    ///
+    /// This suits tools that accept text that may be either a pattern or a
+    /// file name. An unclosed `[` stands for itself, and so does a `**` that
+    /// isn't a path component of its own, or a run of more than two `*`s.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let pattern = Pattern::new_lossy("notes[1.txt");
+    /// assert!(pattern.matches("notes[1.txt"));
+    ///
+    /// let pattern = Pattern::new_lossy("a**b/*.rs");
+    /// assert!(pattern.matches("a**b/lib.rs"));
+    /// ```
+    pub fn new_lossy(pattern: &str) -> Self {
+        match Self::parse(pattern, ParseOptions::new(), true) {
+            Ok(pattern) => pattern,
+            // without limits, only syntax errors are possible
+            Err(_) => unreachable!(),
+        }
+    }
+
+    // Compiles `pattern`, matching invalid constructs literally if `lossy` is
+    // set rather than failing.
+    fn parse(pattern: &str, options: ParseOptions, lossy: bool) -> Result<Self, PatternError> {
+        if let Some(max_len) = options.max_len {
+            if pattern.len() > max_len {
+                return Err(PatternError {
+                    pos: pattern
+                        .char_indices()
+                        .take_while(|&(idx, _)| idx < max_len)
+                        .count(),
+                    msg: ERROR_TOO_LONG,
+                    kind: PatternErrorKind::TooLong,
+                });
+            }
+        }
+
+        if options.braces {
+            let expanded = braces::expand(pattern, options)?;
+            if expanded.len() != 1 || expanded[0] != pattern {
+                let options = options.braces(false);
+                let mut alternatives = Vec::new();
+                for alternative in &expanded {
+                    alternatives.push(Self::parse(alternative, options, lossy)?);
+                }
+                return Ok(Self {
+                    original: pattern.to_string(),
+                    tokens: Vec::new(),
+                    is_recursive: false,
+                    alternatives,
+                    options: None,
+                });
+            }
+        }
+
         let chars = pattern.chars().collect::<Vec<_>>();
         let mut tokens = Vec::new();
         let mut is_recursive = false;
+        let mut wildcards = 0;
         let mut i = 0;
 
         while i < chars.len() {
+            if options.max_tokens.map_or(false, |max| tokens.len() >= max) {
+                return Err(PatternError {
+                    pos: i,
+                    msg: ERROR_TOO_MANY_TOKENS,
+                    kind: PatternErrorKind::TooManyTokens,
+                });
+            }
+
             match chars[i] {
                 '?' => {
+                    check_wildcards(&mut wildcards, i, options)?;
                     tokens.push(AnyChar);
                     i += 1;
                 }
                 '*' => {
+                    check_wildcards(&mut wildcards, i, options)?;
                     let old = i;
 
                     while i < chars.len() && chars[i] == '*' {
                         i += 1;
                     }
 
+                    if options.disable_recursive_wildcards {
+                        tokens.push(AnySequence);
+                        continue;
+                    }
+
                     let count = i - old;
+                    let is_component = (old == 0 || path::is_separator(chars[old - 1]))
+                        && (i == chars.len() || path::is_separator(chars[i]));
 
                     match count.cmp(&2) {
+                        Ordering::Greater if lossy => {
+                            tokens.extend((0..count).map(|_| Char('*')));
+                        }
+                        Ordering::Greater if options.lenient_recursive_wildcards => {
+                            tokens.push(AnySequence);
+                        }
                         Ordering::Greater => {
                             return Err(PatternError {
                                 pos: old + 2,
                                 msg: ERROR_WILDCARDS,
+                                kind: PatternErrorKind::Syntax,
                             })
                         }
+                        Ordering::Equal if lossy && !is_component => {
+                            tokens.push(Char('*'));
+                            tokens.push(Char('*'));
+                        }
+                        Ordering::Equal if options.lenient_recursive_wildcards && !is_component => {
+                            tokens.push(AnySequence);
+                        }
                         Ordering::Equal => {
                             // ** can only be an entire path component
                             // i.e. a/**/b is valid, but a**/b or a/**b is not
@@ -646,6 +2663,7 @@ impl Pattern {
                                     return Err(PatternError {
                                         pos: i,
                                         msg: ERROR_RECURSIVE_WILDCARDS,
+                                        kind: PatternErrorKind::Syntax,
                                     });
                                 }
                             // `**` begins with non-separator
@@ -653,6 +2671,7 @@ impl Pattern {
                                 return Err(PatternError {
                                     pos: old - 1,
                                     msg: ERROR_RECURSIVE_WILDCARDS,
+                                    kind: PatternErrorKind::Syntax,
                                 });
                             };
 
@@ -674,22 +2693,29 @@ impl Pattern {
                     }
                 }
                 '[' => {
-                    if i + 4 <= chars.len() && chars[i + 1] == '!' {
+                    let negated = |c| c == '!' || (options.caret_negation && c == '^');
+                    if i + 4 <= chars.len() && negated(chars[i + 1]) {
                         match chars[i + 3..].iter().position(|x| *x == ']') {
                             None => (),
                             Some(j) => {
                                 let chars = &chars[i + 2..i + 3 + j];
-                                let cs = parse_char_specifiers(chars);
+                                let cs = parse_char_specifiers(chars, i + 2, options)?;
+                                check_class_len(&cs, i, options)?;
                                 tokens.push(AnyExcept(cs));
                                 i += j + 4;
                                 continue;
                             }
                         }
-                    } else if i + 3 <= chars.len() && chars[i + 1] != '!' {
+                    } else if i + 3 <= chars.len() && !negated(chars[i + 1]) {
                         match chars[i + 2..].iter().position(|x| *x == ']') {
                             None => (),
                             Some(j) => {
-                                let cs = parse_char_specifiers(&chars[i + 1..i + 2 + j]);
+                                let cs = parse_char_specifiers(
+                                    &chars[i + 1..i + 2 + j],
+                                    i + 1,
+                                    options,
+                                )?;
+                                check_class_len(&cs, i, options)?;
                                 tokens.push(AnyWithin(cs));
                                 i += j + 3;
                                 continue;
@@ -698,11 +2724,50 @@ impl Pattern {
                     }
 
                     // if we get here then this is not a valid range pattern
+                    if lossy {
+                        tokens.push(Char('['));
+                        i += 1;
+                        continue;
+                    }
                     return Err(PatternError {
                         pos: i,
                         msg: ERROR_INVALID_RANGE,
+                        kind: PatternErrorKind::Syntax,
                     });
                 }
+                '\\' if (options.shorthand_classes
+                    || options.backslash_escapes
+                    || options.unicode_classes)
+                    && i + 1 < chars.len() =>
+                {
+                    if options.unicode_classes {
+                        if let Some((cs, len)) = unicode::property_class(&chars, i)? {
+                            check_class_len(&cs, i, options)?;
+                            tokens.push(AnyWithin(cs));
+                            i += len;
+                            continue;
+                        }
+                    }
+                    let shorthand = if options.shorthand_classes {
+                        shorthand_class(chars[i + 1])
+                    } else {
+                        None
+                    };
+                    match shorthand {
+                        Some(cs) => {
+                            tokens.push(AnyWithin(cs));
+                            i += 2;
+                        }
+                        None if options.backslash_escapes => {
+                            tokens.push(Char(chars[i + 1]));
+                            i += 2;
+                        }
+                        None => {
+                            tokens.push(Char('\\'));
+                            i += 1;
+                        }
+                    }
+                }
                 c => {
                     tokens.push(Char(c));
                     i += 1;
@@ -714,19 +2779,249 @@ impl Pattern {
             tokens,
             original: pattern.to_string(),
             is_recursive,
+            alternatives: Vec::new(),
+            options: None,
         })
     }
 
+    /// Return a new `Pattern` that matches paths made up of a match of this
+    /// pattern followed by a match of `other`, as if they were joined with a
+    /// path separator.
+    ///
+    /// A separator is only inserted if neither pattern has one at the
+    /// boundary. The compiled patterns are joined as they are, so nothing is
+    /// escaped or parsed again, and patterns compiled with different
+    /// `ParseOptions` can be joined.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let base = Pattern::new("src/").unwrap();
+    /// let rule = Pattern::new("**/*.rs").unwrap();
+    /// assert_eq!(base.join(&rule).as_str(), "src/**/*.rs");
+    /// ```
+    pub fn join(&self, other: &Pattern) -> Pattern {
+        let original = PatternBuilder::glob(&self.original)
+            .then_glob(&other.original)
+            .as_str()
+            .to_string();
+        if self.alternatives.is_empty() && other.alternatives.is_empty() {
+            return self.join_tokens(other, original);
+        }
+        let mut alternatives = Vec::new();
+        for a in self.branches() {
+            for b in other.branches() {
+                let original = PatternBuilder::glob(&a.original)
+                    .then_glob(&b.original)
+                    .as_str()
+                    .to_string();
+                alternatives.push(a.join_tokens(b, original));
+            }
+        }
+        Pattern {
+            original,
+            tokens: Vec::new(),
+            is_recursive: false,
+            alternatives,
+            options: self.options,
+        }
+    }
+
+    // Joins the tokens of two patterns without alternatives, see `join`.
+    fn join_tokens(&self, other: &Pattern, original: String) -> Pattern {
+        let starts_with_separator = match other.tokens.first() {
+            Some(&Char(c)) => path::is_separator(c),
+            _ => false,
+        };
+        let mut tokens = self.tokens.clone();
+        let mut rest = &other.tokens[..];
+        match self.tokens.last() {
+            // the separator after `**` is part of the token
+            Some(&AnyRecursiveSequence) if starts_with_separator => rest = &rest[1..],
+            Some(&AnyRecursiveSequence) => (),
+            Some(&Char(c)) if path::is_separator(c) => (),
+            Some(_) if !starts_with_separator && !rest.is_empty() => tokens.push(Char('/')),
+            _ => (),
+        }
+        tokens.extend_from_slice(rest);
+        Pattern {
+            original,
+            tokens,
+            is_recursive: self.is_recursive || other.is_recursive,
+            alternatives: Vec::new(),
+            options: self.options,
+        }
+    }
+
+    /// Return a new `Pattern` that matches paths made up of a match of this
+    /// pattern followed by `segment`, matched literally, as if they were
+    /// joined with a path separator. See `join` for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let base = Pattern::new("out/*").unwrap();
+    /// let joined = base.join_path_segment("[1].log");
+    /// assert_eq!(joined.as_str(), "out/*/[[]1[]].log");
+    /// assert!(joined.matches("out/a/[1].log"));
+    /// ```
+    pub fn join_path_segment(&self, segment: &str) -> Pattern {
+        let literal = Pattern {
+            original: Pattern::escape(segment),
+            tokens: segment.chars().map(Char).collect(),
+            is_recursive: false,
+            alternatives: Vec::new(),
+            options: None,
+        };
+        self.join(&literal)
+    }
+
+    /// Return up to `limit` strings that this `Pattern` matches but `other`
+    /// doesn't using the specified match options, shortest first.
+    ///
+    /// This is meant for showing how two patterns behave differently, for
+    /// example when reviewing a change to a list of ignore rules. Every
+    /// string returned has been checked with `matches_with`. An empty result
+    /// means that `other` matches everything this pattern does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let old = Pattern::new("*.log").unwrap();
+    /// let new = Pattern::new("*.lo?").unwrap();
+    /// let options = MatchOptions::new();
+    /// assert!(old.difference(&new, options, 10).is_empty());
+    ///
+    /// let added = new.difference(&old, options, 10);
+    /// assert!(!added.is_empty());
+    /// for example in &added {
+    ///     assert!(new.matches(example) && !old.matches(example));
+    /// }
+    /// ```
+    pub fn difference(&self, other: &Pattern, options: MatchOptions, limit: usize) -> Vec<String> {
+        difference::examples(self, other, options, limit)
+    }
+
+    /// Return an iterator over every string this `Pattern` matches, or `None`
+    /// if there are infinitely many because it contains `*` or `**`.
+    ///
+    /// `?` and negated character classes like `[!a]` stand for characters
+    /// drawn from `alphabet`, while character classes like `[a-z]` stand for
+    /// exactly the characters they contain. Strings are yielded in
+    /// lexicographic order, without touching the filesystem, which is useful
+    /// for generating candidate file names.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let pattern = Pattern::new("config.[jt]s?n").unwrap();
+    /// let names: Vec<String> = pattern.enumerate(&['o']).unwrap().collect();
+    /// assert_eq!(names, vec!["config.json", "config.tson"]);
+    ///
+    /// assert!(Pattern::new("*.json").unwrap().enumerate(&[]).is_none());
+    /// ```
+    pub fn enumerate(&self, alphabet: &[char]) -> Option<Expansions> {
+        Expansions::new(self, alphabet)
+    }
+
+    /// Return an equivalent `Pattern` that matches case-insensitively even
+    /// with case-sensitive match options, for handing to tools that can't be
+    /// told to ignore case.
+    ///
+    /// Letters are rewritten as classes of both cases, so `a` becomes `[Aa]`,
+    /// and the other case of the letters in character classes is added to
+    /// them. Only letters with a single-character counterpart in the other
+    /// case are rewritten, and ranges only have their ASCII letters folded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let pattern = Pattern::new("*.[a-c]").unwrap().to_case_insensitive();
+    /// assert_eq!(pattern.as_str(), "*.[A-Ca-c]");
+    /// assert!(pattern.matches("lib.A"));
+    /// ```
+    pub fn to_case_insensitive(&self) -> Pattern {
+        rewrite::case_insensitive(self)
+    }
+
+    /// Return the pattern written in a form that behaves the same when it's
+    /// compiled on any platform, for storing in configuration that's shared
+    /// between them.
+    ///
+    /// Path separators, including `\` on Windows, are written as `/`, and
+    /// escaped metacharacters are written consistently as e.g. `[?]`. A
+    /// literal `\`, which can only occur on platforms where it isn't a
+    /// separator, is written as `[\]`; Windows still treats it as matching
+    /// either separator, since the two are interchangeable there.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let pattern = Pattern::new("src/**/[*]*.rs").unwrap();
+    /// assert_eq!(pattern.to_portable_string(), "src/**/[*]*.rs");
+    /// ```
+    pub fn to_portable_string(&self) -> String {
+        rewrite::pattern_to_string(self, true)
+    }
+
     /// Escape metacharacters within the given string by surrounding them in
     /// brackets. The resulting string will, when compiled into a `Pattern`,
-    /// match the input string and nothing else.
+    /// match the input string and nothing else, whether or not braces are
+    /// enabled.
+    ///
+    /// See `escape_with` for patterns compiled with other `ParseOptions`.
     pub fn escape(s: &str) -> String {
+        Self::escape_with(s, ParseOptions::new())
+    }
+
+    /// Escape metacharacters within the given string so that, when compiled
+    /// into a `Pattern` with the given parse options, it matches the input
+    /// string and nothing else.
+    ///
+    /// This differs from `escape` in how a `\` is written when the options
+    /// give it a meaning: it's doubled if `backslash_escapes` is set, and
+    /// otherwise, if `shorthand_classes` or `unicode_classes` is set, a letter
+    /// after it that would form a class is bracketed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{ParseOptions, Pattern, Syntax};
+    ///
+    /// let options = ParseOptions::for_syntax(Syntax::Bash);
+    /// let escaped = Pattern::escape_with("a\\{b,c}*", options);
+    /// assert_eq!(escaped, "a\\\\[{]b,c[}][*]");
+    /// assert!(Pattern::new_with(&escaped, options).unwrap().matches("a\\{b,c}*"));
+    /// ```
+    pub fn escape_with(s: &str, options: ParseOptions) -> String {
         let mut escaped = String::new();
+        let mut follows_backslash = false;
         for c in s.chars() {
             match c {
                 // note that ! does not need escaping because it is only special
                 // inside brackets
-                '?' | '*' | '[' | ']' => {
+                '?' | '*' | '[' | ']' | '{' | '}' => {
+                    escaped.push('[');
+                    escaped.push(c);
+                    escaped.push(']');
+                }
+                '\\' if options.backslash_escapes => escaped.push_str("\\\\"),
+                c if follows_backslash
+                    && ((options.shorthand_classes && shorthand_class(c).is_some())
+                        || (options.unicode_classes && (c == 'p' || c == 'P'))) =>
+                {
                     escaped.push('[');
                     escaped.push(c);
                     escaped.push(']');
@@ -735,12 +3030,48 @@ impl Pattern {
                     escaped.push(c);
                 }
             }
+            follows_backslash = c == '\\'
+                && (options.shorthand_classes || options.unicode_classes)
+                && !options.backslash_escapes;
         }
         escaped
     }
 
-    /// Return if the given `str` matches this `Pattern` using the default
-    /// match options (i.e. `MatchOptions::new()`).
+    /// Return this pattern with the given match options baked in, so that
+    /// `matches`, `matches_path` and `match_spans` use them instead of the
+    /// default ones. The methods that take `MatchOptions` still use the
+    /// options they're given.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let options = MatchOptions {
+    ///     case_sensitive: false,
+    ///     ..MatchOptions::new()
+    /// };
+    /// let pattern = Pattern::new("*.RS").unwrap().with_options(options);
+    /// assert!(pattern.matches("lib.rs"));
+    /// assert!(!pattern.matches_with("lib.rs", MatchOptions::new()));
+    /// ```
+    pub fn with_options(mut self, options: MatchOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// The match options used by `matches`: those given to `with_options`,
+    /// or else `MatchOptions::new()`.
+    pub fn options(&self) -> MatchOptions {
+        match self.options {
+            Some(options) => options,
+            None => MatchOptions::new(),
+        }
+    }
+
+    /// Return if the given `str` matches this `Pattern` using the options
+    /// given to `with_options`, or else the default match options (i.e.
+    /// `MatchOptions::new()`).
     ///
     /// # Examples
     ///
@@ -752,28 +3083,559 @@ impl Pattern {
     /// assert!(Pattern::new("d*g").unwrap().matches("doog"));
     /// ```
     pub fn matches(&self, str: &str) -> bool {
-        self.matches_with(str, MatchOptions::new())
+        self.matches_with(str, self.options())
     }
 
-    /// Return if the given `Path`, when converted to a `str`, matches this
-    /// `Pattern` using the default match options (i.e. `MatchOptions::new()`).
+    /// Return if the given `Path` matches this `Pattern` using the options
+    /// given to `with_options`, or else the default match options. The path
+    /// is compared component by component, see `matches_path_with`.
     pub fn matches_path(&self, path: &Path) -> bool {
-        // FIXME (#9639): This needs to handle non-utf8 paths
-        path.to_str().map_or(false, |s| self.matches(s))
+        self.matches_path_with(path, self.options())
+    }
+
+    /// Return if the given `camino::Utf8Path` matches this `Pattern`. The
+    /// path is compared component by component, as by `matches_path`.
+    ///
+    /// This is only available with the `camino` feature.
+    #[cfg(feature = "camino")]
+    pub fn matches_utf8_path(&self, path: &camino::Utf8Path) -> bool {
+        self.matches_path(path.as_std_path())
     }
 
     /// Return if the given `str` matches this `Pattern` using the specified
     /// match options.
+    ///
+    /// With `MatchOptions::linear_time` this is `matches_chars`, which takes
+    /// time proportional to the length of the pattern times the length of
+    /// `str` at worst.
     pub fn matches_with(&self, str: &str, options: MatchOptions) -> bool {
-        self.matches_from(true, str.chars(), 0, options) == Match
+        if options.linear_time {
+            return nfa::matches(self, str.chars(), options);
+        }
+        if !self.alternatives.is_empty() {
+            return self
+                .alternatives
+                .iter()
+                .any(|p| p.matches_with(str, options));
+        }
+        let mut state = MatchState::new();
+        self.matches_from(true, str.chars(), 0, options, &mut state) == Match
+    }
+
+    /// Return if the given `Candidate` matches this `Pattern` using the
+    /// specified match options, which is the same as matching its
+    /// `as_str()`. A candidate is only worth creating to match a path against
+    /// `GlobSet`s as well, or against many patterns through
+    /// `Candidate::from_path`, which converts the path once.
+    pub fn matches_candidate(&self, candidate: &Candidate, options: MatchOptions) -> bool {
+        self.matches_with(candidate.as_str(), options)
+    }
+
+    /// Return whether each of the given strings matches this `Pattern`, in
+    /// the same order, using the options given to `with_options`, or else
+    /// the default match options.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let pattern = Pattern::new("*.rs").unwrap();
+    /// let names = ["lib.rs", "README.md", "main.rs"];
+    /// assert_eq!(pattern.matches_many(&names), vec![true, false, true]);
+    /// ```
+    pub fn matches_many<S: AsRef<str>>(&self, strs: &[S]) -> Vec<bool> {
+        let options = self.options();
+        strs.iter()
+            .map(|s| self.matches_with(s.as_ref(), options))
+            .collect()
+    }
+
+    /// Like `matches_many`, but matches the strings in parallel on the
+    /// `rayon` thread pool, which pays off for long lists such as the
+    /// entries of a large manifest.
+    ///
+    /// This is only available with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_matches_many<S: AsRef<str> + Sync>(&self, strs: &[S]) -> Vec<bool> {
+        use rayon::prelude::*;
+
+        let options = self.options();
+        strs.par_iter()
+            .map(|s| self.matches_with(s.as_ref(), options))
+            .collect()
+    }
+
+    /// Return if the given `str` matches this `Pattern` using the specified
+    /// match options, along with counters describing the work that took.
+    ///
+    /// This is only available with the `stats` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "stats")]
+    /// # fn main() {
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let pattern = Pattern::new("*.rs").unwrap();
+    /// let (matched, stats) = pattern.matches_with_stats("lib.rs", MatchOptions::new());
+    /// assert!(matched);
+    /// // `*` was retried after consuming each of `l`, `i` and `b`
+    /// assert_eq!(stats.backtracks, 3);
+    /// # }
+    /// # #[cfg(not(feature = "stats"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "stats")]
+    pub fn matches_with_stats(&self, str: &str, options: MatchOptions) -> (bool, MatchStats) {
+        if !self.alternatives.is_empty() {
+            let mut total = MatchStats::default();
+            for pattern in &self.alternatives {
+                let (matched, stats) = pattern.matches_with_stats(str, options);
+                total.steps += stats.steps;
+                total.comparisons += stats.comparisons;
+                total.backtracks += stats.backtracks;
+                total.max_depth = cmp::max(total.max_depth, stats.max_depth);
+                if matched {
+                    return (true, total);
+                }
+            }
+            return (false, total);
+        }
+        let mut state = MatchState::new();
+        let matched = self.matches_from(true, str.chars(), 0, options, &mut state) == Match;
+        state.stats.steps = state.steps;
+        (matched, state.stats)
+    }
+
+    /// If the given `str` matches this `Pattern` using the options given to
+    /// `with_options`, or else the default match options, return the part of
+    /// it that was consumed by each token of the pattern. See
+    /// `match_spans_with` for details.
+    pub fn match_spans(&self, str: &str) -> Option<Vec<MatchSpan>> {
+        self.match_spans_with(str, self.options())
     }
 
-    /// Return if the given `Path`, when converted to a `str`, matches this
-    /// `Pattern` using the specified match options.
+    /// If the given `str` matches this `Pattern` using the specified match
+    /// options, return the part of it that was consumed by each token of the
+    /// pattern, in order.
+    ///
+    /// Each literal character, `?`, `*`, `**` and `[...]` is a token. This is
+    /// useful for highlighting the parts of a name that anchored a match. As
+    /// wildcards may match in more than one way, the spans describe the
+    /// match that was found, in which earlier wildcards consume as little as
+    /// possible. If the pattern has braces, the spans are those of the first
+    /// alternative that matches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// let spans = Pattern::new("*.r?").unwrap().match_spans("lib.rs").unwrap();
+    /// let ranges: Vec<_> = spans.iter().map(|span| span.range.clone()).collect();
+    /// assert_eq!(ranges, vec![0..3, 3..4, 4..5, 5..6]);
+    /// assert!(spans[1].literal && !spans[3].literal);
+    /// ```
+    pub fn match_spans_with(&self, str: &str, options: MatchOptions) -> Option<Vec<MatchSpan>> {
+        if !self.alternatives.is_empty() {
+            return self
+                .alternatives
+                .iter()
+                .filter_map(|p| p.match_spans_with(str, options))
+                .next();
+        }
+        let mut state = MatchState::recording(str.len(), self.tokens.len());
+        if self.matches_from(true, str.chars(), 0, options, &mut state) != Match {
+            return None;
+        }
+
+        let mut start = 0;
+        let spans = self
+            .tokens
+            .iter()
+            .zip(state.ends)
+            .map(|(token, end)| {
+                let span = MatchSpan {
+                    range: start..end,
+                    literal: match *token {
+                        Char(_) => true,
+                        _ => false,
+                    },
+                };
+                start = end;
+                span
+            })
+            .collect();
+        Some(spans)
+    }
+
+    /// Return why the given `str` doesn't match this `Pattern` using the
+    /// specified match options, or `None` if it matches: the character at
+    /// which matching failed, if any, and the tokens that could have matched
+    /// there.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchFailureKind, MatchOptions, Pattern};
+    ///
+    /// let pattern = Pattern::new("src/[a-c]*.rs").unwrap();
+    /// let failure = pattern.explain("src/x.rs", MatchOptions::new()).unwrap();
+    /// assert_eq!(failure.kind, MatchFailureKind::UnexpectedChar);
+    /// assert_eq!(failure.pos, 4);
+    /// assert_eq!(failure.to_string(), "`x` at byte 4 doesn't match `[a-c]`");
+    /// ```
+    pub fn explain(&self, str: &str, options: MatchOptions) -> Option<MatchFailure> {
+        explain::explain(self, str, options)
+    }
+
+    /// If the given `str` matches this `Pattern` using the specified match
+    /// options, return the part of it matched by each `**`, in order.
+    ///
+    /// The separator after a `**` isn't included, so this is the path that
+    /// leads from the components before it to those after it, which is
+    /// empty if it matched no components. As with `match_spans_with`, this
+    /// describes the match that was found, and if the pattern has braces,
+    /// the first alternative that matches. In particular, unless
+    /// `require_literal_separator` is set, a `*` after the `**` may match
+    /// separators and so consume the components instead, as in `**/*.md`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let pattern = Pattern::new("src/**/mod.rs").unwrap();
+    /// let options = MatchOptions::new();
+    /// assert_eq!(
+    ///     pattern.recursive_captures("src/a/b/mod.rs", options),
+    ///     Some(vec!["a/b"])
+    /// );
+    /// assert_eq!(pattern.recursive_captures("src/mod.rs", options), Some(vec![""]));
+    /// assert_eq!(pattern.recursive_captures("lib/mod.rs", options), None);
+    /// ```
+    pub fn recursive_captures<'a>(
+        &self,
+        str: &'a str,
+        options: MatchOptions,
+    ) -> Option<Vec<&'a str>> {
+        if !self.alternatives.is_empty() {
+            return self
+                .alternatives
+                .iter()
+                .filter_map(|p| p.recursive_captures(str, options))
+                .next();
+        }
+        let spans = self.match_spans_with(str, options)?;
+        let captures = self
+            .tokens
+            .iter()
+            .zip(spans)
+            .filter(|&(token, _)| *token == AnyRecursiveSequence)
+            .map(|(_, span)| {
+                let s = &str[span.range];
+                // the separator after `**` is part of the token
+                match s.chars().next_back() {
+                    Some(c) if options.is_separator(c) => &s[..s.len() - c.len_utf8()],
+                    _ => s,
+                }
+            })
+            .collect();
+        Some(captures)
+    }
+
+    /// Return if the given `Path` matches this `Pattern` using the specified
+    /// match options.
+    ///
+    /// The path is compared component by component, the way `Path`s compare
+    /// equal, rather than as the string it was written as: `a//b`, `a/./b`
+    /// and `a/b/` are all matched as `a/b`, and on Windows `a\b` is too. A
+    /// pattern that `is_dir_only` still only matches a path that's written
+    /// with a trailing separator. A path that isn't valid UTF-8 is matched as
+    /// by `matches_os`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    /// use std::path::Path;
+    ///
+    /// let pattern = Pattern::new("src/lib.rs").unwrap();
+    /// let options = MatchOptions::new();
+    /// assert!(pattern.matches_path_with(Path::new("src/./lib.rs"), options));
+    /// assert!(pattern.matches_path_with(Path::new("src//lib.rs/"), options));
+    /// assert!(!pattern.matches_with("src/./lib.rs", options));
+    /// ```
     pub fn matches_path_with(&self, path: &Path, options: MatchOptions) -> bool {
-        // FIXME (#9639): This needs to handle non-utf8 paths
-        path.to_str()
-            .map_or(false, |s| self.matches_with(s, options))
+        let is_dir = os_str_lossy(path.as_os_str()).ends_with(path::is_separator);
+        self.matches_path_is_dir(path, is_dir, options)
+    }
+
+    /// Return if the given `OsStr` matches this `Pattern` using the specified
+    /// match options, even if it isn't valid UTF-8.
+    ///
+    /// On Unix, this is `matches_bytes`. Elsewhere, the parts of the string
+    /// that aren't valid Unicode are replaced with U+FFFD, the replacement
+    /// character, as by `OsStr::to_string_lossy`.
+    pub fn matches_os(&self, s: &OsStr, options: MatchOptions) -> bool {
+        self.matches_with(&os_str_lossy(s), options)
+    }
+
+    /// Return if the given bytes match this `Pattern` using the specified
+    /// match options, even if they aren't valid UTF-8.
+    ///
+    /// The bytes are matched as UTF-8, except that each byte that isn't part
+    /// of a valid character is treated as the replacement character U+FFFD,
+    /// so `?` and `*` match it but no literal character in the pattern does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let options = MatchOptions::new();
+    /// assert!(Pattern::new("caf?.txt").unwrap().matches_bytes(b"caf\xe9.txt", options));
+    /// assert!(Pattern::new("caf?.txt").unwrap().matches_bytes(b"caf\xc3\xa9.txt", options));
+    /// assert!(!Pattern::new("caf\u{e9}.txt").unwrap().matches_bytes(b"caf\xe9.txt", options));
+    /// ```
+    pub fn matches_bytes(&self, bytes: &[u8], options: MatchOptions) -> bool {
+        self.matches_with(&bytes_lossy(bytes), options)
+    }
+
+    /// Return if the given `Path` matches this `Pattern` using the specified
+    /// match options, given whether it's a directory.
+    ///
+    /// The path is compared component by component, as by
+    /// `matches_path_with`, so any trailing separators are ignored. A pattern that
+    /// `is_dir_only` only matches the path if `is_dir` is set, and otherwise
+    /// as it would without its trailing separator, while other patterns are
+    /// matched regardless of `is_dir`. This is how `glob` treats a trailing
+    /// separator, for callers that already know the type of each path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    /// use std::path::Path;
+    ///
+    /// let pattern = Pattern::new("**/target/").unwrap();
+    /// let options = MatchOptions::new();
+    /// assert!(pattern.matches_path_is_dir(Path::new("a/target"), true, options));
+    /// assert!(!pattern.matches_path_is_dir(Path::new("a/target"), false, options));
+    /// ```
+    pub fn matches_path_is_dir(&self, path: &Path, is_dir: bool, options: MatchOptions) -> bool {
+        let normalized: PathBuf = path.components().map(|c| c.as_os_str()).collect();
+        let path = os_str_lossy(normalized.as_os_str());
+        let mut end = path.len();
+        while end > 1 && path[..end].ends_with(|c| options.is_separator(c)) {
+            end -= 1;
+        }
+        let path = &path[..end];
+        self.branches().iter().any(|p| {
+            if !p.is_dir_only() {
+                p.matches_with(path, options)
+            } else if is_dir && path.ends_with(|c| options.is_separator(c)) {
+                // the root
+                p.matches_with(path, options)
+            } else {
+                is_dir && p.matches_with(&format!("{}{}", path, path::MAIN_SEPARATOR), options)
+            }
+        })
+    }
+
+    /// Return if the given `Path` matches this `Pattern` using the specified
+    /// match options, looking up whether it's a directory if the pattern
+    /// requires one.
+    ///
+    /// This is `matches_path_is_dir`, where the path is a directory if it
+    /// refers to one once symbolic links are followed. Nothing is looked up
+    /// unless the pattern has a branch that `is_dir_only`.
+    pub fn matches_existing_path(&self, path: &Path, options: MatchOptions) -> bool {
+        let is_dir = self.branches().iter().any(|p| p.is_dir_only()) && path.is_dir();
+        self.matches_path_is_dir(path, is_dir, options)
+    }
+
+    /// Return if the given file name matches this `Pattern` using the
+    /// specified match options, treating it as a single path component.
+    ///
+    /// Unlike `matches_with`, nothing in the pattern can match a path
+    /// separator regardless of `require_literal_separator`, so a name that
+    /// contains one never matches. `require_literal_leading_dot` applies to
+    /// the start of the name, as it does for each component of a path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    /// use std::ffi::OsStr;
+    ///
+    /// let options = MatchOptions {
+    ///     require_literal_leading_dot: true,
+    ///     ..MatchOptions::new()
+    /// };
+    /// let pattern = Pattern::new("*.rs").unwrap();
+    /// assert!(pattern.matches_file_name(OsStr::new("lib.rs"), options));
+    /// assert!(!pattern.matches_file_name(OsStr::new(".lib.rs"), options));
+    /// assert!(!pattern.matches_file_name(OsStr::new("src/lib.rs"), options));
+    /// ```
+    pub fn matches_file_name(&self, name: &OsStr, options: MatchOptions) -> bool {
+        let name = &*os_str_lossy(name);
+        !name.chars().any(path::is_separator)
+            && self.matches_with(
+                name,
+                MatchOptions {
+                    require_literal_separator: true,
+                    ..options
+                },
+            )
+    }
+
+    /// Return if the characters yielded by `chars` match this `Pattern` using
+    /// the specified match options, without collecting them into a string.
+    ///
+    /// This gives the same result as `matches_with`, but consumes the input
+    /// one character at a time and never backtracks, so it suits very long
+    /// candidates that are produced piecemeal, like a virtual path joined
+    /// from many components. It stops early once no match is possible.
+    /// `max_steps` is ignored, since the time taken is bounded by the length
+    /// of the input times the length of the pattern, which is what
+    /// `MatchOptions::linear_time` relies on.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let pattern = Pattern::new("*/*.rs").unwrap();
+    /// let components = ["src", "lib.rs"];
+    /// let chars = components.join("/").chars().collect::<Vec<_>>();
+    /// assert!(pattern.matches_chars(chars, MatchOptions::new()));
+    ///
+    /// let joined = components.iter().enumerate().flat_map(|(i, c)| {
+    ///     (if i > 0 { "/" } else { "" }).chars().chain(c.chars())
+    /// });
+    /// assert!(pattern.matches_chars(joined, MatchOptions::new()));
+    /// ```
+    pub fn matches_chars<I>(&self, chars: I, options: MatchOptions) -> bool
+    where
+        I: IntoIterator<Item = char>,
+    {
+        nfa::matches(self, chars, options)
+    }
+
+    /// Return if `prefix` could be the start of a string that matches this
+    /// `Pattern` using the specified match options, i.e. if it either matches
+    /// or appending something to it might make it match.
+    ///
+    /// This lets a directory walker of your own prune the subtrees that
+    /// can't contain a match: pass a directory with a trailing separator to
+    /// ask about the paths beneath it. It never returns `false` for a prefix
+    /// of a match, but may return `true` for one that can't be completed,
+    /// e.g. because the rest of the pattern is an empty class like `[b-a]`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{MatchOptions, Pattern};
+    ///
+    /// let pattern = Pattern::new("src/**/*.rs").unwrap();
+    /// let options = MatchOptions::new();
+    /// assert!(pattern.could_match("src/foo/", options));
+    /// assert!(!pattern.could_match("target/foo/", options));
+    /// ```
+    pub fn could_match(&self, prefix: &str, options: MatchOptions) -> bool {
+        nfa::could_match(self, prefix.chars(), options)
+    }
+
+    /// Return if the given directory entry matches this `Pattern` using the
+    /// specified match options, for callers that already have their own
+    /// `read_dir` loop.
+    ///
+    /// Only the entry's file name is matched, as by `matches_file_name`, so
+    /// no path is built. A pattern ending in a separator only matches
+    /// directories, and in that case the entry's file type is consulted,
+    /// following it if it's a symbolic link.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::{MatchOptions, Pattern};
+    /// use std::fs;
+    ///
+    /// let pattern = Pattern::new("*.rs").unwrap();
+    /// for entry in fs::read_dir("src").unwrap() {
+    ///     let entry = entry.unwrap();
+    ///     if pattern.matches_dir_entry(&entry, MatchOptions::new()) {
+    ///         println!("{}", entry.path().display());
+    ///     }
+    /// }
+    /// ```
+    pub fn matches_dir_entry(&self, entry: &DirEntry, options: MatchOptions) -> bool {
+        if !self.alternatives.is_empty() {
+            return self
+                .alternatives
+                .iter()
+                .any(|p| p.matches_dir_entry(entry, options));
+        }
+        if !self.is_dir_only() {
+            return self.matches_file_name(&entry.file_name(), options);
+        }
+
+        let is_dir = match entry.file_type() {
+            Ok(ty) if ty.is_symlink() => fs::metadata(entry.path())
+                .map(|m| m.is_dir())
+                .unwrap_or(false),
+            Ok(ty) => ty.is_dir(),
+            Err(_) => false,
+        };
+        if !is_dir {
+            return false;
+        }
+        let name = entry.file_name();
+        let name = os_str_lossy(&name);
+        !name.chars().any(path::is_separator)
+            && self.matches_with(
+                &format!("{}{}", name, path::MAIN_SEPARATOR),
+                MatchOptions {
+                    require_literal_separator: true,
+                    ..options
+                },
+            )
+    }
+
+    /// Return if this pattern ends in a path separator, like `target/` or
+    /// `src/**/`, and so only matches directories. If its braces expand to
+    /// several alternatives, this is only the case if they all do.
+    ///
+    /// `matches_with` only checks that a path ends in a separator too; use
+    /// `matches_path_is_dir` or `matches_existing_path` to take what the path
+    /// refers to into account instead.
+    pub fn is_dir_only(&self) -> bool {
+        if !self.alternatives.is_empty() {
+            return self.alternatives.iter().all(Pattern::is_dir_only);
+        }
+        // the separator after a trailing `**/` isn't kept as a token
+        match self.tokens.last() {
+            Some(&Char(c)) => path::is_separator(c),
+            Some(&AnyRecursiveSequence) => self
+                .original
+                .chars()
+                .next_back()
+                .map_or(false, path::is_separator),
+            _ => false,
+        }
+    }
+
+    // Return the patterns this one matches the union of: its alternatives if
+    // it has braces, or else itself.
+    fn branches(&self) -> Vec<&Pattern> {
+        if self.alternatives.is_empty() {
+            vec![self]
+        } else {
+            self.alternatives.iter().collect()
+        }
     }
 
     /// Access the original glob pattern.
@@ -781,14 +3643,206 @@ impl Pattern {
         &self.original
     }
 
+    /// Return the tokens this pattern was compiled to, for tools that
+    /// analyze or translate patterns without parsing them again.
+    ///
+    /// A pattern whose braces expanded to several alternatives has no tokens
+    /// of its own; see `alternatives`.
+    pub fn tokens(&self) -> Vec<Token> {
+        self.tokens.iter().map(token::from_pattern_token).collect()
+    }
+
+    /// Return the patterns that this pattern's braces expanded to, if there
+    /// are several of them, in which case it matches what any of them does.
+    /// Otherwise this is empty.
+    pub fn alternatives(&self) -> &[Pattern] {
+        &self.alternatives
+    }
+
+    /// Constructs the pattern made up of `tokens`, such as ones returned by
+    /// `tokens` and then rewritten. Its original string is written with any
+    /// escaping that the tokens require, e.g. `[*]` for a literal `*`.
+    ///
+    /// This may return an error if the tokens don't form a valid pattern,
+    /// e.g. if an `AnyRecursiveSequence` isn't a whole path component.
+    pub fn from_tokens(tokens: &[Token]) -> Result<Self, PatternError> {
+        let tokens: Vec<PatternToken> = tokens.iter().map(token::to_pattern_token).collect();
+        Self::new(&rewrite::to_string(&tokens, false))
+    }
+
+    /// Return how specific this pattern is, to pick the most specific of
+    /// several patterns that match the same path. See `Specificity` for how
+    /// patterns are ranked. A pattern with braces is only as specific as its
+    /// least specific alternative.
+    pub fn specificity(&self) -> Specificity {
+        specificity::specificity(self)
+    }
+
+    /// Return if this pattern has no wildcards or character classes, so it
+    /// only matches the one string returned by `literal`.
+    ///
+    /// Escaped metacharacters like `[*]` are literal, while a pattern whose
+    /// braces expand to several alternatives isn't. Note that a literal
+    /// pattern still matches other strings with case-insensitive match
+    /// options, or when matching Windows paths, where `/` and `\` match each
+    /// other.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::Pattern;
+    ///
+    /// assert!(Pattern::new("src/lib.rs").unwrap().is_literal());
+    /// assert!(Pattern::new("src/[*].rs").unwrap().is_literal());
+    /// assert!(!Pattern::new("src/*.rs").unwrap().is_literal());
+    /// ```
+    pub fn is_literal(&self) -> bool {
+        self.literal().is_some()
+    }
+
+    /// Return the string this pattern matches if it's literal, with any
+    /// escaping removed, e.g. `a*` for `a[*]`. See `is_literal`.
+    ///
+    /// This is the path to look up directly, rather than by listing
+    /// directories, when a pattern names a single file.
+    pub fn literal(&self) -> Option<String> {
+        if !self.alternatives.is_empty() {
+            return None;
+        }
+        self.tokens
+            .iter()
+            .map(|token| match *token {
+                Char(c) => Some(c),
+                // an escaped metacharacter, though a separator or a dot in a
+                // class isn't always matched like a literal one
+                AnyWithin(ref specifiers) if specifiers.len() == 1 => {
+                    let c = match specifiers[0] {
+                        SingleChar(c) => c,
+                        CharRange(start, end) if start == end => start,
+                        _ => return None,
+                    };
+                    if c == '.' || path::is_separator(c) {
+                        None
+                    } else {
+                        Some(c)
+                    }
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     fn matches_from(
         &self,
-        mut follows_separator: bool,
-        mut file: std::str::Chars,
+        follows_separator: bool,
+        file: std::str::Chars,
         i: usize,
         options: MatchOptions,
+        state: &mut MatchState,
     ) -> MatchResult {
+        // The wildcards that may still consume more of the input, innermost
+        // last, each with the input after what it has consumed so far. The
+        // current attempt is the rest of the pattern after the innermost one,
+        // and when it fails, that wildcard consumes one more character and
+        // tries again, so the depth is bounded by the heap, not the stack.
+        let mut wildcards = Vec::new();
+        let mut run = self.run_from(follows_separator, file, i, options, state);
+        loop {
+            let result = match run {
+                Run::Done(result) => result,
+                Run::Wildcard(ti, file, follows_separator) => {
+                    state.record(ti, &file);
+                    // a `**` that has to match a component can't be empty
+                    let requires_component = self.tokens[ti] == AnyRecursiveSequence
+                        && options.require_recursive_component;
+                    if !requires_component {
+                        // Empty match
+                        wildcards.push((ti, file.clone(), follows_separator));
+                        state.descend();
+                        run = self.run_from(follows_separator, file, ti + 1, options, state);
+                        continue;
+                    }
+                    if file.as_str().is_empty() {
+                        SubPatternDoesntMatch
+                    } else {
+                        wildcards.push((ti, file, follows_separator));
+                        run = self.backtrack(&mut wildcards, options, state);
+                        continue;
+                    }
+                }
+            };
+
+            match result {
+                SubPatternDoesntMatch => (), // keep trying
+                m => return m,
+            }
+            if wildcards.is_empty() {
+                return SubPatternDoesntMatch;
+            }
+            state.ascend();
+            run = self.backtrack(&mut wildcards, options, state);
+        }
+    }
+
+    // Makes the innermost of `wildcards` consume one more character, or more
+    // if it needs to, and tries the rest of the pattern after it again. Once
+    // it has consumed all of the input, the rest of the pattern is tried one
+    // last time, and the wildcard is done.
+    fn backtrack<'a>(
+        &self,
+        wildcards: &mut Vec<(usize, std::str::Chars<'a>, bool)>,
+        options: MatchOptions,
+        state: &mut MatchState,
+    ) -> Run<'a> {
+        let (ti, mut file, mut follows_separator) = wildcards.pop().unwrap();
+        while let Some(c) = file.next() {
+            state.steps += 1;
+            state.backtracked();
+            if follows_separator && options.require_literal_leading_dot && c == '.' {
+                return Run::Done(SubPatternDoesntMatch);
+            }
+            follows_separator = options.is_separator(c);
+            match self.tokens[ti] {
+                AnyRecursiveSequence if !follows_separator => continue,
+                AnySequence if options.require_literal_separator && follows_separator => {
+                    return Run::Done(SubPatternDoesntMatch)
+                }
+                _ => (),
+            }
+            state.record(ti, &file);
+            wildcards.push((ti, file.clone(), follows_separator));
+            state.descend();
+            return self.run_from(follows_separator, file, ti + 1, options, state);
+        }
+        // a `**` that has to match a component only matches one that is
+        // followed by a separator, unless nothing follows the `**`
+        if self.tokens[ti] == AnyRecursiveSequence
+            && options.require_recursive_component
+            && !follows_separator
+            && ti + 1 < self.tokens.len()
+        {
+            return Run::Done(SubPatternDoesntMatch);
+        }
+        state.record(ti, &file);
+        self.run_from(follows_separator, file, ti + 1, options, state)
+    }
+
+    // Matches the tokens from `i` on against `file` until the match succeeds
+    // or fails, or a wildcard is reached.
+    fn run_from<'a>(
+        &self,
+        mut follows_separator: bool,
+        mut file: std::str::Chars<'a>,
+        i: usize,
+        options: MatchOptions,
+        state: &mut MatchState,
+    ) -> Run<'a> {
         for (ti, token) in self.tokens[i..].iter().enumerate() {
+            state.steps += 1;
+            if options.max_steps.map_or(false, |max| state.steps > max) {
+                return Run::Done(EntirePatternDoesntMatch);
+            }
+
             match *token {
                 AnySequence | AnyRecursiveSequence => {
                     // ** must be at the start.
@@ -796,75 +3850,414 @@ impl Pattern {
                         AnyRecursiveSequence => follows_separator,
                         _ => true,
                     });
-
-                    // Empty match
-                    match self.matches_from(follows_separator, file.clone(), i + ti + 1, options) {
-                        SubPatternDoesntMatch => (), // keep trying
-                        m => return m,
-                    };
-
-                    while let Some(c) = file.next() {
-                        if follows_separator && options.require_literal_leading_dot && c == '.' {
-                            return SubPatternDoesntMatch;
-                        }
-                        follows_separator = path::is_separator(c);
-                        match *token {
-                            AnyRecursiveSequence if !follows_separator => continue,
-                            AnySequence
-                                if options.require_literal_separator && follows_separator =>
-                            {
-                                return SubPatternDoesntMatch
-                            }
-                            _ => (),
-                        }
-                        match self.matches_from(
-                            follows_separator,
-                            file.clone(),
-                            i + ti + 1,
-                            options,
-                        ) {
-                            SubPatternDoesntMatch => (), // keep trying
-                            m => return m,
-                        }
-                    }
+                    return Run::Wildcard(i + ti, file, follows_separator);
                 }
                 _ => {
                     let c = match file.next() {
                         Some(c) => c,
-                        None => return EntirePatternDoesntMatch,
+                        None => return Run::Done(EntirePatternDoesntMatch),
                     };
 
-                    let is_sep = path::is_separator(c);
+                    let is_sep = options.is_separator(c);
 
+                    state.compared();
                     if !match *token {
+                        AnyChar if is_sep && options.literal_separator(false) => false,
+                        AnyWithin(..) | AnyExcept(..)
+                            if is_sep && options.literal_separator(true) =>
+                        {
+                            false
+                        }
                         AnyChar | AnyWithin(..) | AnyExcept(..)
-                            if (options.require_literal_separator && is_sep)
-                                || (follows_separator
-                                    && options.require_literal_leading_dot
-                                    && c == '.') =>
+                            if (follows_separator
+                                && options.require_literal_leading_dot
+                                && c == '.') =>
                         {
                             false
                         }
                         AnyChar => true,
                         AnyWithin(ref specifiers) => in_char_specifiers(specifiers, c, options),
                         AnyExcept(ref specifiers) => !in_char_specifiers(specifiers, c, options),
-                        Char(c2) => chars_eq(c, c2, options.case_sensitive),
+                        Char(c2) => chars_eq(c, c2, options),
                         AnySequence | AnyRecursiveSequence => unreachable!(),
                     } {
-                        return SubPatternDoesntMatch;
+                        return Run::Done(SubPatternDoesntMatch);
                     }
+                    state.record(i + ti, &file);
                     follows_separator = is_sep;
                 }
             }
         }
 
         // Iter is fused.
-        if file.next().is_none() {
-            Match
+        if file.next().is_none() && (follows_separator || !self.is_dir_only()) {
+            Run::Done(Match)
+        } else {
+            Run::Done(SubPatternDoesntMatch)
+        }
+    }
+}
+
+/// The part of a string consumed by one token of a `Pattern`, as returned by
+/// `Pattern::match_spans`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MatchSpan {
+    /// The byte range of the string consumed by the token.
+    pub range: Range<usize>,
+
+    /// Whether the token is a literal character, rather than a wildcard or
+    /// a `[...]` expression.
+    pub literal: bool,
+}
+
+/// Counters describing the work done to match a string against a `Pattern`,
+/// as returned by `Pattern::matches_with_stats`.
+///
+/// These are meant for finding patterns that are expensive to match, such as
+/// `*a*a*a*a*b`, and may change as the matcher does. This is only available
+/// with the `stats` feature.
+#[cfg(feature = "stats")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct MatchStats {
+    /// The number of steps taken, as limited by `MatchOptions::max_steps`.
+    pub steps: usize,
+
+    /// The number of times a token was compared against a character.
+    pub comparisons: usize,
+
+    /// The number of times a wildcard was made to consume one more character
+    /// after the rest of the pattern failed to match.
+    pub backtracks: usize,
+
+    /// The deepest nesting of wildcards being tried at once.
+    pub max_depth: usize,
+}
+
+// State kept across the match attempts of `Pattern::matches_from`.
+struct MatchState {
+    steps: usize,
+    // when recording spans, the length of the subject and the byte offset at
+    // which the text consumed by each token ends
+    len: usize,
+    ends: Vec<usize>,
+    #[cfg(feature = "stats")]
+    stats: MatchStats,
+    #[cfg(feature = "stats")]
+    depth: usize,
+}
+
+impl MatchState {
+    fn new() -> Self {
+        Self::recording(0, 0)
+    }
+
+    fn recording(len: usize, tokens: usize) -> Self {
+        Self {
+            steps: 0,
+            len,
+            ends: vec![0; tokens],
+            #[cfg(feature = "stats")]
+            stats: MatchStats::default(),
+            #[cfg(feature = "stats")]
+            depth: 0,
+        }
+    }
+
+    fn record(&mut self, token: usize, rest: &std::str::Chars) {
+        if !self.ends.is_empty() {
+            self.ends[token] = self.len - rest.as_str().len();
+        }
+    }
+
+    // The following only count with the `stats` feature.
+
+    fn compared(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.stats.comparisons += 1;
+        }
+    }
+
+    fn backtracked(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.stats.backtracks += 1;
+        }
+    }
+
+    fn descend(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.depth += 1;
+            self.stats.max_depth = cmp::max(self.stats.max_depth, self.depth);
+        }
+    }
+
+    fn ascend(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.depth -= 1;
+        }
+    }
+}
+
+// Returns the leading components of `pattern` that contain no
+// metacharacters, excluding the final component, i.e. the deepest directory
+// that every match of the pattern must lie beneath.
+fn literal_base(pattern: &str, options: ParseOptions) -> PathBuf {
+    let mut base = PathBuf::new();
+    let mut components = Path::new(pattern).components().peekable();
+    while let Some(component) = components.next() {
+        if components.peek().is_none() {
+            break;
+        }
+        if let Component::Normal(s) = component {
+            // the component as it's matched, without any escapes
+            let literal = s
+                .to_str()
+                .and_then(|s| Pattern::new_with(s, options).ok())
+                .and_then(|p| {
+                    if !p.alternatives.is_empty() {
+                        return None;
+                    }
+                    p.tokens
+                        .iter()
+                        .map(|t| match *t {
+                            Char(c) => Some(c),
+                            _ => None,
+                        })
+                        .collect::<Option<String>>()
+                });
+            match literal {
+                Some(literal) => base.push(literal),
+                None => break,
+            }
+            continue;
+        }
+        base.push(component.as_os_str());
+    }
+
+    if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    }
+}
+
+// The bytes of `path`, as they're written by `Paths::write_matches_to`.
+#[cfg(unix)]
+fn path_bytes<'a>(path: &'a Path) -> Cow<'a, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().into()
+}
+
+#[cfg(not(unix))]
+fn path_bytes<'a>(path: &'a Path) -> Cow<'a, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(s) => s.as_bytes().into(),
+        Cow::Owned(s) => s.into_bytes().into(),
+    }
+}
+
+// `bytes` as a string, with each byte that isn't part of valid UTF-8
+// replaced by U+FFFD, see `Pattern::matches_bytes`.
+fn bytes_lossy<'a>(mut bytes: &'a [u8]) -> Cow<'a, str> {
+    let mut s = String::new();
+    loop {
+        match ::std::str::from_utf8(bytes) {
+            // nothing was replaced
+            Ok(valid) if s.is_empty() => return Cow::Borrowed(valid),
+            Ok(valid) => {
+                s.push_str(valid);
+                return Cow::Owned(s);
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                s.push_str(::std::str::from_utf8(valid).unwrap());
+                let invalid = e.error_len().unwrap_or(rest.len());
+                for _ in 0..invalid {
+                    s.push('\u{FFFD}');
+                }
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+}
+
+// `s` as a string, see `Pattern::matches_os`.
+#[cfg(unix)]
+fn os_str_lossy<'a>(s: &'a OsStr) -> Cow<'a, str> {
+    use std::os::unix::ffi::OsStrExt;
+    bytes_lossy(s.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn os_str_lossy<'a>(s: &'a OsStr) -> Cow<'a, str> {
+    s.to_string_lossy()
+}
+
+// Return if `link` leads to one of the directories above it.
+fn is_link_cycle<F: ?Sized + FileSystem>(fs: &F, link: &Path) -> bool {
+    let mut ancestor = link.parent();
+    while let Some(dir) = ancestor {
+        // the parent of a relative path with one component is empty
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        if fs.is_same_file(dir, link).unwrap_or(false) {
+            return true;
+        }
+        ancestor = dir.parent();
+    }
+    false
+}
+
+// `path` relative to `prefix`, or itself if it isn't beneath it, see
+// `GlobBuilder::relative_to`.
+fn relative_path(path: PathBuf, prefix: &Path) -> PathBuf {
+    let rest = path.strip_prefix(prefix).ok().map(Path::to_path_buf);
+    match rest {
+        Some(ref rest) if rest.as_os_str().is_empty() => PathBuf::from("."),
+        Some(rest) => rest,
+        None => path,
+    }
+}
+
+// Reads the entries of the directory `path`, which is the current directory
+// if `curdir` is set.
+fn read_children(path: &Path, curdir: bool, config: &GlobBuilder) -> io::Result<Vec<PathWrapper>> {
+    if let Some(ref listings) = config.listings {
+        if let Some(children) = listings.0.lock().unwrap().get(path) {
+            return Ok(children.clone());
+        }
+        let mut config = config.clone();
+        config.listings = None;
+        let children = read_children(path, curdir, &config)?;
+        listings
+            .0
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), children.clone());
+        return Ok(children);
+    }
+    if let Some(timeout) = config.read_timeout {
+        let (tx, rx) = mpsc::channel();
+        let path = path.to_path_buf();
+        let mut config = config.clone();
+        config.read_timeout = None;
+        thread::Builder::new().spawn(move || {
+            // the receiver is gone if reading took too long
+            let _ = tx.send(read_children(&path, curdir, &config));
+        })?;
+        return match rx.recv_timeout(timeout) {
+            Ok(children) => children,
+            Err(RecvTimeoutError::Timeout) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out reading directory",
+            )),
+            Err(RecvTimeoutError::Disconnected) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "reading directory panicked",
+            )),
+        };
+    }
+    if let Some(ref fs) = config.filesystem {
+        return fs.read_dir(path).map(|entries| {
+            entries
+                .into_iter()
+                .map(|(name, kind)| {
+                    let path = if curdir {
+                        PathBuf::from(name)
+                    } else {
+                        path.join(name)
+                    };
+                    let is_directory = match kind {
+                        FileKind::Dir => true,
+                        FileKind::Symlink => fs.metadata(&path).ok() == Some(FileKind::Dir),
+                        _ => false,
+                    };
+                    PathWrapper {
+                        path,
+                        is_directory,
+                        is_symlink: kind == FileKind::Symlink,
+                        links: 0,
+                        depth: 0,
+                    }
+                })
+                .collect()
+        });
+    }
+    if let Some(children) = cached_children(path, curdir, config) {
+        return children;
+    }
+
+    let batch = config.read_batch_size;
+    let mut children = Vec::with_capacity(batch);
+    for e in fs::read_dir(path)? {
+        let e = e?;
+        let path = if curdir {
+            PathBuf::from(e.path().file_name().unwrap())
         } else {
-            SubPatternDoesntMatch
+            e.path()
+        };
+        if batch > 0 && children.len() == children.capacity() {
+            children.reserve_exact(batch);
         }
+        children.push(PathWrapper::from_dir_entry(path, e));
     }
+    Ok(children)
+}
+
+#[cfg(feature = "cache")]
+fn cached_children(
+    path: &Path,
+    curdir: bool,
+    config: &GlobBuilder,
+) -> Option<io::Result<Vec<PathWrapper>>> {
+    use cache::EntryKind;
+
+    let cache = match config.dir_cache {
+        Some(ref cache) => cache,
+        None => return None,
+    };
+    Some(cache.list(path).map(|entries| {
+        entries
+            .into_iter()
+            .map(|(name, kind)| {
+                let path = if curdir {
+                    PathBuf::from(name)
+                } else {
+                    path.join(name)
+                };
+                match kind {
+                    EntryKind::Dir | EntryKind::Other => PathWrapper {
+                        path,
+                        is_directory: kind == EntryKind::Dir,
+                        is_symlink: false,
+                        links: 0,
+                        depth: 0,
+                    },
+                    EntryKind::Symlink | EntryKind::Unknown => {
+                        let is_directory = fs::metadata(&path).map(|m| m.is_dir()).unwrap_or(false);
+                        PathWrapper {
+                            path,
+                            is_directory,
+                            is_symlink: kind == EntryKind::Symlink,
+                            links: 0,
+                            depth: 0,
+                        }
+                    }
+                }
+            })
+            .collect()
+    }))
+}
+
+#[cfg(not(feature = "cache"))]
+fn cached_children(_: &Path, _: bool, _: &GlobBuilder) -> Option<io::Result<Vec<PathWrapper>>> {
+    None
 }
 
 // Fills `todo` with paths under `path` to be matched by `patterns[idx]`,
@@ -875,10 +4268,14 @@ fn fill_todo(
     patterns: &[Pattern],
     idx: usize,
     path: &PathWrapper,
-    options: MatchOptions,
+    config: &GlobBuilder,
+    state: &mut WalkState,
 ) {
     // convert a pattern that's just many Char(_) to a string
     fn pattern_as_str(pattern: &Pattern) -> Option<String> {
+        if !pattern.alternatives.is_empty() {
+            return None;
+        }
         let mut s = String::new();
         for token in &pattern.tokens {
             match *token {
@@ -890,20 +4287,50 @@ fn fill_todo(
         Some(s)
     }
 
-    let add = |todo: &mut Vec<_>, next_path: PathWrapper| {
+    let fs = config.fs();
+    let links = path.links + if path.is_symlink { 1 } else { 0 };
+    let add = |todo: &mut Vec<_>, state: &mut WalkState, mut next_path: PathWrapper| {
+        next_path.links = links;
+        next_path.depth = path.depth + 1;
+        let escapes =
+            config.confine && next_path.is_symlink && !state.is_within_root(fs, &next_path);
+        if escapes || state.is_excluded(config, &next_path) {
+            state.skip(next_path.into_path(), SkipReason::Excluded);
+            return;
+        }
+        if state.is_ignored(config, &next_path) {
+            state.skip(next_path.into_path(), SkipReason::Ignored);
+            return;
+        }
         if idx + 1 == patterns.len() {
             // We know it's good, so don't make the iterator match this path
             // against the pattern again. In particular, it can't match
             // . or .. globs since these never show up as path components.
             todo.push(Ok((next_path, std::usize::MAX)));
         } else {
-            fill_todo(todo, patterns, idx + 1, &next_path, options);
+            match config.may_descend(&next_path, false) {
+                Ok(true) => fill_todo(todo, patterns, idx + 1, &next_path, config, state),
+                Ok(false) => state.skip(next_path.into_path(), SkipReason::Excluded),
+                Err(e) => todo.push(Err(e)),
+            }
         }
     };
 
+    // everything found from here would be too deep
+    if state.exhausted || config.max_depth.map_or(false, |max| path.depth >= max) {
+        return;
+    }
+    if config.is_cancelled() {
+        cancel(todo, state, path.to_path_buf());
+        return;
+    }
+
+    let options = config.options;
     let pattern = &patterns[idx];
     let is_dir = path.is_directory;
     let curdir = path.as_ref() == Path::new(".");
+    // the entries of the current directory have no leading `./`
+    state.load_ignores(config, if curdir { Path::new("") } else { path });
     match pattern_as_str(pattern) {
         Some(s) => {
             // This pattern component doesn't have any metacharacters, so we
@@ -917,36 +4344,97 @@ fn fill_todo(
             } else {
                 path.join(&s)
             };
-            let next_path = PathWrapper::from_path(next_path);
+            let next_path = PathWrapper::from_path(next_path, fs);
             if (special && is_dir)
                 || (!special
-                    && (fs::metadata(&next_path).is_ok()
-                        || fs::symlink_metadata(&next_path).is_ok()))
+                    && (fs.metadata(&next_path).is_ok() || fs.symlink_metadata(&next_path).is_ok()))
             {
-                add(todo, next_path);
+                add(todo, state, next_path);
             }
         }
         None if is_dir => {
-            let dirs = fs::read_dir(path).and_then(|d| {
-                d.map(|e| {
-                    e.map(|e| {
-                        let path = if curdir {
-                            PathBuf::from(e.path().file_name().unwrap())
-                        } else {
-                            e.path()
-                        };
-                        PathWrapper::from_dir_entry(path, e)
-                    })
-                })
-                .collect::<Result<Vec<_>, _>>()
-            });
-            match dirs {
+            if let Some(ref mut listed) = state.listed {
+                listed.push(path.to_path_buf());
+            }
+            match read_children(path, curdir, config) {
                 Ok(mut children) => {
+                    for child in &mut children {
+                        child.links = links;
+                        child.depth = path.depth + 1;
+                    }
+                    state.entries += children.len();
+                    if config.max_entries.map_or(false, |max| state.entries > max) {
+                        state.exhausted = true;
+                        todo.clear();
+                        todo.push(Err(GlobError::with_kind(
+                            path.to_path_buf(),
+                            GlobErrorKind::TooManyResults,
+                            io::Error::new(
+                                io::ErrorKind::Other,
+                                "too many directory entries were read",
+                            ),
+                        )));
+                        return;
+                    }
+                    // sorted in reverse, since the todo buffer is a stack
+                    let by_name = |p1: &PathWrapper, p2: &PathWrapper| match (
+                        config.sort_by.as_ref(),
+                        p1.file_name(),
+                        p2.file_name(),
+                    ) {
+                        (Some(compare), Some(name1), Some(name2)) => (compare.0)(name1, name2),
+                        (_, name1, name2) => name1.cmp(&name2),
+                    };
+                    match config.sort_order {
+                        SortOrder::Unsorted => (),
+                        SortOrder::Modified | SortOrder::Size => {
+                            sort_by_metadata(&mut children, config, by_name)
+                        }
+                        _ => children.sort_by(|p1, p2| by_name(p2, p1)),
+                    }
+
+                    // children are in reverse order, so collect what gets
+                    // left out and report it afterwards in visiting order
+                    let mut skipped = Vec::new();
+                    if config.confine || !config.excludes.is_empty() {
+                        children.retain(|x| {
+                            let escapes =
+                                config.confine && x.is_symlink && !state.is_within_root(fs, x);
+                            let keep = !escapes && !state.is_excluded(config, x);
+                            if !keep {
+                                skipped.push((x.to_path_buf(), SkipReason::Excluded));
+                            }
+                            keep
+                        });
+                    }
+                    if config.ignore_files {
+                        children.retain(|x| {
+                            let ignored = state.is_ignored(config, x);
+                            if ignored {
+                                skipped.push((x.to_path_buf(), SkipReason::Ignored));
+                            }
+                            !ignored
+                        });
+                    }
                     if options.require_literal_leading_dot {
-                        children
-                            .retain(|x| !x.file_name().unwrap().to_str().unwrap().starts_with('.'));
+                        let tagging = state.is_tagging();
+                        // `**` may still have to search hidden directories
+                        let descends = config.descend_hidden == Some(true) && pattern.is_recursive;
+                        children.retain(|x| {
+                            let hidden = x.file_name().unwrap().to_str().unwrap().starts_with('.')
+                                && !(descends && x.is_directory);
+                            if hidden && tagging {
+                                skipped.push((x.to_path_buf(), SkipReason::Hidden));
+                            }
+                            !hidden
+                        });
+                    }
+                    for (path, reason) in skipped.into_iter().rev() {
+                        state.skip(path, reason);
+                    }
+                    if let Some(dir) = children.first().and_then(|x| x.parent()) {
+                        state.memo.enter(dir, todo.len());
                     }
-                    children.sort_by(|p1, p2| p2.file_name().cmp(&p1.file_name()));
                     todo.extend(children.into_iter().map(|x| Ok((x, idx))));
 
                     // Matching the special directory entries . and .. that
@@ -954,20 +4442,27 @@ fn fill_todo(
                     // requires that the pattern has a leading dot, even if the
                     // `MatchOptions` field `require_literal_leading_dot` is not
                     // set.
-                    if !pattern.tokens.is_empty() && pattern.tokens[0] == Char('.') {
+                    let leading_dot = pattern
+                        .branches()
+                        .iter()
+                        .any(|p| p.tokens.first() == Some(&Char('.')));
+                    if !config.confine && leading_dot {
                         for &special in &[".", ".."] {
                             if pattern.matches_with(special, options) {
-                                add(todo, PathWrapper::from_path(path.join(special)));
+                                add(todo, state, PathWrapper::from_path(path.join(special), fs));
                             }
                         }
                     }
                 }
-                Err(e) => {
-                    todo.push(Err(GlobError {
-                        path: path.to_path_buf(),
-                        error: e,
-                    }));
-                }
+                Err(e) => match config.read_errors {
+                    ReadErrors::Skip => (),
+                    ReadErrors::Abort => {
+                        state.exhausted = true;
+                        todo.clear();
+                        todo.push(Err(GlobError::new(path.to_path_buf(), e)));
+                    }
+                    _ => todo.push(Err(GlobError::new(path.to_path_buf(), e))),
+                },
             }
         }
         None => {
@@ -976,11 +4471,87 @@ fn fill_todo(
     }
 }
 
-fn parse_char_specifiers(s: &[char]) -> Vec<CharSpecifier> {
+// Ends the search, leaving only a `GlobErrorKind::Cancelled` error for `path`
+// to be yielded.
+fn cancel(
+    todo: &mut Vec<Result<(PathWrapper, usize), GlobError>>,
+    state: &mut WalkState,
+    path: PathBuf,
+) {
+    state.exhausted = true;
+    todo.clear();
+    todo.push(Err(GlobError::with_kind(
+        path,
+        GlobErrorKind::Cancelled,
+        io::Error::new(io::ErrorKind::Other, "the search was cancelled"),
+    )));
+}
+
+// Sorts `children` into the reverse of the order `SortOrder::Modified` or
+// `SortOrder::Size` visits them in, breaking ties with `by_name`.
+fn sort_by_metadata<F>(children: &mut Vec<PathWrapper>, config: &GlobBuilder, by_name: F)
+where
+    F: Fn(&PathWrapper, &PathWrapper) -> Ordering,
+{
+    let fs = config.fs();
+    let by_size = config.sort_order == SortOrder::Size;
+    let mut keyed: Vec<_> = children
+        .drain(..)
+        .map(|child| {
+            let key = fs.size_and_modified(&child).ok().map(|(size, modified)| {
+                if by_size {
+                    (size, None)
+                } else {
+                    (0, Some(modified))
+                }
+            });
+            (key, child)
+        })
+        .collect();
+    // entries whose metadata can't be read go last
+    let visit = |a: &(Option<(u64, Option<SystemTime>)>, PathWrapper),
+                 b: &(Option<(u64, Option<SystemTime>)>, PathWrapper)| {
+        match (a.0, b.0) {
+            (Some(key1), Some(key2)) => key1.cmp(&key2),
+            (key1, key2) => key2.is_some().cmp(&key1.is_some()),
+        }
+        .then_with(|| by_name(&a.1, &b.1))
+    };
+    keyed.sort_by(|a, b| visit(b, a));
+    children.extend(keyed.into_iter().map(|(_, child)| child));
+}
+
+// Parses the inside of a `[...]` expression, which starts at `pos` in the
+// pattern.
+fn parse_char_specifiers(
+    s: &[char],
+    pos: usize,
+    options: ParseOptions,
+) -> Result<Vec<CharSpecifier>, PatternError> {
     let mut cs = Vec::new();
     let mut i = 0;
     while i < s.len() {
-        if i + 3 <= s.len() && s[i + 1] == '-' {
+        if options.unicode_classes && s[i] == '\\' {
+            if let Some((property, len)) =
+                unicode::property_class(s, i).map_err(|e| PatternError {
+                    pos: pos + e.pos,
+                    ..e
+                })?
+            {
+                cs.extend(property);
+                i += len;
+                continue;
+            }
+        }
+        let shorthand = if options.shorthand_classes && s[i] == '\\' && i + 1 < s.len() {
+            shorthand_class(s[i + 1])
+        } else {
+            None
+        };
+        if let Some(shorthand) = shorthand {
+            cs.extend(shorthand);
+            i += 2;
+        } else if i + 3 <= s.len() && s[i + 1] == '-' {
             cs.push(CharRange(s[i], s[i + 2]));
             i += 3;
         } else {
@@ -988,14 +4559,70 @@ fn parse_char_specifiers(s: &[char]) -> Vec<CharSpecifier> {
             i += 1;
         }
     }
-    cs
+    Ok(cs)
+}
+
+// The characters of the shorthand class `\c`, see
+// `ParseOptions::shorthand_classes`.
+fn shorthand_class(c: char) -> Option<Vec<CharSpecifier>> {
+    match c {
+        'd' => Some(vec![CharRange('0', '9')]),
+        'w' => Some(vec![
+            CharRange('a', 'z'),
+            CharRange('A', 'Z'),
+            CharRange('0', '9'),
+            SingleChar('_'),
+        ]),
+        's' => Some(
+            [' ', '\t', '\n', '\r', '\x0b', '\x0c']
+                .iter()
+                .map(|&c| SingleChar(c))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn check_class_len(
+    specifiers: &[CharSpecifier],
+    pos: usize,
+    options: ParseOptions,
+) -> Result<(), PatternError> {
+    if options
+        .max_class_len
+        .map_or(false, |max| specifiers.len() > max)
+    {
+        return Err(PatternError {
+            pos,
+            msg: ERROR_CLASS_TOO_LARGE,
+            kind: PatternErrorKind::ClassTooLarge,
+        });
+    }
+    Ok(())
+}
+
+// Counts a wildcard at `pos`, failing if there are too many of them.
+fn check_wildcards(
+    wildcards: &mut usize,
+    pos: usize,
+    options: ParseOptions,
+) -> Result<(), PatternError> {
+    *wildcards += 1;
+    if options.max_wildcards.map_or(false, |max| *wildcards > max) {
+        return Err(PatternError {
+            pos,
+            msg: ERROR_TOO_MANY_WILDCARDS,
+            kind: PatternErrorKind::TooManyWildcards,
+        });
+    }
+    Ok(())
 }
 
 fn in_char_specifiers(specifiers: &[CharSpecifier], c: char, options: MatchOptions) -> bool {
     for &specifier in specifiers.iter() {
         match specifier {
             SingleChar(sc) => {
-                if chars_eq(c, sc, options.case_sensitive) {
+                if chars_eq(c, sc, options) {
                     return true;
                 }
             }
@@ -1029,10 +4656,15 @@ fn in_char_specifiers(specifiers: &[CharSpecifier], c: char, options: MatchOptio
 }
 
 /// A helper function to determine if two chars are (possibly case-insensitively) equal.
-fn chars_eq(a: char, b: char, case_sensitive: bool) -> bool {
-    if cfg!(windows) && path::is_separator(a) && path::is_separator(b) {
+fn chars_eq(a: char, b: char, options: MatchOptions) -> bool {
+    let windows = match options.path_flavor {
+        PathFlavor::Unix => false,
+        PathFlavor::Windows => true,
+        _ => cfg!(windows),
+    };
+    if windows && options.is_separator(a) && options.is_separator(b) {
         true
-    } else if !case_sensitive && a.is_ascii() && b.is_ascii() {
+    } else if !options.case_sensitive && a.is_ascii() && b.is_ascii() {
         // FIXME: work with non-ascii chars properly (issue #9084)
         a.eq_ignore_ascii_case(&b)
     } else {
@@ -1055,12 +4687,87 @@ pub struct MatchOptions {
     /// `[...]`.
     pub require_literal_separator: bool,
 
+    /// Whether `?` never matches a path separator, even if
+    /// `require_literal_separator` isn't set, as in implementations where
+    /// only `*` may cross directories.
+    pub any_char_excludes_separator: bool,
+
+    /// Whether `[...]` never matches a path separator, even if
+    /// `require_literal_separator` isn't set.
+    pub class_excludes_separator: bool,
+
     /// Whether or not paths that contain components that start with a `.`
     /// will require that `.` appears literally in the pattern; `*`, `?`, `**`,
     /// or `[...]` will not match. This is useful because such files are
     /// conventionally considered hidden on Unix systems and it might be
     /// desirable to skip them when listing files.
     pub require_literal_leading_dot: bool,
+
+    /// Whether or not a pattern passed to `glob_with` that ends in a `**`
+    /// component yields files in addition to directories, the way zsh does.
+    /// By default `a/**` only produces the directories beneath `a`. This has
+    /// no effect on `Pattern::matches_with`, where a trailing `**` already
+    /// matches any path.
+    pub trailing_recursive_matches_files: bool,
+
+    /// The maximum number of steps the matcher may take before giving up, if
+    /// any. A step is roughly one comparison of a token against a character,
+    /// and a match that would need more steps is reported as not matching.
+    /// This bounds the cost of pathological patterns such as `*a*a*a*a*b`.
+    pub max_steps: Option<usize>,
+
+    /// The conventions of the paths being matched, e.g. `PathFlavor::Windows`
+    /// to match paths from a Windows machine while running elsewhere.
+    pub path_flavor: PathFlavor,
+
+    /// Whether `**` has to match at least one path component, so `a/**/b`
+    /// matches `a/x/b` but not `a/b`, and `a/**` matches `a/x` but not `a/`,
+    /// as some tools have it. By default `**` may match no components at
+    /// all.
+    pub require_recursive_component: bool,
+
+    /// Whether `matches_with`, and the functions built on it such as
+    /// `matches_path_with` and `glob_with`, use the algorithm of
+    /// `Pattern::matches_chars` rather than backtracking. That takes time
+    /// proportional to the length of the pattern times the length of the
+    /// string in the worst case, whereas backtracking may take much longer
+    /// on patterns like `a*a*a*a*b`, so this is the safe choice for patterns
+    /// or names chosen by an attacker. Backtracking is usually faster on
+    /// ordinary patterns. `max_steps` doesn't apply to the linear algorithm.
+    pub linear_time: bool,
+}
+
+/// The conventions a path follows, as set in `MatchOptions::path_flavor`.
+///
+/// This decides which characters separate path components, and therefore
+/// what `*`, `?`, `**` and the `require_literal_*` options treat as a
+/// component boundary. With `Windows`, `/` and `\` are both separators and
+/// match each other, so `C:/Users/*` matches `C:\Users\me` as well as
+/// `C:/Users/me`. Case sensitivity is still controlled by
+/// `MatchOptions::case_sensitive`.
+///
+/// Only matching is affected: a pattern is always compiled with the
+/// conventions of the current platform, so a `**` must be delimited by `/`
+/// to be recognized everywhere.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum PathFlavor {
+    /// The conventions of the platform this is running on.
+    Native,
+
+    /// Unix conventions, where only `/` separates components.
+    Unix,
+
+    /// Windows conventions, where both `/` and `\` separate components.
+    Windows,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Default for PathFlavor {
+    fn default() -> Self {
+        PathFlavor::Native
+    }
 }
 
 impl MatchOptions {
@@ -1074,7 +4781,14 @@ impl MatchOptions {
     /// MatchOptions {
     ///     case_sensitive: true,
     ///     require_literal_separator: false,
-    ///     require_literal_leading_dot: false
+    ///     any_char_excludes_separator: false,
+    ///     class_excludes_separator: false,
+    ///     require_literal_leading_dot: false,
+    ///     trailing_recursive_matches_files: false,
+    ///     max_steps: None,
+    ///     path_flavor: PathFlavor::Native,
+    ///     require_recursive_component: false,
+    ///     linear_time: false
     /// }
     /// ```
     ///
@@ -1086,15 +4800,328 @@ impl MatchOptions {
         Self {
             case_sensitive: true,
             require_literal_separator: false,
+            any_char_excludes_separator: false,
+            class_excludes_separator: false,
             require_literal_leading_dot: false,
+            trailing_recursive_matches_files: false,
+            max_steps: None,
+            path_flavor: PathFlavor::Native,
+            require_recursive_component: false,
+            linear_time: false,
+        }
+    }
+
+    /// Constructs a `MatchOptions` suitable for matching against patterns
+    /// that come from untrusted input. This is `MatchOptions::new()` with
+    /// `require_literal_leading_dot` enabled, so that hidden files are not
+    /// exposed by wildcards, `linear_time` enabled, and `max_steps` set to
+    /// 100,000 for the functions that still backtrack, like `match_spans_with`.
+    pub fn untrusted() -> Self {
+        Self {
+            require_literal_leading_dot: true,
+            linear_time: true,
+            max_steps: Some(100_000),
+            ..Self::new()
+        }
+    }
+
+    // Return if a separator can't be matched by a `?`, or by a `[...]` if
+    // `class` is set.
+    fn literal_separator(&self, class: bool) -> bool {
+        self.require_literal_separator
+            || if class {
+                self.class_excludes_separator
+            } else {
+                self.any_char_excludes_separator
+            }
+    }
+
+    // Return if `c` separates path components in the paths being matched.
+    fn is_separator(&self, c: char) -> bool {
+        match self.path_flavor {
+            PathFlavor::Unix => c == '/',
+            PathFlavor::Windows => c == '/' || c == '\\',
+            _ => path::is_separator(c),
+        }
+    }
+}
+
+/// Configuration options to modify how `Pattern::new_with(..)` compiles a
+/// pattern.
+///
+/// The limits are useful when compiling patterns from untrusted input, as
+/// they bound the time and memory spent on a single pattern. Exceeding one
+/// yields a `PatternError` whose `kind` says which limit was hit.
+///
+/// Each option is set by the method of the same name, on top of one of the
+/// constructors.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::{ParseOptions, Pattern};
+///
+/// let options = ParseOptions::new().braces(true).max_alternatives(Some(8));
+/// let pattern = Pattern::new_with("*.{rs,toml}", options).unwrap();
+/// assert!(pattern.matches("Cargo.toml"));
+/// assert!(Pattern::new_with("{a,b}{c,d}{e,f}{g,h}", options).is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ParseOptions {
+    max_len: Option<usize>,
+    max_tokens: Option<usize>,
+    max_class_len: Option<usize>,
+    max_wildcards: Option<usize>,
+    lenient_recursive_wildcards: bool,
+    shorthand_classes: bool,
+    braces: bool,
+    max_alternatives: Option<usize>,
+    backslash_escapes: bool,
+    caret_negation: bool,
+    disable_recursive_wildcards: bool,
+    unicode_classes: bool,
+}
+
+/// A syntax that patterns can be written in, which selects the extensions
+/// `ParseOptions::for_syntax` enables.
+///
+/// The syntaxes only differ in what a pattern is compiled to; how it's
+/// matched is still up to `MatchOptions`, e.g. `fnmatch`'s `FNM_PATHNAME` is
+/// `require_literal_separator`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Syntax {
+    /// This crate's own syntax, where `**` is a recursive wildcard and
+    /// nothing else is enabled. This is `ParseOptions::new()`.
+    Glob,
+
+    /// POSIX shell patterns: backslash escapes, and `**` is just `*`.
+    Posix,
+
+    /// Bash patterns with `globstar` set: braces, backslash escapes,
+    /// `[^...]`, and `**` is a recursive wildcard where it's a whole path
+    /// component and `*` anywhere else.
+    Bash,
+
+    /// The patterns of glibc's `fnmatch`: those of `Posix` and `[^...]`.
+    Fnmatch,
+
+    /// The patterns of `.gitignore` files: backslash escapes, `[^...]`, and
+    /// `**` as in `Bash`. The meaning of a leading `!` or `/` and of a
+    /// trailing `/` is up to the caller, see `PatternSet`.
+    Gitignore,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Default for Syntax {
+    fn default() -> Self {
+        Syntax::Glob
+    }
+}
+
+impl ParseOptions {
+    /// Constructs a new `ParseOptions` without any limits. This is used when
+    /// calling functions that do not take an explicit `ParseOptions`
+    /// parameter.
+    pub fn new() -> Self {
+        Self {
+            max_len: None,
+            max_tokens: None,
+            max_class_len: None,
+            max_wildcards: None,
+            lenient_recursive_wildcards: false,
+            shorthand_classes: false,
+            braces: false,
+            max_alternatives: None,
+            backslash_escapes: false,
+            caret_negation: false,
+            disable_recursive_wildcards: false,
+            unicode_classes: false,
+        }
+    }
+
+    /// Constructs a `ParseOptions` without any limits that compiles patterns
+    /// written in the given syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::{ParseOptions, Pattern, Syntax};
+    ///
+    /// let options = ParseOptions::for_syntax(Syntax::Bash);
+    /// let pattern = Pattern::new_with("src/**/[^_]*.{c,h}", options).unwrap();
+    /// assert!(pattern.matches("src/x/main.c"));
+    /// assert!(!pattern.matches("src/_test.c"));
+    ///
+    /// let options = ParseOptions::for_syntax(Syntax::Posix);
+    /// let pattern = Pattern::new_with("\\[draft\\]*", options).unwrap();
+    /// assert!(pattern.matches("[draft] notes"));
+    /// ```
+    pub fn for_syntax(syntax: Syntax) -> Self {
+        let mut options = Self::new();
+        match syntax {
+            Syntax::Posix | Syntax::Fnmatch => {
+                options.backslash_escapes = true;
+                options.caret_negation = syntax == Syntax::Fnmatch;
+                options.disable_recursive_wildcards = true;
+            }
+            Syntax::Bash | Syntax::Gitignore => {
+                options.backslash_escapes = true;
+                options.caret_negation = true;
+                options.lenient_recursive_wildcards = true;
+                options.braces = syntax == Syntax::Bash;
+            }
+            _ => (),
+        }
+        options
+    }
+
+    /// Constructs a `ParseOptions` with conservative limits for patterns that
+    /// come from untrusted input: at most 1024 bytes, 256 tokens, 64 entries
+    /// per `[...]` expression, 32 wildcards, and 64 alternatives if braces
+    /// are enabled.
+    pub fn untrusted() -> Self {
+        Self {
+            max_len: Some(1024),
+            max_tokens: Some(256),
+            max_class_len: Some(64),
+            max_wildcards: Some(32),
+            lenient_recursive_wildcards: false,
+            shorthand_classes: false,
+            braces: false,
+            max_alternatives: Some(64),
+            backslash_escapes: false,
+            caret_negation: false,
+            disable_recursive_wildcards: false,
+            unicode_classes: false,
         }
     }
+
+    /// Sets the maximum length of the pattern in bytes, if any.
+    pub fn max_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Sets the maximum number of tokens the pattern may compile to, if any.
+    /// Each literal character, wildcard, and `[...]` expression is one token.
+    pub fn max_tokens(mut self, max_tokens: Option<usize>) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Sets the maximum number of characters and ranges within a single `[...]`
+    /// expression, if any.
+    pub fn max_class_len(mut self, max_class_len: Option<usize>) -> Self {
+        self.max_class_len = max_class_len;
+        self
+    }
+
+    /// Sets the maximum number of wildcards the pattern may contain, if any.
+    /// Each `?`, `*` and `**` is one wildcard. Since every `*` may have to be
+    /// retried at each position of the input, this bounds the cost of matching
+    /// more directly than `max_tokens` does.
+    pub fn max_wildcards(mut self, max_wildcards: Option<usize>) -> Self {
+        self.max_wildcards = max_wildcards;
+        self
+    }
+
+    /// Sets whether a run of `*`s that isn't a valid recursive wildcard, like
+    /// the ones in `a**b`, `foo**` or `a/***`, is treated as a single `*` the
+    /// way bash does without `globstar`, rather than being an error.
+    pub fn lenient_recursive_wildcards(mut self, lenient: bool) -> Self {
+        self.lenient_recursive_wildcards = lenient;
+        self
+    }
+
+    /// Sets whether the regex-style shorthands `\d` (an ASCII digit), `\w` (an
+    /// ASCII letter, digit or `_`) and `\s` (ASCII whitespace) are accepted,
+    /// both on their own and within `[...]`, so `log-\d\d.txt` matches
+    /// `log-01.txt`. A `\` followed by anything else is still a literal
+    /// character.
+    ///
+    /// Since `\` is a path separator on Windows, this shouldn't be enabled
+    /// for patterns written with backslashes, such as `C:\data\*`.
+    pub fn shorthand_classes(mut self, enable: bool) -> Self {
+        self.shorthand_classes = enable;
+        self
+    }
+
+    /// Sets whether bash-style braces are expanded, so `*.{rs,toml}` matches
+    /// whatever `*.rs` or `*.toml` does. Alternatives may be nested or empty,
+    /// as in `x{,y}`, while braces without a comma between them, and the ones
+    /// within `[...]`, are literal.
+    ///
+    /// A range of integers like `{1..20}` expands to each of them in turn,
+    /// counting down if the first is larger. If either end has a leading
+    /// zero, as in `frame_{001..120}.png`, the numbers are zero-padded to the
+    /// width of the longer end.
+    ///
+    /// When globbing, alternatives can't contain path separators and `**`
+    /// can't be one, but matches are still yielded in order, and only once.
+    pub fn braces(mut self, expand: bool) -> Self {
+        self.braces = expand;
+        self
+    }
+
+    /// Sets the maximum number of alternatives the braces in the pattern may
+    /// expand to, if any. Regardless of this, braces never expand to more than
+    /// 100,000 alternatives.
+    pub fn max_alternatives(mut self, max_alternatives: Option<usize>) -> Self {
+        self.max_alternatives = max_alternatives;
+        self
+    }
+
+    /// Sets whether a `\` outside of `[...]` makes the character after it
+    /// literal, so `\*` matches `*`, as in POSIX shells. The shorthands of
+    /// `shorthand_classes` take precedence if it's also set.
+    ///
+    /// As with `shorthand_classes`, this shouldn't be enabled for patterns
+    /// written with backslashes as separators.
+    pub fn backslash_escapes(mut self, enable: bool) -> Self {
+        self.backslash_escapes = enable;
+        self
+    }
+
+    /// Sets whether `[^...]` is a negated class like `[!...]`, as in bash and
+    /// most regular expression syntaxes.
+    pub fn caret_negation(mut self, enable: bool) -> Self {
+        self.caret_negation = enable;
+        self
+    }
+
+    /// Sets whether `**` is an ordinary `*` rather than a recursive wildcard,
+    /// as in POSIX shells and `fnmatch`. Any run of `*`s is then one `*`.
+    pub fn disable_recursive_wildcards(mut self, disable: bool) -> Self {
+        self.disable_recursive_wildcards = disable;
+        self
+    }
+
+    /// Sets whether Unicode property classes like `\p{L}` (a letter in any
+    /// script), `\p{Greek}` or the negated `\P{L}` are accepted, both on their
+    /// own and within `[...]`, so `[\p{L}\p{Nd}]*` matches localized names that
+    /// `[a-z0-9]*` can't. A single-letter property may be written without
+    /// braces, as in `\pL`. General categories, scripts and the other
+    /// properties of the `regex` crate are supported.
+    ///
+    /// The properties are only available with the `unicode` feature; without
+    /// it, patterns that use them fail to compile. As with
+    /// `shorthand_classes`, this shouldn't be enabled for patterns written
+    /// with backslashes as separators.
+    pub fn unicode_classes(mut self, enable: bool) -> Self {
+        self.unicode_classes = enable;
+        self
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{glob, MatchOptions, Pattern};
-    use std::path::Path;
+    use super::{
+        common_base, common_base_with, glob, literal_base, GlobBuilder, MatchMemo, MatchOptions,
+        ParseOptions, PathFlavor, Pattern, PatternErrorKind, Syntax,
+    };
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn test_pattern_from_str() {
@@ -1123,6 +5150,772 @@ mod test {
         assert!(Pattern::new("abc[!]").unwrap_err().pos == 3);
     }
 
+    #[test]
+    fn test_new_lossy() {
+        for &(p, s) in &[
+            ("abc[def", "abc[def"),
+            ("abc[!", "abc[!"),
+            ("abc[]", "abc[]"),
+            ("a[b]c[d", "abc[d"),
+            ("a/**b", "a/**b"),
+            ("a**/*.rs", "a**/lib.rs"),
+            ("a/*****", "a/*****"),
+            ("**/*.rs", "src/lib.rs"),
+            ("a/**", "a/b/c"),
+        ] {
+            assert!(Pattern::new_lossy(p).matches(s), "{:?} {:?}", p, s);
+        }
+        assert!(!Pattern::new_lossy("a/**b").matches("a/xb"));
+        assert_eq!(Pattern::new_lossy("*.rs"), Pattern::new("*.rs").unwrap());
+        assert_eq!(Pattern::new_lossy("abc[").as_str(), "abc[");
+    }
+
+    #[test]
+    fn test_syntax() {
+        let new = |p, syntax| Pattern::new_with(p, ParseOptions::for_syntax(syntax));
+        let literal_separator = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+
+        assert_eq!(ParseOptions::for_syntax(Syntax::Glob), ParseOptions::new());
+        assert!(new("a**b", Syntax::Glob).is_err());
+
+        let posix = new("a/**/b", Syntax::Posix).unwrap();
+        assert!(posix.matches_with("a/x/b", literal_separator));
+        assert!(!posix.matches_with("a/x/y/b", literal_separator));
+        assert!(new("a**b", Syntax::Posix).unwrap().matches("axb"));
+        let escaped = new("\\*\\[a]\\", Syntax::Posix).unwrap();
+        assert!(escaped.matches("*[a]\\"));
+        assert!(!escaped.matches("x[a]\\"));
+        assert!(new("[^a]", Syntax::Posix).unwrap().matches("^"));
+        assert!(!new("[^a]", Syntax::Fnmatch).unwrap().matches("^a"));
+        assert!(new("[^a]", Syntax::Fnmatch).unwrap().matches("b"));
+        assert!(!new("[^a]", Syntax::Fnmatch).unwrap().matches("a"));
+
+        let bash = new("**/{a,b\\,c}", Syntax::Bash).unwrap();
+        assert!(bash.matches_with("x/y/a", literal_separator));
+        assert!(bash.matches("b,c"));
+        assert!(!bash.matches("c"));
+        assert!(new("a**b", Syntax::Bash).unwrap().matches("axb"));
+
+        let gitignore = new("**/{a,b}", Syntax::Gitignore).unwrap();
+        assert!(gitignore.matches_with("x/y/{a,b}", literal_separator));
+        assert!(!gitignore.matches("x/y/a"));
+
+        let options = ParseOptions::for_syntax(Syntax::Posix);
+        assert_eq!(literal_base("a\\*b/c/*", options), Path::new("a*b/c"));
+        assert_eq!(
+            literal_base("a\\*b/c/*", ParseOptions::new()),
+            Path::new(".")
+        );
+    }
+
+    #[test]
+    fn test_dir_only() {
+        let options = MatchOptions::new();
+        let braces = ParseOptions::new().braces(true);
+        assert!(Pattern::new("a/").unwrap().is_dir_only());
+        assert!(Pattern::new("a/**/").unwrap().is_dir_only());
+        assert!(!Pattern::new("a/**").unwrap().is_dir_only());
+        assert!(!Pattern::new("a").unwrap().is_dir_only());
+        assert!(Pattern::new_with("{a,b}/", braces).unwrap().is_dir_only());
+        assert!(!Pattern::new_with("{a/,b}", braces).unwrap().is_dir_only());
+
+        let pattern = Pattern::new("*/").unwrap();
+        assert!(pattern.matches_path_is_dir(Path::new("a"), true, options));
+        assert!(pattern.matches_path_is_dir(Path::new("a//"), true, options));
+        assert!(!pattern.matches_path_is_dir(Path::new("a/"), false, options));
+        let pattern = Pattern::new("a").unwrap();
+        assert!(pattern.matches_path_is_dir(Path::new("a/"), true, options));
+        assert!(pattern.matches_path_is_dir(Path::new("a"), false, options));
+        let pattern = Pattern::new_with("{a/,b}", braces).unwrap();
+        assert!(pattern.matches_path_is_dir(Path::new("b"), false, options));
+        assert!(!pattern.matches_path_is_dir(Path::new("a"), false, options));
+        assert!(pattern.matches_path_is_dir(Path::new("a"), true, options));
+        assert!(Pattern::new("/")
+            .unwrap()
+            .matches_path_is_dir(Path::new("/"), true, options));
+
+        let pattern = Pattern::new("sr[c]/").unwrap();
+        assert!(pattern.matches_existing_path(Path::new("src"), options));
+        assert!(!pattern.matches_existing_path(Path::new("src/lib.rs"), options));
+        assert!(!Pattern::new("*.toml/")
+            .unwrap()
+            .matches_existing_path(Path::new("Cargo.toml"), options));
+    }
+
+    #[test]
+    fn test_matches_bytes() {
+        let options = MatchOptions::new();
+        let pattern = Pattern::new("a?b*").unwrap();
+        assert!(pattern.matches_bytes(b"axb", options));
+        assert!(pattern.matches_bytes(b"a\xffb\xfe\xfd", options));
+        assert!(!pattern.matches_bytes(b"a\xff\xffb", options));
+        // a truncated character is one invalid byte per byte
+        assert!(!pattern.matches_bytes(b"a\xe2\x82b", options));
+        assert!(Pattern::new("a??b")
+            .unwrap()
+            .matches_bytes(b"a\xe2\x82b", options));
+        assert!(Pattern::new("a?b")
+            .unwrap()
+            .matches_bytes("a\u{20ac}b".as_bytes(), options));
+        assert!(!Pattern::new("\u{FFFD}")
+            .unwrap()
+            .matches_bytes(b"\xff\xff", options));
+    }
+
+    #[test]
+    fn test_matches_path_components() {
+        let options = MatchOptions::new();
+        let matches = |p: &str, path: &str| {
+            Pattern::new(p)
+                .unwrap()
+                .matches_path_with(Path::new(path), options)
+        };
+        assert!(matches("a/b", "a/b"));
+        assert!(matches("a/b", "a//b"));
+        assert!(matches("a/b", "a/./b"));
+        assert!(matches("a/b", "a/b/"));
+        assert!(matches("a/*", "a/./b"));
+        assert!(matches("./a", "./a"));
+        assert!(!matches("a", "./a"));
+        assert!(!matches("a/b", "a/c/../b"));
+        assert!(matches("/a", "//a") == cfg!(not(windows)));
+        assert!(matches("/", "/"));
+
+        assert!(matches("a/", "a/"));
+        assert!(matches("a/", "a//"));
+        assert!(!matches("a/", "a"));
+        assert!(matches("some/**/", "some/./one/"));
+        assert!(!matches("some/**/", "some/one"));
+
+        if cfg!(windows) {
+            assert!(matches("a/b", "a\\b"));
+            assert!(matches("a/b", "a\\.\\b\\"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_matches_os() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let options = MatchOptions::new();
+        let name = OsStr::from_bytes(b"caf\xe9.txt");
+        let pattern = Pattern::new("*.txt").unwrap();
+        assert!(pattern.matches_os(name, options));
+        assert!(pattern.matches_file_name(name, options));
+        assert!(pattern.matches_path(Path::new(name)));
+        assert!(Pattern::new("src/*").unwrap().matches_path_is_dir(
+            &Path::new("src").join(name),
+            false,
+            options
+        ));
+        assert!(!Pattern::new("*.md").unwrap().matches_os(name, options));
+    }
+
+    #[test]
+    fn test_recursive_captures() {
+        let options = MatchOptions::new();
+        let literal_separator = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let captures = |p: &str, s: &'static str| {
+            let options = ParseOptions::new().braces(true);
+            Pattern::new_with(p, options)
+                .unwrap()
+                .recursive_captures(s, literal_separator)
+        };
+        assert_eq!(
+            captures("src/**/mod.rs", "src/a/b/mod.rs"),
+            Some(vec!["a/b"])
+        );
+        assert_eq!(captures("**/mod.rs", "mod.rs"), Some(vec![""]));
+        assert_eq!(captures("src/**", "src/a/b"), Some(vec!["a/b"]));
+        assert_eq!(
+            captures("a/**/b/**/c", "a/x/b/y/z/c"),
+            Some(vec!["x", "y/z"])
+        );
+        assert_eq!(captures("*.rs", "a.rs"), Some(vec![]));
+        assert_eq!(captures("*.rs", "a.md"), None);
+        assert_eq!(captures("{x,**}/*.md", "a/b/c.md"), Some(vec!["a/b"]));
+        assert_eq!(captures("{x,**}/*.md", "x/c.md"), Some(vec![]));
+
+        let windows = MatchOptions {
+            path_flavor: PathFlavor::Windows,
+            ..MatchOptions::new()
+        };
+        let pattern = Pattern::new("src/**/mod.rs").unwrap();
+        assert_eq!(
+            pattern.recursive_captures("src\\a\\b\\mod.rs", windows),
+            Some(vec!["a\\b"])
+        );
+        assert_eq!(
+            pattern.recursive_captures("src/a/mod.rs", options),
+            Some(vec!["a"])
+        );
+
+        // `*` takes as much as it can unless it can't match a separator
+        let pattern = Pattern::new("**/*.md").unwrap();
+        assert_eq!(
+            pattern.recursive_captures("a/b/c.md", options),
+            Some(vec![""])
+        );
+        assert_eq!(
+            pattern.recursive_captures("a/b/c.md", literal_separator),
+            Some(vec!["a/b"])
+        );
+    }
+
+    #[test]
+    fn test_with_options() {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let pattern = Pattern::new("a*b").unwrap();
+        assert!(pattern.matches("a/b"));
+        assert_eq!(pattern.options(), MatchOptions::new());
+
+        let pattern = pattern.with_options(options);
+        assert_eq!(pattern.options(), options);
+        assert!(!pattern.matches("a/b"));
+        assert!(!pattern.matches_path(Path::new("a/b")));
+        assert!(pattern.match_spans("a/b").is_none());
+        assert!(pattern.matches("ab"));
+        assert!(pattern.matches_with("a/b", MatchOptions::new()));
+        assert_ne!(pattern, Pattern::new("a*b").unwrap());
+
+        let joined = pattern.join_path_segment("c");
+        assert!(!joined.matches("a/b/c"));
+        assert!(joined.matches("ab/c"));
+
+        let braces = ParseOptions::new().braces(true);
+        let pattern = Pattern::new_with("{x,a*b}", braces)
+            .unwrap()
+            .with_options(options);
+        assert!(!pattern.matches("a/b"));
+        assert!(pattern.matches("ab"));
+    }
+
+    #[test]
+    fn test_join_tokens() {
+        let join = |a: &str, b: &str| {
+            let joined = Pattern::new(a).unwrap().join(&Pattern::new(b).unwrap());
+            let reparsed = Pattern::new(joined.as_str()).unwrap();
+            assert_eq!(joined.tokens, reparsed.tokens, "{:?} {:?}", a, b);
+            assert_eq!(joined.is_recursive, reparsed.is_recursive);
+            joined.original
+        };
+        assert_eq!(join("a", "b"), "a/b");
+        assert_eq!(join("a/", "b"), "a/b");
+        assert_eq!(join("a", "/b"), "a/b");
+        assert_eq!(join("a/", "/b"), "a//b");
+        assert_eq!(join("a/**", "b"), "a/**/b");
+        assert_eq!(join("a/**", "/b"), "a/**/b");
+        assert_eq!(join("**/", "*.rs"), "**/*.rs");
+        assert_eq!(join("a", "**"), "a/**");
+        assert_eq!(join("", "b"), "b");
+        assert_eq!(join("a", ""), "a");
+        assert_eq!(join("[*]", "?"), "[*]/?");
+
+        let braces = ParseOptions::new().braces(true);
+        let base = Pattern::new_with("{src,tests}", braces).unwrap();
+        let rule = Pattern::new_with("*.{rs,toml}", braces).unwrap();
+        let joined = base.join(&rule);
+        assert_eq!(joined.as_str(), "{src,tests}/*.{rs,toml}");
+        assert_eq!(joined.alternatives.len(), 4);
+        assert!(joined.matches("tests/a.toml"));
+        assert!(!joined.matches("tests/a.md"));
+        let joined = base.join_path_segment("*");
+        assert!(joined.matches("src/*"));
+        assert!(!joined.matches("src/a"));
+
+        let escaped = Pattern::new_with("a\\*", ParseOptions::for_syntax(Syntax::Posix)).unwrap();
+        let joined = escaped.join(&Pattern::new("b").unwrap());
+        assert!(joined.matches("a*/b"));
+        assert!(!joined.matches("ax/b"));
+    }
+
+    #[test]
+    fn test_escape_with() {
+        let inputs = [
+            "a\\*b",
+            "\\d\\w",
+            "\\\\d",
+            "{a,b}[c]?",
+            "\\",
+            "x\\",
+            "[!^]",
+            "**/\\{x}",
+        ];
+        let syntaxes = [
+            Syntax::Glob,
+            Syntax::Posix,
+            Syntax::Bash,
+            Syntax::Fnmatch,
+            Syntax::Gitignore,
+        ];
+        for s in &inputs {
+            for &syntax in &syntaxes {
+                for &shorthand_classes in &[false, true] {
+                    let options =
+                        ParseOptions::for_syntax(syntax).shorthand_classes(shorthand_classes);
+                    let escaped = Pattern::escape_with(s, options);
+                    let pattern = Pattern::new_with(&escaped, options).unwrap();
+                    assert_eq!(pattern.literal(), Some(s.to_string()), "{:?}", escaped);
+                }
+            }
+        }
+        assert_eq!(Pattern::escape("a\\d"), "a\\d");
+    }
+
+    #[test]
+    fn test_separator_exclusions() {
+        let any_char = MatchOptions {
+            any_char_excludes_separator: true,
+            ..MatchOptions::new()
+        };
+        let class = MatchOptions {
+            class_excludes_separator: true,
+            ..MatchOptions::new()
+        };
+        for &(p, s, with_any_char, with_class) in &[
+            ("a?b", "a/b", false, true),
+            ("a[/]b", "a/b", true, false),
+            ("a[!x]b", "a/b", true, false),
+            ("a*b", "a/b", true, true),
+            ("a/b", "a/b", true, true),
+        ] {
+            let pattern = Pattern::new(p).unwrap();
+            assert!(pattern.matches(s));
+            assert_eq!(pattern.matches_with(s, any_char), with_any_char, "{:?}", p);
+            assert_eq!(pattern.matches_with(s, class), with_class, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn test_require_recursive_component() {
+        let options = MatchOptions {
+            require_recursive_component: true,
+            ..MatchOptions::new()
+        };
+        let pattern = Pattern::new("a/**/b").unwrap();
+        assert!(pattern.matches("a/b"));
+        assert!(!pattern.matches_with("a/b", options));
+        assert!(pattern.matches_with("a/x/b", options));
+        assert!(pattern.matches_with("a/x/y/b", options));
+
+        let pattern = Pattern::new("a/**").unwrap();
+        assert!(pattern.matches("a/"));
+        assert!(!pattern.matches_with("a/", options));
+        assert!(pattern.matches_with("a/x", options));
+        assert!(!Pattern::new("**").unwrap().matches_with("", options));
+        assert!(!Pattern::new("**/b").unwrap().matches_with("b", options));
+        assert!(Pattern::new("**/b").unwrap().matches_with("x/b", options));
+        assert!(!Pattern::new("a/**/*").unwrap().matches_with("a/b", options));
+        assert!(Pattern::new("a/**/*")
+            .unwrap()
+            .matches_with("a/x/b", options));
+        assert!(!Pattern::new("**/*").unwrap().matches_with("b", options));
+        assert!(Pattern::new("**/*").unwrap().matches_with("x/b", options));
+    }
+
+    #[test]
+    fn test_require_recursive_component_linear_time() {
+        let options = MatchOptions {
+            require_recursive_component: true,
+            ..MatchOptions::new()
+        };
+        let linear = MatchOptions {
+            linear_time: true,
+            ..options
+        };
+        let patterns = [
+            "**", "**/*", "**/b", "a/**", "a/**/*", "a/**/b", "a/**/*b", "**/**/*",
+        ];
+        let inputs = [
+            "", "b", "a", "a/", "a/b", "a/x/b", "a/x/y/b", "x/b", "a/bb", "x/y",
+        ];
+        for p in &patterns {
+            let pattern = Pattern::new(p).unwrap();
+            for s in &inputs {
+                assert_eq!(
+                    pattern.matches_with(s, options),
+                    pattern.matches_with(s, linear),
+                    "{:?} {:?}",
+                    p,
+                    s
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_literal() {
+        assert_eq!(Pattern::new("").unwrap().literal(), Some(String::new()));
+        assert_eq!(
+            Pattern::new("a/[[]b[]]/c").unwrap().literal(),
+            Some("a/[b]/c".to_string())
+        );
+        assert_eq!(
+            Pattern::new("a/").unwrap().literal(),
+            Some("a/".to_string())
+        );
+        for p in &["?", "a*", "**", "[ab]", "[!a]", "a/**/b", "[.]", "a[/]b"] {
+            assert!(!Pattern::new(p).unwrap().is_literal(), "{:?}", p);
+        }
+
+        let options = ParseOptions::new().braces(true);
+        assert!(!Pattern::new_with("{a,b}", options).unwrap().is_literal());
+        assert_eq!(
+            Pattern::new_with("{a}", options).unwrap().literal(),
+            Some("{a}".to_string())
+        );
+        assert!(Pattern::new_with("x{a,a}", options)
+            .unwrap()
+            .literal()
+            .is_none());
+    }
+
+    #[test]
+    fn test_lenient_recursive_wildcards() {
+        let options = ParseOptions::new().lenient_recursive_wildcards(true);
+        let compile = |p| Pattern::new_with(p, options).unwrap();
+        assert_eq!(compile("a**b").tokens, Pattern::new("a*b").unwrap().tokens);
+        assert_eq!(compile("a**b").as_str(), "a**b");
+        assert!(compile("foo**").matches("foobar"));
+        assert!(compile("**.rs").matches("lib.rs"));
+        assert!(compile("a/***").matches("a/b"));
+        assert!(compile("a/**/b").matches("a/x/y/b"));
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        assert!(compile("a/**b").matches_with("a/xb", options));
+        assert!(!compile("a/**b").matches_with("a/x/b", options));
+        assert!(Pattern::new("a**b").is_err());
+        assert_eq!(Pattern::new_lenient("a**/b").unwrap(), compile("a**/b"));
+    }
+
+    #[test]
+    fn test_shorthand_classes() {
+        let options = ParseOptions::new().shorthand_classes(true);
+        let compile = |p| Pattern::new_with(p, options).unwrap();
+        assert!(compile("log-\\d\\d\\d\\d.txt").matches("log-2024.txt"));
+        assert!(!compile("log-\\d\\d\\d\\d.txt").matches("log-20x4.txt"));
+        assert!(compile("\\w*").matches("_tmp"));
+        assert!(!compile("\\w*").matches("-tmp"));
+        assert!(compile("a\\sb").matches("a\tb"));
+        assert!(compile("[\\d.]*").matches(".5"));
+        assert!(compile("[!\\d]*").matches("x1"));
+        assert!(!compile("[!\\d]*").matches("1x"));
+        assert!(compile("[\\sx-z]").matches("y"));
+        assert!(compile("a\\x").matches("a\\x"));
+        assert!(compile("a\\").matches("a\\"));
+        assert!(Pattern::new("\\d").unwrap().matches("\\d"));
+        assert!(!Pattern::new("\\d").unwrap().matches("1"));
+    }
+
+    #[test]
+    fn test_braces() {
+        let options = ParseOptions::new().braces(true);
+        let pattern = Pattern::new_with("src/**/*.{rs,toml}", options).unwrap();
+        assert_eq!(pattern.as_str(), "src/**/*.{rs,toml}");
+        assert!(pattern.matches("src/lib.rs"));
+        assert!(pattern.matches("src/a/Cargo.toml"));
+        assert!(!pattern.matches("src/README.md"));
+        assert!(pattern.matches_chars("src/a/b.toml".chars(), MatchOptions::new()));
+        assert!(!pattern.matches_chars("src/a/b.md".chars(), MatchOptions::new()));
+        assert!(!Pattern::new("*.{rs,toml}").unwrap().matches("lib.rs"));
+        assert!(Pattern::new("*.{rs,toml}").unwrap().matches("a.{rs,toml}"));
+
+        let spans = Pattern::new_with("{a,b}?", options)
+            .unwrap()
+            .match_spans("bc")
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+        assert!(Pattern::new_with("{a,b}?", options)
+            .unwrap()
+            .match_spans("cc")
+            .is_none());
+
+        assert_eq!(
+            Pattern::new_with("{a,[b}", options).unwrap_err().kind(),
+            PatternErrorKind::Syntax
+        );
+        assert_eq!(
+            Pattern::new_with("{a,b}", options)
+                .unwrap()
+                .to_portable_string(),
+            "{a,b}"
+        );
+        assert_eq!(Pattern::escape("{a,b}"), "[{]a,b[}]");
+        assert!(Pattern::new_with(&Pattern::escape("{a,b}"), options)
+            .unwrap()
+            .matches("{a,b}"));
+
+        let mut builder = GlobBuilder::new();
+        builder.parse_options(options);
+        assert_eq!(builder.glob("x/{a/b,c}").unwrap_err().pos, 2);
+        assert_eq!(builder.glob("x/{**,c}/y").unwrap_err().pos, 2);
+
+        let pattern = Pattern::new_with("frame_{001..120}.png", options).unwrap();
+        assert!(pattern.matches("frame_007.png"));
+        assert!(pattern.matches("frame_120.png"));
+        assert!(!pattern.matches("frame_7.png"));
+        assert!(!pattern.matches("frame_121.png"));
+
+        let pattern = Pattern::new_with("frame_{001..001}.png", options).unwrap();
+        assert_eq!(pattern.as_str(), "frame_{001..001}.png");
+        assert!(pattern.matches("frame_001.png"));
+        assert!(!pattern.matches("frame_{001..001}.png"));
+        let pattern = Pattern::new_with("{5..5}", options).unwrap();
+        assert!(pattern.matches("5"));
+        assert!(!pattern.matches("{5..5}"));
+    }
+
+    #[test]
+    fn test_match_memo() {
+        let patterns = [Pattern::new("*.rs").unwrap()];
+        let options = MatchOptions::new();
+        let mut memo = MatchMemo::default();
+
+        // two entries of `a` are queued, and one is taken out
+        memo.enter(Path::new("a"), 0);
+        assert!(memo.matches(Path::new("a/x.rs"), "x.rs", 1, &patterns, 0, options));
+        assert_eq!(memo.dirs[0].results[&0].get("x.rs"), Some(&true));
+
+        // the same name is matched again from the memo
+        memo.dirs[0]
+            .results
+            .get_mut(&0)
+            .unwrap()
+            .insert("x.rs".to_string(), false);
+        assert!(!memo.matches(Path::new("a/x.rs"), "x.rs", 1, &patterns, 0, options));
+
+        // `a/x.rs` was a directory, whose entries are queued above the
+        // other entry of `a`
+        memo.enter(Path::new("a/x.rs"), 1);
+        assert_eq!(memo.dirs.len(), 2);
+
+        // taking out the last entry of `a` forgets about `a/x.rs`
+        assert!(!memo.matches(Path::new("a/y.c"), "y.c", 0, &patterns, 0, options));
+        assert_eq!(memo.dirs.len(), 1);
+        memo.enter(Path::new("b"), 0);
+        assert_eq!(memo.dirs.len(), 1);
+        assert_eq!(memo.dirs[0].dir, Path::new("b"));
+
+        // entries of other directories are matched without the memo
+        assert!(memo.matches(Path::new("c/z.rs"), "z.rs", 0, &patterns, 0, options));
+        assert!(memo.dirs[0].results.is_empty());
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let options = ParseOptions::new()
+            .max_len(Some(6))
+            .max_tokens(Some(4))
+            .max_class_len(Some(2));
+        assert!(Pattern::new_with("[ab]*", options).is_ok());
+
+        let err = Pattern::new_with("abcdefg", options).unwrap_err();
+        assert_eq!(err.kind(), PatternErrorKind::TooLong);
+        assert_eq!(err.pos, 6);
+
+        let err = Pattern::new_with("abcde", options).unwrap_err();
+        assert_eq!(err.kind(), PatternErrorKind::TooManyTokens);
+        assert_eq!(err.pos, 4);
+
+        let err = Pattern::new_with("[!xyz]", options).unwrap_err();
+        assert_eq!(err.kind(), PatternErrorKind::ClassTooLarge);
+        assert_eq!(err.pos, 0);
+
+        let options = ParseOptions::new().max_wildcards(Some(3));
+        assert!(Pattern::new_with("**/a?*[ab]", options).is_ok());
+        let err = Pattern::new_with("a*b?c*d*", options).unwrap_err();
+        assert_eq!(err.kind(), PatternErrorKind::TooManyWildcards);
+        assert_eq!(err.pos, 7);
+        assert!(Pattern::new_with("{*,?}{*,?}", ParseOptions::untrusted()).is_ok());
+        let long = "*a".repeat(33);
+        let err = Pattern::new_with(&long, ParseOptions::untrusted()).unwrap_err();
+        assert_eq!(err.kind(), PatternErrorKind::TooManyWildcards);
+
+        assert_eq!(
+            Pattern::new("a[").unwrap_err().kind(),
+            PatternErrorKind::Syntax
+        );
+    }
+
+    #[test]
+    fn test_max_steps() {
+        let pat = Pattern::new("a*b").unwrap();
+        let s = "aaaaaaaaaaaaaaaaaaaaaaaaab";
+        assert!(pat.matches(s));
+
+        let options = MatchOptions {
+            max_steps: Some(10),
+            ..MatchOptions::new()
+        };
+        assert!(!pat.matches_with(s, options));
+        assert!(Pattern::new("a*").unwrap().matches_with("ab", options));
+    }
+
+    #[test]
+    fn test_many_wildcards() {
+        // deeper than the stack would allow if each `*` took a stack frame
+        let pat = Pattern::new(&"*a".repeat(50_000)).unwrap();
+        let s = "a".repeat(50_000);
+        assert!(pat.matches(&s));
+        assert!(pat.match_spans(&s).is_some());
+
+        let options = MatchOptions {
+            max_steps: Some(1_000_000),
+            ..MatchOptions::new()
+        };
+        assert!(!pat.matches_with(&format!("{}b", &s[1..]), options));
+    }
+
+    #[test]
+    fn test_matches_many() {
+        let pat = Pattern::new("a*").unwrap();
+        let strs: Vec<String> = (0..1_000)
+            .map(|i| format!("{}{}", ["a", "b"][i % 2], i))
+            .collect();
+        let expected: Vec<bool> = (0..1_000).map(|i| i % 2 == 0).collect();
+        assert_eq!(pat.matches_many(&strs), expected);
+        assert!(pat.matches_many::<&str>(&[]).is_empty());
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        let pat = pat.with_options(options);
+        assert_eq!(pat.matches_many(&["A", "b"]), vec![true, false]);
+        #[cfg(feature = "rayon")]
+        {
+            assert_eq!(pat.par_matches_many(&strs), expected);
+            assert_eq!(pat.par_matches_many(&["A", "b"]), vec![true, false]);
+        }
+    }
+
+    #[test]
+    fn test_linear_time() {
+        // exponential for a backtracking matcher without `max_steps`
+        let pat = Pattern::new(&format!("{}b", "a*".repeat(1_000))).unwrap();
+        let s = "a".repeat(1_000);
+        let options = MatchOptions {
+            linear_time: true,
+            ..MatchOptions::new()
+        };
+        assert!(pat.matches_with(&format!("{}b", s), options));
+        assert!(!pat.matches_with(&format!("{}c", s), options));
+        assert!(!pat.matches_with(&format!("{}b", &s[1..]), options));
+        assert!(MatchOptions::untrusted().linear_time);
+
+        let braces = ParseOptions::new().braces(true);
+        let pat = Pattern::new_with("{*.rs,src/**}", braces).unwrap();
+        assert!(pat.matches_with("lib.rs", options));
+        assert!(pat.matches_with("src/a/b", options));
+        assert!(!pat.matches_with("lib.md", options));
+    }
+
+    #[test]
+    fn test_literal_base() {
+        assert_eq!(literal_base("*.rs", ParseOptions::new()), Path::new("."));
+        assert_eq!(
+            literal_base("src/*.rs", ParseOptions::new()),
+            Path::new("src")
+        );
+        assert_eq!(
+            literal_base("src/bin/main.rs", ParseOptions::new()),
+            Path::new("src/bin")
+        );
+        assert_eq!(
+            literal_base("./src/**/*.rs", ParseOptions::new()),
+            Path::new("./src")
+        );
+        assert_eq!(
+            literal_base("/usr/*/lib/*", ParseOptions::new()),
+            Path::new("/usr")
+        );
+        assert_eq!(
+            literal_base("a/b?/c/*", ParseOptions::new()),
+            Path::new("a")
+        );
+
+        let braces = ParseOptions::new().braces(true);
+        assert_eq!(literal_base("a/{b,c}/*", braces), Path::new("a"));
+        assert_eq!(literal_base("a/{b}/*", braces), Path::new("a/{b}"));
+    }
+
+    #[test]
+    fn test_common_base() {
+        let base = |patterns: &[&str]| common_base(patterns);
+        assert_eq!(
+            base(&["src/**/*.rs", "src/bin/*.rs"]),
+            Some(PathBuf::from("src"))
+        );
+        assert_eq!(
+            base(&["src/bin/a.rs", "src/bin/b.rs"]),
+            Some(PathBuf::from("src/bin"))
+        );
+        assert_eq!(
+            base(&["./src/*.rs", "src/lib/*"]),
+            Some(PathBuf::from("src"))
+        );
+        assert_eq!(base(&["src/*", "tests/*"]), Some(PathBuf::from(".")));
+        assert_eq!(base(&["*"]), Some(PathBuf::from(".")));
+        assert_eq!(base(&[]), Some(PathBuf::from(".")));
+        assert_eq!(
+            base(&["/usr/lib/*", "/usr/*/lib"]),
+            Some(PathBuf::from("/usr"))
+        );
+        assert_eq!(base(&["/usr/*", "/etc/*"]), Some(PathBuf::from("/")));
+        assert_eq!(base(&["/usr/*", "usr/*"]), None);
+        assert_eq!(base(&["../a/*", "a/*"]), None);
+        assert_eq!(base(&["../a/*", "../b/*"]), Some(PathBuf::from("..")));
+
+        let braces = ParseOptions::new().braces(true);
+        assert_eq!(
+            common_base_with(&["a/{b,c}/*", "a/b/*"], braces),
+            Some(PathBuf::from("a"))
+        );
+    }
+
+    #[test]
+    fn test_match_spans() {
+        let ranges = |pattern: &str, s: &str| {
+            Pattern::new(pattern)
+                .unwrap()
+                .match_spans(s)
+                .map(|spans| spans.into_iter().map(|span| span.range).collect::<Vec<_>>())
+        };
+        assert_eq!(ranges("a*c", "abbc"), Some(vec![0..1, 1..3, 3..4]));
+        assert_eq!(ranges("a*c", "abbd"), None);
+        assert_eq!(ranges("*x", "x"), Some(vec![0..0, 0..1]));
+        assert_eq!(ranges("[é]?", "éa"), Some(vec![0..2, 2..3]));
+        assert_eq!(
+            ranges("src/**/mod.rs", "src/a/b/mod.rs"),
+            Some(vec![
+                0..1,
+                1..2,
+                2..3,
+                3..4,
+                4..8,
+                8..9,
+                9..10,
+                10..11,
+                11..12,
+                12..13,
+                13..14
+            ])
+        );
+        assert_eq!(ranges("a/**", "a/b/c"), Some(vec![0..1, 1..2, 2..5]));
+    }
+
     #[test]
     fn test_glob_errors() {
         assert!(glob("a/**b").err().unwrap().pos == 4);
@@ -1182,6 +5975,19 @@ mod test {
                 .unwrap()
                 .next()
                 .is_some());
+
+            // UNC paths are searched from their share, which has to be literal
+            assert_eq!(glob(r"\\server\sh*re\*").unwrap_err().pos, 11);
+            assert_eq!(glob(r"\\?\UNC\s?rver\share\*").unwrap_err().pos, 9);
+            assert!(glob(r"\\server\share\logs\**\*.log").is_ok());
+
+            // a drive letter with wildcards searches every drive it matches
+            let letter = &root_with_device.to_str().unwrap()[..1];
+            let drive = PathBuf::from(format!("{}:\\", letter));
+            let drives = glob("?:/").unwrap().map(Result::unwrap).collect::<Vec<_>>();
+            assert!(drives.contains(&drive), "{:?} {:?}", drives, drive);
+            let pattern = format!("[{}]:/*", letter.to_lowercase());
+            assert!(glob(&pattern).unwrap().next().is_some());
         }
         win()
     }
@@ -1251,12 +6057,77 @@ mod test {
         assert!(pat.matches("abc/.abc"));
         assert!(!pat.matches("ab.c"));
         assert!(!pat.matches("abc/ab.c"));
+
+        // a trailing **/ only matches directories
+        let pat = Pattern::new("some/**/").unwrap();
+        assert!(pat.matches("some/"));
+        assert!(pat.matches("some/one/"));
+        assert!(pat.matches("some/one/two/"));
+        assert!(!pat.matches("some/one"));
+        assert!(!pat.matches("some/one/needle.txt"));
+        assert!(pat.matches_path(Path::new("some/one/")));
+        assert!(!pat.matches_path(Path::new("some/needle.txt")));
+        assert!(Pattern::new("some/**").unwrap().matches("some/one"));
+    }
+
+    #[test]
+    fn test_write_matches_to() {
+        let mut out = Vec::new();
+        let errors = glob("Cargo.t*ml")
+            .unwrap()
+            .write_matches_to(&mut out, b'\0')
+            .unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(out, b"Cargo.toml\0");
+
+        let mut out = Vec::new();
+        glob("src/lib.r[s]")
+            .unwrap()
+            .write_matches_to(&mut out, b'\n')
+            .unwrap();
+        assert_eq!(
+            out,
+            format!("src{}lib.rs\n", ::std::path::MAIN_SEPARATOR).into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_path_flavor() {
+        let windows = MatchOptions {
+            path_flavor: PathFlavor::Windows,
+            require_literal_separator: true,
+            require_literal_leading_dot: true,
+            ..MatchOptions::new()
+        };
+        let unix = MatchOptions {
+            path_flavor: PathFlavor::Unix,
+            ..windows
+        };
+
+        let pat = Pattern::new("C:/Users/*/*.log").unwrap();
+        assert!(pat.matches_with("C:\\Users\\me\\app.log", windows));
+        assert!(pat.matches_with("C:/Users\\me/app.log", windows));
+        assert!(!pat.matches_with("C:\\Users\\me\\app.log", unix));
+        assert!(!pat.matches_with("C:\\Users\\a\\b\\app.log", windows));
+
+        let pat = Pattern::new("logs/*.txt").unwrap();
+        assert!(!pat.matches_with("logs/a\\b.txt", windows));
+        assert!(pat.matches_with("logs/a\\b.txt", unix));
+        assert!(!Pattern::new("a/*")
+            .unwrap()
+            .matches_with("a\\.hidden", windows));
+
+        let pat = Pattern::new("**/*.txt").unwrap();
+        for &(s, expected) in &[("a\\b\\c.txt", true), ("a\\b\\.c.txt", false)] {
+            assert_eq!(pat.matches_with(s, windows), expected);
+            assert_eq!(pat.matches_chars(s.chars(), windows), expected);
+        }
     }
 
     #[test]
     fn test_lots_of_files() {
         // this is a good test because it touches lots of differently named files
-        glob("/*/*/*/*").unwrap().skip(10000).next();
+        glob("/*/*/*/*").unwrap().nth(10000);
     }
 
     #[test]
@@ -1341,6 +6212,7 @@ mod test {
             case_sensitive: false,
             require_literal_separator: false,
             require_literal_leading_dot: false,
+            ..MatchOptions::new()
         };
 
         assert!(pat.matches_with("aBcDeFg", options));
@@ -1358,11 +6230,13 @@ mod test {
             case_sensitive: false,
             require_literal_separator: false,
             require_literal_leading_dot: false,
+            ..MatchOptions::new()
         };
         let options_case_sensitive = MatchOptions {
             case_sensitive: true,
             require_literal_separator: false,
             require_literal_leading_dot: false,
+            ..MatchOptions::new()
         };
 
         assert!(pat_within.matches_with("a", options_case_insensitive));
@@ -1380,11 +6254,13 @@ mod test {
             case_sensitive: true,
             require_literal_separator: true,
             require_literal_leading_dot: false,
+            ..MatchOptions::new()
         };
         let options_not_require_literal = MatchOptions {
             case_sensitive: true,
             require_literal_separator: false,
             require_literal_leading_dot: false,
+            ..MatchOptions::new()
         };
 
         assert!(Pattern::new("abc/def")
@@ -1420,11 +6296,13 @@ mod test {
             case_sensitive: true,
             require_literal_separator: false,
             require_literal_leading_dot: true,
+            ..MatchOptions::new()
         };
         let options_not_require_literal_leading_dot = MatchOptions {
             case_sensitive: true,
             require_literal_separator: false,
             require_literal_leading_dot: false,
+            ..MatchOptions::new()
         };
 
         let f = |options| {
@@ -1488,6 +6366,311 @@ mod test {
         assert!(!f(options_require_literal_leading_dot));
     }
 
+    #[test]
+    fn test_join() {
+        let join = |a: &str, b: &str| {
+            Pattern::new(a)
+                .unwrap()
+                .join(&Pattern::new(b).unwrap())
+                .original
+        };
+        assert_eq!(join("a", "b"), "a/b");
+        assert_eq!(join("a/", "b"), "a/b");
+        assert_eq!(join("a", "/b"), "a/b");
+        assert_eq!(join("", "b"), "b");
+        assert_eq!(join("a/**", ""), "a/**");
+        assert_eq!(join("a/**", "*.rs"), "a/**/*.rs");
+
+        let pat = Pattern::new("logs/*").unwrap().join_path_segment("a?");
+        assert!(pat.matches("logs/x/a?"));
+        assert!(!pat.matches("logs/x/ab"));
+    }
+
+    #[test]
+    fn test_matches_file_name() {
+        use std::ffi::OsStr;
+
+        let options = MatchOptions::new();
+        let pat = Pattern::new("a?c").unwrap();
+        assert!(pat.matches_file_name(OsStr::new("abc"), options));
+        assert!(pat.matches_with("a/c", options));
+        assert!(!pat.matches_file_name(OsStr::new("a/c"), options));
+
+        let pat = Pattern::new("a/c").unwrap();
+        assert!(!pat.matches_file_name(OsStr::new("a/c"), options));
+
+        let options = MatchOptions {
+            require_literal_leading_dot: true,
+            ..MatchOptions::new()
+        };
+        let pat = Pattern::new("*").unwrap();
+        assert!(pat.matches_file_name(OsStr::new("a.b"), options));
+        assert!(!pat.matches_file_name(OsStr::new(".b"), options));
+        assert!(Pattern::new(".*")
+            .unwrap()
+            .matches_file_name(OsStr::new(".b"), options));
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        use std::ffi::OsString;
+        use std::io;
+        use std::path::PathBuf;
+        use std::thread;
+        use std::time::Duration;
+        use {FileKind, FileSystem, GlobBuilder, GlobErrorKind};
+
+        // `slow` takes far longer to list than the timeout
+        #[derive(Debug)]
+        struct SlowFs;
+
+        impl FileSystem for SlowFs {
+            fn metadata(&self, _: &Path) -> io::Result<FileKind> {
+                Ok(FileKind::Dir)
+            }
+
+            fn symlink_metadata(&self, _: &Path) -> io::Result<FileKind> {
+                Ok(FileKind::Dir)
+            }
+
+            fn read_dir(&self, path: &Path) -> io::Result<Vec<(OsString, FileKind)>> {
+                match path.file_name().and_then(|name| name.to_str()) {
+                    Some("fast") => Ok(vec![(OsString::from("a"), FileKind::File)]),
+                    Some("slow") => {
+                        thread::sleep(Duration::from_secs(5));
+                        Ok(vec![(OsString::from("a"), FileKind::File)])
+                    }
+                    _ => Ok(vec![
+                        (OsString::from("fast"), FileKind::Dir),
+                        (OsString::from("slow"), FileKind::Dir),
+                    ]),
+                }
+            }
+
+            fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+                Ok(path.to_path_buf())
+            }
+
+            fn read_link(&self, _: &Path) -> io::Result<PathBuf> {
+                Err(io::Error::new(io::ErrorKind::InvalidInput, "not a link"))
+            }
+        }
+
+        let results: Vec<_> = GlobBuilder::new()
+            .filesystem(SlowFs)
+            .read_timeout(Some(Duration::from_millis(200)))
+            .glob("*/?")
+            .unwrap()
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &PathBuf::from("fast/a"));
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.path(), Path::new("slow"));
+        assert_eq!(err.error().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(err.kind(), GlobErrorKind::Timeout);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        use std::ffi::OsString;
+        use std::io;
+        use std::path::PathBuf;
+        use {FileKind, FileSystem, GlobBuilder, ReadErrors};
+
+        // `b` can't be listed
+        #[derive(Debug)]
+        struct DeniedFs;
+
+        impl FileSystem for DeniedFs {
+            fn metadata(&self, _: &Path) -> io::Result<FileKind> {
+                Ok(FileKind::Dir)
+            }
+
+            fn symlink_metadata(&self, _: &Path) -> io::Result<FileKind> {
+                Ok(FileKind::Dir)
+            }
+
+            fn read_dir(&self, path: &Path) -> io::Result<Vec<(OsString, FileKind)>> {
+                match path.to_str() {
+                    Some(".") => Ok(vec![
+                        (OsString::from("a"), FileKind::Dir),
+                        (OsString::from("b"), FileKind::Dir),
+                        (OsString::from("c"), FileKind::Dir),
+                    ]),
+                    Some("b") => Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied")),
+                    _ => Ok(vec![(OsString::from("x"), FileKind::File)]),
+                }
+            }
+
+            fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+                Ok(path.to_path_buf())
+            }
+
+            fn read_link(&self, _: &Path) -> io::Result<PathBuf> {
+                Err(io::Error::new(io::ErrorKind::InvalidInput, "not a link"))
+            }
+        }
+
+        let results = |policy: ReadErrors| -> Vec<Result<PathBuf, PathBuf>> {
+            GlobBuilder::new()
+                .filesystem(DeniedFs)
+                .read_errors(policy)
+                .glob("*/?")
+                .unwrap()
+                .map(|r| r.map_err(|e| e.path().to_path_buf()))
+                .collect()
+        };
+        let a = Ok(PathBuf::from("a/x"));
+        let b = Err(PathBuf::from("b"));
+        let c = Ok(PathBuf::from("c/x"));
+        assert_eq!(
+            results(ReadErrors::Yield),
+            vec![a.clone(), b.clone(), c.clone()]
+        );
+        assert_eq!(results(ReadErrors::Skip), vec![a.clone(), c]);
+        assert_eq!(results(ReadErrors::Abort), vec![a, b]);
+    }
+
+    #[test]
+    fn test_glob_many_reads_once() {
+        use std::collections::HashMap;
+        use std::ffi::OsString;
+        use std::io;
+        use std::path::PathBuf;
+        use std::sync::{Arc, Mutex};
+        use {FileKind, FileSystem, GlobBuilder};
+
+        // `.` holds the directories `a` and `b`, which each hold a file `x`
+        #[derive(Debug, Default)]
+        struct CountingFs {
+            reads: Mutex<HashMap<PathBuf, usize>>,
+        }
+
+        impl FileSystem for CountingFs {
+            fn metadata(&self, path: &Path) -> io::Result<FileKind> {
+                match path.file_name().and_then(|name| name.to_str()) {
+                    Some("x") => Ok(FileKind::File),
+                    _ => Ok(FileKind::Dir),
+                }
+            }
+
+            fn symlink_metadata(&self, path: &Path) -> io::Result<FileKind> {
+                self.metadata(path)
+            }
+
+            fn read_dir(&self, path: &Path) -> io::Result<Vec<(OsString, FileKind)>> {
+                *self
+                    .reads
+                    .lock()
+                    .unwrap()
+                    .entry(path.to_path_buf())
+                    .or_insert(0) += 1;
+                match path.to_str() {
+                    Some(".") => Ok(vec![
+                        (OsString::from("a"), FileKind::Dir),
+                        (OsString::from("b"), FileKind::Dir),
+                    ]),
+                    _ => Ok(vec![(OsString::from("x"), FileKind::File)]),
+                }
+            }
+
+            fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+                Ok(path.to_path_buf())
+            }
+
+            fn read_link(&self, _: &Path) -> io::Result<PathBuf> {
+                Err(io::Error::new(io::ErrorKind::InvalidInput, "not a link"))
+            }
+        }
+
+        let fs = Arc::new(CountingFs::default());
+        let mut builder = GlobBuilder::new();
+        builder.filesystem = Some(fs.clone());
+        let matches: Vec<_> = builder
+            .glob_many(&["*/x", "*/?", "a/*", "**/*"])
+            .unwrap()
+            .per_pattern()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(matches.len(), 2 + 2 + 1 + 4);
+        let reads = fs.reads.lock().unwrap();
+        assert_eq!(reads.len(), 3);
+        assert!(reads.values().all(|&n| n == 1), "{:?}", *reads);
+    }
+
+    #[test]
+    fn test_glob_error_kind() {
+        use std::io;
+        use GlobErrorKind;
+
+        let kind = |e| GlobErrorKind::classify(&e);
+        assert_eq!(
+            kind(io::Error::new(io::ErrorKind::PermissionDenied, "denied")),
+            GlobErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            kind(io::Error::new(io::ErrorKind::NotFound, "gone")),
+            GlobErrorKind::Io(io::ErrorKind::NotFound)
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            kind(io::Error::from_raw_os_error(20)),
+            GlobErrorKind::NotADirectory
+        );
+        #[cfg(target_os = "linux")]
+        assert_eq!(kind(io::Error::from_raw_os_error(40)), GlobErrorKind::Loop);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_matches_with_stats() {
+        let options = MatchOptions::new();
+        let (matched, stats) = Pattern::new("abc")
+            .unwrap()
+            .matches_with_stats("abc", options);
+        assert!(matched);
+        assert_eq!(stats.comparisons, 3);
+        assert_eq!(stats.backtracks, 0);
+        assert_eq!(stats.max_depth, 0);
+
+        let (matched, stats) = Pattern::new("*c")
+            .unwrap()
+            .matches_with_stats("abc", options);
+        assert!(matched);
+        assert_eq!(stats.backtracks, 2);
+        assert_eq!(stats.max_depth, 1);
+
+        let (matched, stats) = Pattern::new("*a*b")
+            .unwrap()
+            .matches_with_stats("aaa", options);
+        assert!(!matched);
+        assert_eq!(stats.max_depth, 2);
+        assert!(stats.steps >= stats.comparisons);
+    }
+
+    #[test]
+    fn test_matches_dir_entry() {
+        use std::fs;
+
+        let options = MatchOptions::new();
+        let entries: Vec<_> = fs::read_dir(".").unwrap().map(|e| e.unwrap()).collect();
+        let matching = |pattern: &str| -> Vec<String> {
+            let pat = Pattern::new(pattern).unwrap();
+            let mut names: Vec<_> = entries
+                .iter()
+                .filter(|e| pat.matches_dir_entry(e, options))
+                .map(|e| e.file_name().into_string().unwrap())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(matching("Cargo.t*ml"), vec!["Cargo.toml"]);
+        assert_eq!(matching("s?c"), vec!["src"]);
+        assert_eq!(matching("s?c/"), vec!["src"]);
+        assert!(matching("Cargo.toml/").is_empty());
+        assert!(matching("./src").is_empty());
+    }
+
     #[test]
     fn test_matches_path() {
         // on windows, (Path::new("a/b").as_str().unwrap() == "a\\b"), so this
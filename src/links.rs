@@ -0,0 +1,67 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::path::PathBuf;
+
+use {GlobError, Paths};
+
+/// An iterator that yields the same matches as a `Paths` iterator, each
+/// paired with the target of the symbolic link it names, if it is one.
+///
+/// Targets are read with `read_link` as matches are found, and are returned
+/// exactly as stored in the link, so a relative target is relative to the
+/// directory containing the link. A link whose target can't be read is
+/// yielded as a `GlobError`.
+///
+/// Created by `Paths::with_link_targets`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::glob;
+///
+/// for entry in glob("/etc/alternatives/*").unwrap().with_link_targets() {
+///     if let Ok((path, Some(target))) = entry {
+///         println!("{} -> {}", path.display(), target.display());
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LinkTargets {
+    paths: Paths,
+}
+
+impl LinkTargets {
+    pub(crate) fn new(paths: Paths) -> Self {
+        Self { paths }
+    }
+}
+
+impl Iterator for LinkTargets {
+    type Item = Result<(PathBuf, Option<PathBuf>), GlobError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = match self.paths.next_match() {
+            Some(Ok(path)) => path,
+            Some(Err(e)) => return Some(Err(e)),
+            None => return None,
+        };
+
+        let target = if path.is_symlink {
+            match self.paths.config.fs().read_link(&path) {
+                Ok(target) => Some(target),
+                Err(error) => return Some(Err(GlobError::new(path.into_path(), error))),
+            }
+        } else {
+            None
+        };
+        Some(self.paths.finish(path).map(|path| (path, target)))
+    }
+}
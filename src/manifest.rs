@@ -0,0 +1,194 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::btree_map::{self, BTreeMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use {GlobError, Paths};
+
+/// The metadata recorded for each path in a `Manifest`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FileStamp {
+    /// The size of the file in bytes.
+    pub len: u64,
+
+    /// The last modification time of the file.
+    pub modified: SystemTime,
+}
+
+/// The paths matched by a glob along with the size and modification time of
+/// each of them, which can be compared with a manifest of an earlier run to
+/// find out what changed.
+///
+/// Unlike a `Fingerprint`, a manifest tells which paths changed, at the cost
+/// of keeping all of them. Manifests aren't persisted by this crate, but
+/// their entries can be, and a manifest can be rebuilt from them with
+/// `insert`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::glob;
+///
+/// let before = glob("assets/**/*").unwrap().manifest().unwrap();
+/// // ... some time later ...
+/// let after = glob("assets/**/*").unwrap().manifest().unwrap();
+/// for path in before.diff(&after).modified {
+///     println!("{} changed", path.display());
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Manifest {
+    entries: BTreeMap<PathBuf, FileStamp>,
+}
+
+/// The differences between two `Manifest`s, as returned by
+/// `Manifest::diff`. Each list is sorted.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct ManifestDiff {
+    /// The paths that are only in the newer manifest.
+    pub added: Vec<PathBuf>,
+
+    /// The paths that are only in the older manifest.
+    pub removed: Vec<PathBuf>,
+
+    /// The paths that are in both manifests, but with a different size or
+    /// modification time.
+    pub modified: Vec<PathBuf>,
+}
+
+impl ManifestDiff {
+    /// Return if the manifests have the same paths with the same metadata.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Constructs an empty `Manifest`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn from_paths(mut paths: Paths) -> Result<Self, GlobError> {
+        let config = paths.config.clone();
+        let mut manifest = Self::new();
+        for path in &mut paths {
+            let path = path?;
+            match config.fs().size_and_modified(&path) {
+                Ok((len, modified)) => manifest.insert(path, FileStamp { len, modified }),
+                Err(error) => return Err(GlobError::new(path, error)),
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Adds a path with its metadata, replacing any previous entry for it.
+    pub fn insert(&mut self, path: PathBuf, stamp: FileStamp) {
+        self.entries.insert(path, stamp);
+    }
+
+    /// Return the metadata recorded for `path`, if it's in the manifest.
+    pub fn get(&self, path: &Path) -> Option<&FileStamp> {
+        self.entries.get(path)
+    }
+
+    /// The number of paths in the manifest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return if the manifest has no paths.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// An iterator over the paths and their metadata, sorted by path.
+    pub fn iter<'a>(&'a self) -> btree_map::Iter<'a, PathBuf, FileStamp> {
+        self.entries.iter()
+    }
+
+    /// Compares this manifest with a newer one.
+    pub fn diff(&self, newer: &Manifest) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+        for (path, stamp) in &self.entries {
+            match newer.entries.get(path) {
+                None => diff.removed.push(path.clone()),
+                Some(new_stamp) if new_stamp != stamp => diff.modified.push(path.clone()),
+                Some(_) => (),
+            }
+        }
+        diff.added = newer
+            .entries
+            .keys()
+            .filter(|path| !self.entries.contains_key(*path))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl<'a> IntoIterator for &'a Manifest {
+    type Item = (&'a PathBuf, &'a FileStamp);
+    type IntoIter = btree_map::Iter<'a, PathBuf, FileStamp>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileStamp, Manifest};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn stamp(len: u64, secs: u64) -> FileStamp {
+        FileStamp {
+            len,
+            modified: UNIX_EPOCH + Duration::from_secs(secs),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut old = Manifest::new();
+        old.insert(PathBuf::from("a"), stamp(1, 10));
+        old.insert(PathBuf::from("b"), stamp(2, 10));
+        old.insert(PathBuf::from("c"), stamp(3, 10));
+        let mut new = Manifest::new();
+        new.insert(PathBuf::from("b"), stamp(2, 20));
+        new.insert(PathBuf::from("c"), stamp(3, 10));
+        new.insert(PathBuf::from("d"), stamp(4, 10));
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, vec![PathBuf::from("d")]);
+        assert_eq!(diff.removed, vec![PathBuf::from("a")]);
+        assert_eq!(diff.modified, vec![PathBuf::from("b")]);
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(new.diff(&old).added, vec![PathBuf::from("a")]);
+
+        assert_eq!(new.len(), 3);
+        assert_eq!(new.get(Path::new("d")), Some(&stamp(4, 10)));
+        let paths: Vec<_> = new.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("b"), PathBuf::from("c"), PathBuf::from("d")]
+        );
+    }
+
+    #[test]
+    fn test_manifest_from_glob() {
+        let manifest = ::glob("src/*.rs").unwrap().manifest().unwrap();
+        assert!(manifest.get(Path::new("src/lib.rs")).unwrap().len > 0);
+        assert!(manifest.diff(&manifest.clone()).is_empty());
+    }
+}
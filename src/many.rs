@@ -0,0 +1,203 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use {GlobBuilder, GlobError, GlobResult, Paths, PatternError, SharedListings};
+
+/// An iterator that yields the `Path`s matching any of several patterns.
+///
+/// The matches of each pattern are yielded in turn, in the order the patterns
+/// were given, unless `sorted` is used to merge them into a single sorted
+/// sequence. The patterns share the directory listings they read, so a
+/// directory searched by several of them is only read once. Its listing is
+/// kept for as long as the iterator lives. A path matched by more than one pattern is only yielded the
+/// first time; use `per_pattern` to get every match of every pattern
+/// instead.
+///
+/// See the `glob_many` function for more details.
+#[derive(Debug)]
+pub struct MultiPaths {
+    sources: Sources,
+    seen: HashSet<PathBuf>,
+}
+
+/// An iterator that yields every match of several patterns along with the
+/// index of the pattern it matched, so a path matched by two patterns is
+/// yielded twice.
+///
+/// Created by `MultiPaths::per_pattern`.
+#[derive(Debug)]
+pub struct PerPatternPaths {
+    sources: Sources,
+}
+
+// The iterators of each pattern, read one after the other or merged.
+#[derive(Debug)]
+struct Sources {
+    paths: Vec<Paths>,
+    current: usize,
+    sorted: bool,
+    // the next item of each iterator, when merging
+    heads: Vec<Option<GlobResult>>,
+}
+
+/// Return an iterator that produces all the `Path`s that match any of the
+/// given patterns, using default match options.
+///
+/// This may return an error if any of the patterns is invalid.
+///
+/// See the `MultiPaths` documentation for more information.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::glob_many;
+///
+/// // `README.md` is only yielded once
+/// for path in glob_many(&["*.md", "README*"]).unwrap().filter_map(Result::ok) {
+///     println!("{}", path.display());
+/// }
+/// ```
+pub fn glob_many<I>(patterns: I) -> Result<MultiPaths, PatternError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    GlobBuilder::new().glob_many(patterns)
+}
+
+impl MultiPaths {
+    pub(crate) fn new<I>(config: &GlobBuilder, patterns: I) -> Result<Self, PatternError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut config = config.clone();
+        config.listings = Some(SharedListings::default());
+        let mut paths = Vec::new();
+        for pattern in patterns {
+            paths.push(config.glob(pattern.as_ref())?);
+        }
+        Ok(Self {
+            sources: Sources {
+                heads: paths.iter().map(|_| None).collect(),
+                paths,
+                current: 0,
+                sorted: false,
+            },
+            seen: HashSet::new(),
+        })
+    }
+
+    /// Return an iterator that yields the matches of all the patterns as a
+    /// single sequence in alphabetical order, rather than those of each
+    /// pattern in turn.
+    ///
+    /// Since each pattern's matches are already yielded in alphabetical
+    /// order, this is done by merging them as they're produced, and errors
+    /// are ordered by the path they're for. It carries over to
+    /// `per_pattern`, in which case matches of the same path are yielded in
+    /// the order of the patterns.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use glob::glob_many;
+    ///
+    /// // `docs/guide.md` and `src/lib.rs` are interleaved correctly
+    /// for path in glob_many(&["src/**/*.rs", "docs/**/*.md"]).unwrap().sorted() {
+    ///     println!("{}", path.unwrap().display());
+    /// }
+    /// ```
+    pub fn sorted(mut self) -> Self {
+        self.sources.sorted = true;
+        self
+    }
+
+    /// Return an iterator that yields one item per match of each pattern,
+    /// tagged with the index of the pattern, rather than one per path.
+    ///
+    /// This must be called before iteration starts; matches that have
+    /// already been yielded are not repeated.
+    pub fn per_pattern(self) -> PerPatternPaths {
+        PerPatternPaths {
+            sources: self.sources,
+        }
+    }
+}
+
+impl Iterator for MultiPaths {
+    type Item = GlobResult;
+
+    fn next(&mut self) -> Option<GlobResult> {
+        loop {
+            match self.sources.next()? {
+                (Ok(path), _) => {
+                    if self.seen.insert(path.clone()) {
+                        return Some(Ok(path));
+                    }
+                }
+                (Err(e), _) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl Iterator for PerPatternPaths {
+    type Item = Result<(PathBuf, usize), GlobError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (result, i) = self.sources.next()?;
+        Some(result.map(|path| (path, i)))
+    }
+}
+
+impl Sources {
+    // The next item along with the index of the pattern it's for.
+    fn next(&mut self) -> Option<(GlobResult, usize)> {
+        if !self.sorted {
+            loop {
+                match self.paths.get_mut(self.current)?.next() {
+                    Some(result) => return Some((result, self.current)),
+                    None => self.current += 1,
+                }
+            }
+        }
+
+        for (head, paths) in self.heads.iter_mut().zip(&mut self.paths) {
+            if head.is_none() {
+                *head = paths.next();
+            }
+        }
+        let mut min: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            if let Some(ref result) = *head {
+                let smaller = match min {
+                    Some(m) => key(result) < key(self.heads[m].as_ref().unwrap()),
+                    None => true,
+                };
+                if smaller {
+                    min = Some(i);
+                }
+            }
+        }
+        let i = min?;
+        Some((self.heads[i].take().unwrap(), i))
+    }
+}
+
+fn key(result: &GlobResult) -> &Path {
+    match *result {
+        Ok(ref path) => path,
+        Err(ref e) => e.path(),
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Matching by simulating a pattern as a nondeterministic finite automaton.
+//!
+//! The states of the automaton are token indices, with `tokens.len()` being
+//! the accepting state. Unlike `Pattern::matches_from`, this never needs to
+//! look back at the input, so it works on a stream of characters, and it
+//! takes time proportional to the length of the input times the number of
+//! tokens.
+
+use PatternToken::{AnyChar, AnyExcept, AnyRecursiveSequence, AnySequence, AnyWithin, Char};
+use {chars_eq, in_char_specifiers, MatchOptions, Pattern};
+
+// Return if `chars` matches `pattern`, see `Pattern::matches_chars`.
+pub(crate) fn matches<I>(pattern: &Pattern, chars: I, options: MatchOptions) -> bool
+where
+    I: IntoIterator<Item = char>,
+{
+    let patterns = pattern.branches();
+    let (states, follows_separator) = match run(&patterns, chars, options) {
+        Some(end) => end,
+        None => return false,
+    };
+    // a trailing `**/` only matches directories, but isn't a token
+    patterns.iter().zip(&states).any(|(pattern, states)| {
+        accepts(pattern, states, options) && (follows_separator || !pattern.is_dir_only())
+    })
+}
+
+// Return if `chars` is the start of a string that may match `pattern`, see
+// `Pattern::could_match`.
+pub(crate) fn could_match<I>(pattern: &Pattern, chars: I, options: MatchOptions) -> bool
+where
+    I: IntoIterator<Item = char>,
+{
+    run(&pattern.branches(), chars, options).is_some()
+}
+
+// Feeds `chars` to `patterns`, the alternatives of a pattern with braces,
+// which are simulated side by side. Returns the states of each afterwards and
+// whether the last character was a separator, or `None` as soon as none of
+// them can match.
+fn run<I>(patterns: &[&Pattern], chars: I, options: MatchOptions) -> Option<(Vec<Vec<usize>>, bool)>
+where
+    I: IntoIterator<Item = char>,
+{
+    let mut states: Vec<Vec<usize>> = patterns.iter().map(|p| initial(p, options)).collect();
+    let mut follows_separator = true;
+    for c in chars {
+        for (pattern, states) in patterns.iter().zip(&mut states) {
+            *states = step(pattern, states, c, follows_separator, options);
+        }
+        if states.iter().all(|states| states.is_empty()) {
+            return None;
+        }
+        follows_separator = options.is_separator(c);
+    }
+    Some((states, follows_separator))
+}
+
+// The states before any input has been read.
+pub(crate) fn initial(pattern: &Pattern, options: MatchOptions) -> Vec<usize> {
+    closure(pattern, vec![0], !options.require_recursive_component)
+}
+
+// Return if the input matches once it ends in `states`. The end of the input
+// counts as the start of a component, since a `**` that has consumed the
+// rest of the input matches regardless of where it ends. If `**` has to
+// match a component, `step` already accepts once a trailing one has.
+pub(crate) fn accepts(pattern: &Pattern, states: &[usize], options: MatchOptions) -> bool {
+    let skips_recursive = !options.require_recursive_component;
+    closure(pattern, states.to_vec(), skips_recursive).last() == Some(&pattern.tokens.len())
+}
+
+// Adds the tokens reachable without consuming a character: a `*` may match
+// nothing, and so may a `**` at the start of a component, unless
+// `follows_separator` is false because it has to match one. The result is
+// sorted and free of duplicates, and takes time proportional to the number of
+// tokens however many `states` there are.
+pub(crate) fn closure(
+    pattern: &Pattern,
+    states: Vec<usize>,
+    follows_separator: bool,
+) -> Vec<usize> {
+    let mut marked = vec![false; pattern.tokens.len() + 1];
+    for i in states {
+        marked[i] = true;
+    }
+    // skipping only ever moves forward, so one pass reaches everything
+    for (i, token) in pattern.tokens.iter().enumerate() {
+        let skips = match *token {
+            AnySequence => true,
+            AnyRecursiveSequence => follows_separator,
+            _ => false,
+        };
+        if marked[i] && skips {
+            marked[i + 1] = true;
+        }
+    }
+    (0..marked.len()).filter(|&i| marked[i]).collect()
+}
+
+pub(crate) fn step(
+    pattern: &Pattern,
+    states: &[usize],
+    c: char,
+    follows_separator: bool,
+    options: MatchOptions,
+) -> Vec<usize> {
+    let is_sep = options.is_separator(c);
+    let hidden = follows_separator && options.require_literal_leading_dot && c == '.';
+    let literal_sep = options.require_literal_separator && is_sep;
+    let mut next = Vec::new();
+    for &i in states {
+        let token = match pattern.tokens.get(i) {
+            Some(token) => token,
+            None => continue,
+        };
+        let matched = match *token {
+            AnyChar if is_sep && options.literal_separator(false) => false,
+            AnyWithin(..) | AnyExcept(..) if is_sep && options.literal_separator(true) => false,
+            AnySequence => !(hidden || literal_sep),
+            AnyRecursiveSequence => !hidden,
+            AnyChar | AnyWithin(..) | AnyExcept(..) if hidden => false,
+            AnyChar => true,
+            AnyWithin(ref specifiers) => in_char_specifiers(specifiers, c, options),
+            AnyExcept(ref specifiers) => !in_char_specifiers(specifiers, c, options),
+            Char(c2) => chars_eq(c, c2, options),
+        };
+        if !matched {
+            continue;
+        }
+        let target = match *token {
+            AnySequence | AnyRecursiveSequence => i,
+            _ => i + 1,
+        };
+        next.push(target);
+        // a `**` that has to match a component may end after it's matched
+        // one, or anywhere once it's trailing
+        let ends = *token == AnyRecursiveSequence
+            && options.require_recursive_component
+            && (is_sep || i + 1 == pattern.tokens.len());
+        if ends {
+            next.push(i + 1);
+        }
+    }
+    // `closure` also removes the duplicates
+    closure(
+        pattern,
+        next,
+        is_sep && !options.require_recursive_component,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use {MatchOptions, ParseOptions, Pattern};
+
+    #[test]
+    fn test_matches_chars() {
+        let patterns = [
+            "", "a", "a*", "*a", "a?c", "*.rs", "**/*.rs", "a/**", "a/**/b", "[a-c]*", "[!a]?",
+            "*/*", ".*", "*a*b*", "**", "a/**/", "a?b", "a[/]b", "a[!x]b",
+        ];
+        let strings = [
+            "",
+            "a",
+            "ab",
+            "abc",
+            "a.rs",
+            "x/a.rs",
+            ".rs",
+            "a/.b/c.rs",
+            "a/b",
+            "a/x/y/b",
+            "b",
+            ".a",
+            "x/y",
+            "aXbYb",
+            "a/",
+            "/a",
+        ];
+        for &literal_separator in &[false, true] {
+            for &literal_leading_dot in &[false, true] {
+                for &(recursive_component, excludes) in
+                    &[(false, false), (true, false), (false, true)]
+                {
+                    let options = MatchOptions {
+                        any_char_excludes_separator: excludes,
+                        class_excludes_separator: excludes,
+                        require_literal_separator: literal_separator,
+                        require_literal_leading_dot: literal_leading_dot,
+                        require_recursive_component: recursive_component,
+                        ..MatchOptions::new()
+                    };
+                    for p in &patterns {
+                        let pattern = Pattern::new(p).unwrap();
+                        for s in &strings {
+                            assert_eq!(
+                                pattern.matches_chars(s.chars(), options),
+                                pattern.matches_with(s, options),
+                                "{:?} {:?} {:?}",
+                                p,
+                                s,
+                                options
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_could_match() {
+        let options = MatchOptions::new();
+        let could_match = |p: &str, s: &str| Pattern::new(p).unwrap().could_match(s, options);
+        assert!(could_match("src/**/*.rs", "src/foo"));
+        assert!(could_match("src/**/*.rs", "src/foo/"));
+        assert!(could_match("src/**/*.rs", ""));
+        assert!(could_match("src/**/*.rs", "s"));
+        assert!(!could_match("src/**/*.rs", "target/foo"));
+        assert!(could_match("*/b", "a/"));
+        assert!(!could_match("a/b", "a/bc"));
+
+        let literal_separator = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let pattern = Pattern::new("*/*.rs").unwrap();
+        assert!(pattern.could_match("src/", literal_separator));
+        assert!(!pattern.could_match("src/a/", literal_separator));
+        assert!(pattern.could_match("src/a/", options));
+
+        // every prefix of a match may still match
+        let braces = ParseOptions::new().braces(true);
+        for p in &["a/**/b", "*.{rs,md}", "[!a]?", "**/x"] {
+            let pattern = Pattern::new_with(p, braces).unwrap();
+            for s in &["a/x/b", "x.md", "bc", "y/x"] {
+                if pattern.matches(s) {
+                    for (i, _) in s.char_indices() {
+                        assert!(pattern.could_match(&s[..i], options), "{:?} {:?}", p, s);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::path::PathBuf;
+
+use {GlobError, Paths};
+
+/// An iterator that yields the matches of a `Paths` iterator while setting
+/// aside its errors in a `Vec`.
+///
+/// Created by `Paths::partition_into`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::glob;
+///
+/// let mut errors = Vec::new();
+/// for path in glob("/var/log/**/*.log").unwrap().partition_into(&mut errors) {
+///     println!("{}", path.display());
+/// }
+/// for e in errors {
+///     eprintln!("couldn't read {}: {}", e.path().display(), e.error());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PartitionedPaths<'a> {
+    paths: Paths,
+    errors: &'a mut Vec<GlobError>,
+}
+
+impl<'a> PartitionedPaths<'a> {
+    pub(crate) fn new(paths: Paths, errors: &'a mut Vec<GlobError>) -> Self {
+        Self { paths, errors }
+    }
+}
+
+impl<'a> Iterator for PartitionedPaths<'a> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            match self.paths.next() {
+                Some(Ok(path)) => return Some(path),
+                Some(Err(e)) => self.errors.push(e),
+                None => return None,
+            }
+        }
+    }
+}
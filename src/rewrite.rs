@@ -0,0 +1,355 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::path;
+
+use CharSpecifier::{CharRange, SingleChar};
+use PatternToken::{AnyChar, AnyExcept, AnyRecursiveSequence, AnySequence, AnyWithin, Char};
+use {CharSpecifier, Pattern, PatternToken};
+
+// The counterpart of `c` in the other case, if it has exactly one.
+fn other_case(c: char) -> Option<char> {
+    let mut lower = c.to_lowercase();
+    let mut upper = c.to_uppercase();
+    match (lower.next(), lower.next(), upper.next(), upper.next()) {
+        (Some(l), None, Some(u), None) if l != u => Some(if c == l { u } else { l }),
+        _ => None,
+    }
+}
+
+pub(crate) fn case_insensitive(pattern: &Pattern) -> Pattern {
+    if !pattern.alternatives.is_empty() {
+        let mut folded = pattern.clone();
+        folded.alternatives = pattern.alternatives.iter().map(case_insensitive).collect();
+        folded.original = pattern_to_string(&folded, false);
+        return folded;
+    }
+    let tokens: Vec<PatternToken> = pattern
+        .tokens
+        .iter()
+        .map(|token| match *token {
+            Char(c) => match other_case(c) {
+                Some(other) => {
+                    let mut chars = [c, other];
+                    chars.sort();
+                    AnyWithin(chars.iter().map(|&c| SingleChar(c)).collect())
+                }
+                None => Char(c),
+            },
+            AnyWithin(ref specifiers) => AnyWithin(fold_specifiers(specifiers)),
+            AnyExcept(ref specifiers) => AnyExcept(fold_specifiers(specifiers)),
+            ref token => token.clone(),
+        })
+        .collect();
+    Pattern {
+        original: to_string(&tokens, false),
+        tokens,
+        is_recursive: pattern.is_recursive,
+        alternatives: Vec::new(),
+        options: pattern.options,
+    }
+}
+
+// Adds the other case of every letter in `specifiers`.
+fn fold_specifiers(specifiers: &[CharSpecifier]) -> Vec<CharSpecifier> {
+    let mut folded = specifiers.to_vec();
+    for specifier in specifiers {
+        match *specifier {
+            SingleChar(c) => folded.extend(other_case(c).map(SingleChar)),
+            CharRange(start, end) => {
+                // only ASCII letters within a range are folded, since the
+                // other case of a run of non-ASCII letters is rarely a run
+                for &(from, to) in &[('a', 'z'), ('A', 'Z')] {
+                    let start = if start > from { start } else { from };
+                    let end = if end < to { end } else { to };
+                    if start <= end {
+                        folded.push(CharRange(
+                            other_case(start).unwrap(),
+                            other_case(end).unwrap(),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    folded.sort();
+    folded.dedup();
+    folded
+}
+
+// Writes `pattern` back out in pattern syntax, like `to_string`. The
+// alternatives of a pattern with braces are written within a single pair of
+// them.
+pub(crate) fn pattern_to_string(pattern: &Pattern, portable: bool) -> String {
+    if pattern.alternatives.is_empty() {
+        return to_string(&pattern.tokens, portable);
+    }
+    let alternatives: Vec<String> = pattern
+        .alternatives
+        .iter()
+        .map(|p| write(&p.tokens, portable, true))
+        .collect();
+    format!("{{{}}}", alternatives.join(","))
+}
+
+// Writes `tokens` back out in pattern syntax, escaping what needs to be. If
+// `portable` is set, separators are written as `/` and a literal `\` as a
+// character class, since it's a separator on Windows.
+pub(crate) fn to_string(tokens: &[PatternToken], portable: bool) -> String {
+    write(tokens, portable, false)
+}
+
+// Like `to_string`, also escaping commas if the tokens are written as an
+// alternative within braces.
+fn write(tokens: &[PatternToken], portable: bool, in_braces: bool) -> String {
+    let mut s = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match *token {
+            Char(c) if portable && path::is_separator(c) => s.push('/'),
+            Char('\\') if portable => s.push_str("[\\]"),
+            Char(',') if in_braces => s.push_str("[,]"),
+            Char(c) => push_char(&mut s, c),
+            AnyChar => s.push('?'),
+            AnySequence => s.push('*'),
+            AnyRecursiveSequence => {
+                s.push_str("**");
+                // the separator after `**` is part of the token
+                if i + 1 < tokens.len() {
+                    s.push(if portable { '/' } else { path::MAIN_SEPARATOR });
+                }
+            }
+            AnyWithin(ref specifiers) => push_class(&mut s, specifiers, false),
+            AnyExcept(ref specifiers) => push_class(&mut s, specifiers, true),
+        }
+    }
+    s
+}
+
+fn push_char(s: &mut String, c: char) {
+    match c {
+        '?' | '*' | '[' | ']' | '{' | '}' => {
+            s.push('[');
+            s.push(c);
+            s.push(']');
+        }
+        c => s.push(c),
+    }
+}
+
+// Writes a character class. Within one, `]` is only literal first, `-` is
+// only literal first or last, and a leading `!` negates the class, so the
+// specifiers are rearranged to avoid ambiguity, splitting off the ends of
+// ranges that start or end with `]` or `-`.
+pub(crate) fn push_class(s: &mut String, specifiers: &[CharSpecifier], negated: bool) {
+    let mut items = Vec::new();
+    for specifier in specifiers {
+        let (mut start, mut end) = match *specifier {
+            SingleChar(c) => (c, c),
+            CharRange(start, end) => (start, end),
+        };
+        if start > end {
+            // matches nothing; keep it as is, unless it'd be ambiguous
+            if !"]-".contains(start) && !"]-".contains(end) {
+                items.push((start, end));
+            }
+            continue;
+        }
+        if start != end && (start == ']' || start == '-') {
+            items.push((start, start));
+            start = ::std::char::from_u32(start as u32 + 1).unwrap();
+        }
+        if start != end && (end == ']' || end == '-') {
+            items.push((end, end));
+            end = ::std::char::from_u32(end as u32 - 1).unwrap();
+        }
+        items.push((start, end));
+    }
+    let bracket = items.contains(&(']', ']'));
+    let mut dash = items.contains(&('-', '-'));
+    items.retain(|&item| item != (']', ']') && item != ('-', '-'));
+
+    let mut body = String::new();
+    if bracket {
+        body.push(']');
+    }
+    if !negated && !bracket && items.first().map_or(false, |&(c, _)| c == '!') {
+        if items.len() > 1 {
+            items.swap(0, 1);
+        } else if dash {
+            // `[-!]`
+            body.push('-');
+            dash = false;
+        } else {
+            // a class of just `!` can't be written
+            s.push('!');
+            return;
+        }
+    }
+    for &(start, end) in &items {
+        body.push(start);
+        if start != end {
+            body.push('-');
+            body.push(end);
+        }
+    }
+    if dash {
+        body.push('-');
+    }
+    if body.is_empty() {
+        // `[]` isn't a class, but an empty one is either any character or
+        // none of them
+        s.push_str(if negated { "?" } else { "[b-a]" });
+        return;
+    }
+
+    s.push('[');
+    if negated {
+        s.push('!');
+    }
+    s.push_str(&body);
+    s.push(']');
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pattern_to_string, to_string};
+    use {MatchOptions, ParseOptions, Pattern};
+
+    #[test]
+    fn test_to_string() {
+        for &p in &[
+            "abc",
+            "a?c*",
+            "**/*.rs",
+            "a/**",
+            "[[]x[]]y[?][*]",
+            "[]]",
+            "[!]]",
+            "[a-z0-9_]",
+            "[!a-c]",
+            "[-a]",
+            "[a-]",
+            "[]-a]",
+            "[]-]]",
+            "[a-]x]",
+            "[!!]",
+            "[a!]",
+            "[!-]",
+            "[z-a]",
+        ] {
+            let pattern = Pattern::new(p).unwrap();
+            let s = to_string(&pattern.tokens, false);
+            let reparsed = Pattern::new(&s).unwrap();
+            assert_eq!(
+                reparsed.tokens.len(),
+                pattern.tokens.len(),
+                "{} -> {}",
+                p,
+                s
+            );
+            for c in (0u8..128).map(|b| b as char) {
+                let c = c.to_string();
+                for t in &[c.clone(), format!("x{}", c), format!("{}y", c)] {
+                    assert_eq!(
+                        pattern.matches(t),
+                        reparsed.matches(t),
+                        "{} -> {}: {}",
+                        p,
+                        s,
+                        t
+                    );
+                }
+            }
+        }
+        assert_eq!(
+            to_string(&Pattern::new("[!]a]").unwrap().tokens, false),
+            "[!]a]"
+        );
+        assert_eq!(
+            to_string(&Pattern::new("[?]").unwrap().tokens, false),
+            "[?]"
+        );
+        assert_eq!(
+            to_string(&Pattern::new("{a,b}").unwrap().tokens, false),
+            "[{]a,b[}]"
+        );
+    }
+
+    #[test]
+    fn test_pattern_to_string() {
+        let options = ParseOptions::new().braces(true);
+        let pattern = Pattern::new_with("*.{rs,[,]x,[{]}", options).unwrap();
+        let s = pattern_to_string(&pattern, false);
+        assert_eq!(s, "{*.rs,*.[,]x,*.[{]}");
+        let reparsed = Pattern::new_with(&s, options).unwrap();
+        for t in &["a.rs", "a.,x", "a.{", "a.x", "a.{}"] {
+            assert_eq!(pattern.matches(t), reparsed.matches(t), "{}", t);
+        }
+
+        let folded = pattern.to_case_insensitive();
+        assert_eq!(folded.as_str(), "{*.[Rr][Ss],*.[,][Xx],*.[{]}");
+        assert!(folded.matches("A.RS"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        for &p in &["abc", "*.TXT", "[a-c]x", "[!a-cX]", "[0-z]", "ß", "[é]"] {
+            let pattern = Pattern::new(p).unwrap();
+            let folded = pattern.to_case_insensitive();
+            assert_eq!(Pattern::new(folded.as_str()).unwrap(), folded);
+            for s in &[
+                "ABC", "abc", "aBc", "x.txt", "Y.TxT", "Bx", "bX", "dx", "D", "d", "x", "X", "_",
+            ] {
+                assert_eq!(
+                    pattern.matches_with(s, options),
+                    folded.matches(s),
+                    "{} -> {}: {}",
+                    p,
+                    folded,
+                    s
+                );
+            }
+        }
+        assert_eq!(
+            Pattern::new("a1").unwrap().to_case_insensitive().as_str(),
+            "[Aa]1"
+        );
+        assert_eq!(
+            Pattern::new("[!b-d]")
+                .unwrap()
+                .to_case_insensitive()
+                .as_str(),
+            "[!B-Db-d]"
+        );
+        assert!(Pattern::new("é")
+            .unwrap()
+            .to_case_insensitive()
+            .matches("É"));
+    }
+
+    #[test]
+    fn test_to_portable_string() {
+        let portable = |p: &str| Pattern::new(p).unwrap().to_portable_string();
+        assert_eq!(portable("src/**/*.rs"), "src/**/*.rs");
+        assert_eq!(portable("a/**"), "a/**");
+        assert_eq!(portable("[?][]]"), "[?][]]");
+        if cfg!(windows) {
+            assert_eq!(portable("src\\**\\*.rs"), "src/**/*.rs");
+            assert_eq!(portable("C:\\x"), "C:/x");
+        } else {
+            assert_eq!(portable("a\\b"), "a[\\]b");
+            assert!(Pattern::new(&portable("a\\b")).unwrap().matches("a\\b"));
+        }
+    }
+}
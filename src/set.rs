@@ -0,0 +1,321 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use {os_str_lossy, MatchOptions, Pattern, PatternError};
+
+/// An ordered list of include and exclude patterns, evaluated by default the
+/// way `.gitignore` files are: the last pattern that matches a path decides
+/// whether it's part of the set. See `precedence` to have the first one
+/// decide instead.
+///
+/// A path that matches none of the patterns is not part of the set, so a set
+/// made up only of exclusions never matches anything.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::PatternSet;
+///
+/// let set: PatternSet = "**/*.rs\n!**/generated/*.rs".parse().unwrap();
+/// assert!(set.matches("src/lib.rs"));
+/// assert!(!set.matches("src/generated/bindings.rs"));
+/// assert!(!set.matches("README.md"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct PatternSet {
+    rules: Vec<(Pattern, bool)>,
+    precedence: Precedence,
+}
+
+/// Which of the patterns of a `PatternSet` that match a path decides whether
+/// it's part of the set.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Precedence {
+    /// The first matching pattern wins, as with rsync's filter rules.
+    FirstMatch,
+
+    /// The last matching pattern wins, as with `.gitignore` files. This is
+    /// the default.
+    LastMatch,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Default for Precedence {
+    fn default() -> Self {
+        Precedence::LastMatch
+    }
+}
+
+impl PatternSet {
+    /// Constructs an empty `PatternSet`, which matches nothing.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            precedence: Precedence::LastMatch,
+        }
+    }
+
+    /// Parses a list of patterns, one per line.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, and trailing
+    /// whitespace is removed. A pattern prefixed with `!` excludes what it
+    /// matches. Since patterns have no escape syntax of their own, a leading
+    /// `\` can be used to start a pattern with a literal `#` or `!`.
+    ///
+    /// This is the format commonly accepted by `--exclude-from` style
+    /// options.
+    pub fn parse(s: &str) -> Result<Self, PatternSetError> {
+        let mut set = Self::new();
+        for (i, line) in s.lines().enumerate() {
+            // `trim_end` requires Rust 1.30
+            #[allow(deprecated)]
+            let line = line.trim_right();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            set.add(line)
+                .map_err(|error| PatternSetError { line: i + 1, error })?;
+        }
+        Ok(set)
+    }
+
+    /// Constructs a `PatternSet` from a list of rules, each of which is
+    /// added with `add`.
+    ///
+    /// The `line` of a `PatternSetError` is the position of the invalid rule
+    /// in the list, counting from 1.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use glob::PatternSet;
+    ///
+    /// let set = PatternSet::from_rules(&["**/*.rs", "!**/target/**"]).unwrap();
+    /// assert!(set.matches("src/main.rs"));
+    /// assert!(!set.matches("target/debug/build/out.rs"));
+    /// ```
+    pub fn from_rules<I>(rules: I) -> Result<Self, PatternSetError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut set = Self::new();
+        for (i, rule) in rules.into_iter().enumerate() {
+            set.add(rule.as_ref())
+                .map_err(|error| PatternSetError { line: i + 1, error })?;
+        }
+        Ok(set)
+    }
+
+    /// Reads and parses a file of patterns, see `parse` for the format.
+    ///
+    /// An invalid pattern is reported as an error of kind
+    /// `io::ErrorKind::InvalidData` wrapping a `PatternSetError`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        Self::parse(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Sets which matching pattern decides whether a path is part of the
+    /// set. Defaults to `Precedence::LastMatch`.
+    pub fn precedence(&mut self, precedence: Precedence) -> &mut Self {
+        self.precedence = precedence;
+        self
+    }
+
+    /// Appends a rule: a pattern whose matches are included in the set, or
+    /// excluded from it if it's prefixed with `!`. As in `parse`, a leading
+    /// `\` lets a pattern start with a literal `#` or `!`, but the rule is
+    /// otherwise taken as it is.
+    ///
+    /// This may return an error if the pattern is invalid.
+    pub fn add(&mut self, rule: &str) -> Result<&mut Self, PatternError> {
+        if rule.starts_with('!') {
+            Ok(self.exclude(Pattern::new(&rule[1..])?))
+        } else if rule.starts_with("\\#") || rule.starts_with("\\!") {
+            Ok(self.include(Pattern::new(&rule[1..])?))
+        } else {
+            Ok(self.include(Pattern::new(rule)?))
+        }
+    }
+
+    /// Appends a pattern whose matches are included in the set.
+    pub fn include(&mut self, pattern: Pattern) -> &mut Self {
+        self.rules.push((pattern, false));
+        self
+    }
+
+    /// Appends a pattern whose matches are excluded from the set.
+    pub fn exclude(&mut self, pattern: Pattern) -> &mut Self {
+        self.rules.push((pattern, true));
+        self
+    }
+
+    /// The number of patterns in the set.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Return if the set has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Return if the given `str` is part of this set using the default match
+    /// options (i.e. `MatchOptions::new()`).
+    pub fn matches(&self, str: &str) -> bool {
+        self.matches_with(str, MatchOptions::new())
+    }
+
+    /// Return if the given `Path` is part of this set using the default match
+    /// options. Like `Pattern::matches_os`, each byte that isn't part of
+    /// valid UTF-8 is only matched by a wildcard.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        self.matches_path_with(path, MatchOptions::new())
+    }
+
+    /// Return if the given `str` is part of this set using the specified
+    /// match options.
+    pub fn matches_with(&self, str: &str, options: MatchOptions) -> bool {
+        let matches = |rule: &&(Pattern, bool)| rule.0.matches_with(str, options);
+        let rule = match self.precedence {
+            Precedence::FirstMatch => self.rules.iter().find(matches),
+            _ => self.rules.iter().rev().find(matches),
+        };
+        rule.map_or(false, |rule| !rule.1)
+    }
+
+    /// Return if the given `Path` is part of this set using the specified
+    /// match options, see `matches_path`.
+    pub fn matches_path_with(&self, path: &Path, options: MatchOptions) -> bool {
+        self.matches_with(&os_str_lossy(path.as_os_str()), options)
+    }
+}
+
+impl FromStr for PatternSet {
+    type Err = PatternSetError;
+
+    fn from_str(s: &str) -> Result<Self, PatternSetError> {
+        Self::parse(s)
+    }
+}
+
+/// An error parsing a list of patterns, see `PatternSet::parse`.
+#[derive(Debug)]
+pub struct PatternSetError {
+    /// The line the invalid pattern is on, counting from 1.
+    pub line: usize,
+
+    /// The error in the pattern.
+    pub error: PatternError,
+}
+
+impl Error for PatternSetError {
+    fn description(&self) -> &str {
+        self.error.msg
+    }
+
+    #[allow(unknown_lints, bare_trait_objects)]
+    fn cause(&self) -> Option<&Error> {
+        Some(&self.error)
+    }
+}
+
+impl fmt::Display for PatternSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PatternSet, Precedence};
+    use Pattern;
+
+    #[test]
+    fn test_parse() {
+        let set = PatternSet::parse(
+            "# build output\n\
+             target/**\n\
+             \n\
+             !target/doc/**   \r\n\
+             \\#notes\n\
+             \\!important\n",
+        )
+        .unwrap();
+        assert_eq!(set.len(), 4);
+        assert!(set.matches("target/debug/glob"));
+        assert!(!set.matches("target/doc/glob/index.html"));
+        assert!(set.matches("#notes"));
+        assert!(set.matches("!important"));
+        assert!(!set.matches("# build output"));
+        assert!(!set.matches("src/lib.rs"));
+
+        let err = PatternSet::parse("a\n\n[a-\n").err().unwrap();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.error.pos, 0);
+    }
+
+    #[test]
+    fn test_add() {
+        let mut set = PatternSet::new();
+        set.add("**/*.rs").unwrap().add("!**/target/**").unwrap();
+        assert!(set.matches("src/lib.rs"));
+        assert!(!set.matches("a/target/x.rs"));
+        set.add("\\!x").unwrap().add("#y").unwrap();
+        assert!(set.matches("!x"));
+        assert!(set.matches("#y"));
+        assert!(set.add("![a-").is_err());
+        assert_eq!(set.len(), 4);
+
+        let err = PatternSet::from_rules(vec!["a", "!b**"]).err().unwrap();
+        assert_eq!(err.line, 2);
+        assert!(PatternSet::from_rules(Vec::<String>::new())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_last_match_wins() {
+        let mut set = PatternSet::new();
+        assert!(!set.matches("a.log"));
+        set.exclude(Pattern::new("*.log").unwrap());
+        assert!(!set.matches("a.log"));
+        set.include(Pattern::new("a.*").unwrap());
+        assert!(set.matches("a.log"));
+        set.exclude(Pattern::new("a.log").unwrap());
+        assert!(!set.matches("a.log"));
+        assert!(set.matches("a.txt"));
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let mut set: PatternSet = "!*.log\na.*\n!a.txt".parse().unwrap();
+        assert!(set.matches("a.log"));
+        assert!(set.matches("a.md"));
+        assert!(!set.matches("a.txt"));
+
+        set.precedence(Precedence::FirstMatch);
+        assert!(!set.matches("a.log"));
+        assert!(set.matches("a.md"));
+        assert!(set.matches("a.txt"));
+        assert!(!set.matches("b.txt"));
+    }
+}
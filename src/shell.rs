@@ -0,0 +1,515 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Shell-style expansion of a single word.
+//!
+//! `expand` performs the expansions a POSIX shell applies to a word, in the
+//! order bash applies them: brace expansion, tilde expansion, parameter
+//! expansion, field splitting and finally pathname expansion, i.e. globbing.
+//! Quoting is respected throughout, and removed from the result.
+//!
+//! Only the parts of each expansion that don't involve running commands are
+//! supported: `$NAME` and `${NAME}` are expanded, but `$(...)`, arithmetic
+//! and special parameters like `$1` are left as they are.
+//!
+//! A leading `~` is the current user's home directory, given by `HOME`, or on
+//! Windows by `USERPROFILE` if that isn't set. A leading `~name` is the home
+//! directory of the user `name`, which `expand` looks up with the `users`
+//! feature: through `getpwnam_r(3)` on Unix, so users of directory services
+//! are found too, and in the profile list of the registry on Windows. A user
+//! that can't be found is left as it is, like in bash.
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "users")]
+use users::home_dir as user_home;
+use {glob_with, MatchOptions, Pattern};
+
+/// An error expanding a word, see `expand`.
+#[derive(Debug)]
+#[allow(missing_copy_implementations)]
+pub struct ExpandError {
+    /// The approximate character index of where the error occurred.
+    pub pos: usize,
+
+    /// A message describing the error.
+    pub msg: &'static str,
+}
+
+impl Error for ExpandError {
+    fn description(&self) -> &str {
+        self.msg
+    }
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Expansion error near position {}: {}",
+            self.pos, self.msg
+        )
+    }
+}
+
+const ERROR_UNTERMINATED_QUOTE: &str = "unterminated quote";
+const ERROR_UNTERMINATED_BRACE: &str = "unterminated `${`";
+const ERROR_BAD_SUBSTITUTION: &str = "invalid parameter name in `${...}`";
+
+// A character of a word, or a reference to a parameter, along with whether
+// it was quoted.
+#[derive(Clone, PartialEq, Debug)]
+enum Piece {
+    Char(char, bool),
+    Var(String, bool),
+}
+
+/// Expands `word` the way a shell would, looking up parameters in the
+/// environment, and returns the resulting words.
+///
+/// A word usually expands to a single word, but brace expansion, splitting
+/// of unquoted parameters and globbing can produce several, or none. Like
+/// bash without `nullglob`, a pattern that matches nothing is left as it is,
+/// and like bash without `dotglob`, wildcards don't match a leading `.`.
+///
+/// See the module documentation for details.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::shell;
+/// use std::env;
+///
+/// env::set_var("GREETING", "hello");
+/// assert_eq!(
+///     shell::expand("{$GREETING,'$GREETING'}.txt").unwrap(),
+///     vec!["hello.txt", "$GREETING.txt"]
+/// );
+/// ```
+pub fn expand(word: &str) -> Result<Vec<String>, ExpandError> {
+    expand_with_homes(word, |name| env::var(name).ok(), user_home)
+}
+
+/// Expands `word` like `expand`, looking up parameters, including `HOME` for
+/// tilde expansion, with `vars` instead of in the environment. Since other
+/// users aren't looked up, `~name` is left as it is.
+pub fn expand_with<F>(word: &str, vars: F) -> Result<Vec<String>, ExpandError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    expand_with_homes(word, vars, |_| None)
+}
+
+/// Expands `word` like `expand_with`, looking up the home directory of the
+/// user `name` for `~name` with `homes` as well.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::shell;
+///
+/// let words = shell::expand_with_homes(
+///     "~alice/notes.txt",
+///     |_| None,
+///     |user| match user {
+///         "alice" => Some("/home/alice".to_string()),
+///         _ => None,
+///     },
+/// );
+/// assert_eq!(words.unwrap(), vec!["/home/alice/notes.txt"]);
+/// ```
+pub fn expand_with_homes<F, H>(word: &str, vars: F, homes: H) -> Result<Vec<String>, ExpandError>
+where
+    F: Fn(&str) -> Option<String>,
+    H: Fn(&str) -> Option<String>,
+{
+    let (pieces, quoted) = lex(word)?;
+    let mut words = Vec::new();
+    for pieces in braces(pieces) {
+        let pieces = tilde(pieces, &vars, &homes);
+        let fields = split(&pieces, &vars);
+        if fields.is_empty() && quoted {
+            // e.g. `""` is a single empty word
+            words.push(String::new());
+        }
+        for field in fields {
+            pathnames(&field, &mut words);
+        }
+    }
+    Ok(words)
+}
+
+// Splits `word` into pieces, removing quotes. Also returns whether there
+// were any quotes.
+fn lex(word: &str) -> Result<(Vec<Piece>, bool), ExpandError> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut pieces = Vec::new();
+    let mut quoted = false;
+    let mut in_double = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' if !in_double => {
+                quoted = true;
+                let end = match chars[i + 1..].iter().position(|&c| c == '\'') {
+                    Some(end) => i + 1 + end,
+                    None => {
+                        return Err(ExpandError {
+                            pos: i,
+                            msg: ERROR_UNTERMINATED_QUOTE,
+                        })
+                    }
+                };
+                pieces.extend(chars[i + 1..end].iter().map(|&c| Piece::Char(c, true)));
+                i = end + 1;
+            }
+            '"' => {
+                quoted = true;
+                in_double = !in_double;
+                i += 1;
+            }
+            '\\' if i + 1 < chars.len() => {
+                let c = chars[i + 1];
+                // within double quotes, only some characters can be escaped
+                if in_double && !"$`\"\\\n".contains(c) {
+                    pieces.push(Piece::Char('\\', true));
+                }
+                if c != '\n' {
+                    pieces.push(Piece::Char(c, true));
+                }
+                i += 2;
+            }
+            '$' => {
+                let (piece, len) = variable(&chars[i..], i, in_double)?;
+                pieces.push(piece);
+                i += len;
+            }
+            c => {
+                pieces.push(Piece::Char(c, in_double));
+                i += 1;
+            }
+        }
+    }
+    if in_double {
+        return Err(ExpandError {
+            pos: chars.len(),
+            msg: ERROR_UNTERMINATED_QUOTE,
+        });
+    }
+    Ok((pieces, quoted))
+}
+
+// Lexes the parameter reference at the start of `chars`, which begins with
+// `$`, returning it and its length. A `$` that doesn't start a reference is
+// a literal `$`.
+fn variable(chars: &[char], pos: usize, quoted: bool) -> Result<(Piece, usize), ExpandError> {
+    // `char::is_ascii_alphanumeric` requires Rust 1.24
+    fn is_name_char(c: char) -> bool {
+        (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || (c >= '0' && c <= '9') || c == '_'
+    }
+
+    fn is_name(name: &[char]) -> bool {
+        !name.is_empty()
+            && (name[0] < '0' || name[0] > '9')
+            && name.iter().all(|&c| is_name_char(c))
+    }
+
+    if chars.get(1) == Some(&'{') {
+        let end = match chars.iter().position(|&c| c == '}') {
+            Some(end) => end,
+            None => {
+                return Err(ExpandError {
+                    pos,
+                    msg: ERROR_UNTERMINATED_BRACE,
+                })
+            }
+        };
+        if !is_name(&chars[2..end]) {
+            return Err(ExpandError {
+                pos,
+                msg: ERROR_BAD_SUBSTITUTION,
+            });
+        }
+        let name = chars[2..end].iter().cloned().collect();
+        return Ok((Piece::Var(name, quoted), end + 1));
+    }
+
+    let len = chars[1..].iter().take_while(|&&c| is_name_char(c)).count();
+    if is_name(&chars[1..1 + len]) {
+        let name = chars[1..1 + len].iter().cloned().collect();
+        Ok((Piece::Var(name, quoted), 1 + len))
+    } else {
+        Ok((Piece::Char('$', quoted), 1))
+    }
+}
+
+// Performs brace expansion, e.g. `a{b,c}d` becomes `abd` and `acd`. Braces
+// without a comma between them are left alone.
+fn braces(pieces: Vec<Piece>) -> Vec<Vec<Piece>> {
+    let is = |piece: &Piece, c: char| *piece == Piece::Char(c, false);
+    for open in 0..pieces.len() {
+        if !is(&pieces[open], '{') {
+            continue;
+        }
+        let mut depth = 0;
+        let mut commas = Vec::new();
+        let mut close = None;
+        for (i, piece) in pieces.iter().enumerate().skip(open + 1) {
+            if is(piece, '{') {
+                depth += 1;
+            } else if is(piece, '}') {
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+                depth -= 1;
+            } else if is(piece, ',') && depth == 0 {
+                commas.push(i);
+            }
+        }
+        let close = match close {
+            Some(close) if !commas.is_empty() => close,
+            _ => continue,
+        };
+
+        let mut bounds = vec![open];
+        bounds.extend(commas);
+        bounds.push(close);
+        let mut words = Vec::new();
+        for alternative in bounds.windows(2) {
+            let mut word = pieces[..open].to_vec();
+            word.extend_from_slice(&pieces[alternative[0] + 1..alternative[1]]);
+            word.extend_from_slice(&pieces[close + 1..]);
+            words.extend(braces(word));
+        }
+        return words;
+    }
+    vec![pieces]
+}
+
+// Performs tilde expansion of a leading `~` or `~name`, which has to be
+// unquoted up to the first `/`. The result is quoted, so it's not globbed.
+fn tilde<F, H>(pieces: Vec<Piece>, vars: &F, homes: &H) -> Vec<Piece>
+where
+    F: Fn(&str) -> Option<String>,
+    H: Fn(&str) -> Option<String>,
+{
+    let end = pieces
+        .iter()
+        .position(|piece| *piece == Piece::Char('/', false))
+        .unwrap_or(pieces.len());
+    if pieces.first() != Some(&Piece::Char('~', false)) {
+        return pieces;
+    }
+    let name = pieces[1..end]
+        .iter()
+        .map(|piece| match *piece {
+            Piece::Char(c, false) => Some(c),
+            _ => None,
+        })
+        .collect::<Option<String>>();
+    let home = match name {
+        Some(ref name) if name.is_empty() => vars("HOME").or_else(|| {
+            if cfg!(windows) {
+                vars("USERPROFILE")
+            } else {
+                None
+            }
+        }),
+        Some(ref name) => homes(name),
+        None => None,
+    };
+    match home {
+        Some(home) => home
+            .chars()
+            .map(|c| Piece::Char(c, true))
+            .chain(pieces.into_iter().skip(end))
+            .collect(),
+        None => pieces,
+    }
+}
+
+#[cfg(not(feature = "users"))]
+fn user_home(_: &str) -> Option<String> {
+    None
+}
+
+// Expands parameters and splits the result into fields on unquoted
+// whitespace coming from unquoted parameters.
+fn split<F>(pieces: &[Piece], vars: &F) -> Vec<Vec<(char, bool)>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut fields = Vec::new();
+    let mut field = Vec::new();
+    // whether the current field exists even if it's empty, because of a
+    // quoted parameter
+    let mut started = false;
+    for piece in pieces {
+        match *piece {
+            Piece::Char(c, quoted) => {
+                field.push((c, quoted));
+                started = true;
+            }
+            Piece::Var(ref name, true) => {
+                field.extend(vars(name).unwrap_or_default().chars().map(|c| (c, true)));
+                started = true;
+            }
+            Piece::Var(ref name, false) => {
+                for c in vars(name).unwrap_or_default().chars() {
+                    if c == ' ' || c == '\t' || c == '\n' {
+                        if started {
+                            fields.push(field);
+                            field = Vec::new();
+                            started = false;
+                        }
+                    } else {
+                        field.push((c, false));
+                        started = true;
+                    }
+                }
+            }
+        }
+    }
+    if started {
+        fields.push(field);
+    }
+    fields
+}
+
+// Globs for `field` if it has unquoted wildcards, adding the matches to
+// `words`, or the field itself if there are none.
+fn pathnames(field: &[(char, bool)], words: &mut Vec<String>) {
+    let literal: String = field.iter().map(|&(c, _)| c).collect();
+    if !field
+        .iter()
+        .any(|&(c, quoted)| !quoted && "*?[".contains(c))
+    {
+        words.push(literal);
+        return;
+    }
+
+    let mut pattern = String::new();
+    for &(c, quoted) in field {
+        if quoted {
+            pattern.push_str(&Pattern::escape(&c.to_string()));
+        } else {
+            pattern.push(c);
+        }
+    }
+    let options = MatchOptions {
+        require_literal_leading_dot: true,
+        ..MatchOptions::new()
+    };
+    let matches: Vec<String> = match glob_with(&pattern, options) {
+        Ok(paths) => paths
+            .filter_map(Result::ok)
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    if matches.is_empty() {
+        words.push(literal);
+    } else {
+        words.extend(matches);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{expand_with, expand_with_homes};
+
+    fn expand(word: &str) -> Vec<String> {
+        expand_with_homes(
+            word,
+            |name| match name {
+                "HOME" => Some("/home/me".to_string()),
+                "X" => Some("x".to_string()),
+                "SPACED" => Some(" a  b ".to_string()),
+                "STAR" => Some("Cargo.t*ml".to_string()),
+                _ => None,
+            },
+            |name| match name {
+                "alice" => Some("/home/alice".to_string()),
+                _ => None,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(expand("abc"), vec!["abc"]);
+        assert_eq!(expand("'a b'\"c\"\\d"), vec!["a bcd"]);
+        assert_eq!(expand("\"a\\$X\\z\""), vec!["a$X\\z"]);
+        assert_eq!(expand("''"), vec![""]);
+        assert_eq!(expand("\"$UNSET\""), vec![""]);
+        assert!(expand("$UNSET").is_empty());
+        assert!(expand_with("'abc", |_| None).is_err());
+        assert!(expand_with("\"abc", |_| None).is_err());
+        assert!(expand_with("${X", |_| None).is_err());
+        assert!(expand_with("${1a}", |_| None).is_err());
+    }
+
+    #[test]
+    fn test_parameters() {
+        assert_eq!(expand("$X"), vec!["x"]);
+        assert_eq!(expand("${X}y"), vec!["xy"]);
+        assert_eq!(expand("$Xy"), vec![""; 0]);
+        assert_eq!(expand("$1 $"), vec!["$1 $"]);
+        assert_eq!(expand("$SPACED"), vec!["a", "b"]);
+        assert_eq!(expand("<$SPACED>"), vec!["<", "a", "b", ">"]);
+        assert_eq!(expand("\"$SPACED\""), vec![" a  b "]);
+    }
+
+    #[test]
+    fn test_braces() {
+        assert_eq!(expand("a{b,c}d"), vec!["abd", "acd"]);
+        assert_eq!(expand("{a,b{c,d}}"), vec!["a", "bc", "bd"]);
+        assert_eq!(expand("{a}"), vec!["{a}"]);
+        assert_eq!(expand("{a,}"), vec!["a"]);
+        assert_eq!(expand("'{a,b}'"), vec!["{a,b}"]);
+        assert_eq!(expand("{$X,y}"), vec!["x", "y"]);
+        assert_eq!(expand("${X}{1,2}"), vec!["x1", "x2"]);
+        assert_eq!(expand("{a,b"), vec!["{a,b"]);
+    }
+
+    #[test]
+    fn test_tilde() {
+        assert_eq!(expand("~"), vec!["/home/me"]);
+        assert_eq!(expand("~/src"), vec!["/home/me/src"]);
+        assert_eq!(expand("{~,~/x}"), vec!["/home/me", "/home/me/x"]);
+        assert_eq!(expand("'~'"), vec!["~"]);
+        assert_eq!(expand("a~"), vec!["a~"]);
+        assert_eq!(expand("~nobody"), vec!["~nobody"]);
+        assert_eq!(expand("~alice"), vec!["/home/alice"]);
+        assert_eq!(expand("~alice/src"), vec!["/home/alice/src"]);
+        assert_eq!(expand("~nobody/src"), vec!["~nobody/src"]);
+        assert_eq!(expand("~'alice'"), vec!["~alice"]);
+        assert_eq!(expand("~$X"), vec!["~x"]);
+
+        // without a lookup, other users are left alone
+        assert_eq!(expand_with("~alice", |_| None).unwrap(), vec!["~alice"]);
+    }
+
+    #[test]
+    fn test_pathnames() {
+        assert_eq!(expand("Cargo.t*ml"), vec!["Cargo.toml"]);
+        assert_eq!(expand("$STAR"), vec!["Cargo.toml"]);
+        assert_eq!(expand("\"$STAR\""), vec!["Cargo.t*ml"]);
+        assert_eq!(expand("'Cargo.t'*ml"), vec!["Cargo.toml"]);
+        assert_eq!(expand("Cargo.t'*'ml"), vec!["Cargo.t*ml"]);
+        assert_eq!(expand("no-such-*"), vec!["no-such-*"]);
+        assert_eq!(expand("src/[l]ib.rs"), vec!["src/lib.rs"]);
+        assert_eq!(expand("["), vec!["["]);
+    }
+}
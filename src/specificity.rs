@@ -0,0 +1,166 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cmp::{Ordering, Reverse};
+
+use CharSpecifier::{CharRange, SingleChar};
+use PatternToken::{AnyChar, AnyExcept, AnyRecursiveSequence, AnySequence, AnyWithin, Char};
+use {CharSpecifier, Pattern, PatternToken};
+
+/// How specific a pattern is, as returned by `Pattern::specificity`, for
+/// deciding which of several patterns that match a path should apply to it.
+///
+/// A `Specificity` compares greater the more specific it is. Patterns are
+/// compared by each of the following in turn, until one of them differs:
+///
+/// 1. the number of `**`, fewer being more specific;
+/// 2. the number of literal characters, more being more specific;
+/// 3. the number of `*`, fewer being more specific;
+/// 4. the number of `?` and `[...]`, more being more specific, since each
+///    has to match a character where a `*` could match none.
+///
+/// So `src/main.rs` is more specific than `src/*.rs`, which is more specific
+/// than `**/*.rs`.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::Pattern;
+///
+/// let patterns: Vec<Pattern> = ["**/*.rs", "src/*.rs", "src/main.rs", "*"]
+///     .iter()
+///     .map(|p| Pattern::new(p).unwrap())
+///     .collect();
+/// let best = patterns
+///     .iter()
+///     .filter(|p| p.matches("src/main.rs"))
+///     .max_by_key(|p| p.specificity())
+///     .unwrap();
+/// assert_eq!(best.as_str(), "src/main.rs");
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Specificity {
+    /// The number of `**`.
+    pub recursive_wildcards: usize,
+
+    /// The number of characters that are matched literally, including
+    /// escaped metacharacters like `[*]`.
+    pub literal_chars: usize,
+
+    /// The number of `*`.
+    pub wildcards: usize,
+
+    /// The number of `?` and of character classes like `[a-z]`.
+    pub single_char_wildcards: usize,
+}
+
+impl Specificity {
+    // The terms in the order they're compared, each greater when more
+    // specific.
+    fn key(&self) -> (Reverse<usize>, usize, Reverse<usize>, usize) {
+        (
+            Reverse(self.recursive_wildcards),
+            self.literal_chars,
+            Reverse(self.wildcards),
+            self.single_char_wildcards,
+        )
+    }
+}
+
+impl PartialOrd for Specificity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Specificity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+// The specificity of `pattern`, see `Pattern::specificity`.
+pub(crate) fn specificity(pattern: &Pattern) -> Specificity {
+    pattern
+        .branches()
+        .into_iter()
+        .map(|branch| of_tokens(&branch.tokens))
+        .min()
+        .unwrap_or_default()
+}
+
+fn of_tokens(tokens: &[PatternToken]) -> Specificity {
+    let mut specificity = Specificity::default();
+    for token in tokens {
+        match *token {
+            Char(_) => specificity.literal_chars += 1,
+            AnyWithin(ref specifiers) if is_escape(specifiers) => specificity.literal_chars += 1,
+            AnyChar | AnyWithin(..) | AnyExcept(..) => specificity.single_char_wildcards += 1,
+            AnySequence => specificity.wildcards += 1,
+            AnyRecursiveSequence => specificity.recursive_wildcards += 1,
+        }
+    }
+    specificity
+}
+
+// Return if a class only matches one character, like `[*]`.
+fn is_escape(specifiers: &[CharSpecifier]) -> bool {
+    specifiers.len() == 1
+        && match specifiers[0] {
+            SingleChar(_) => true,
+            CharRange(start, end) => start == end,
+        }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Specificity;
+    use {ParseOptions, Pattern};
+
+    fn specificity(p: &str) -> Specificity {
+        let braces = ParseOptions::new().braces(true);
+        Pattern::new_with(p, braces).unwrap().specificity()
+    }
+
+    #[test]
+    fn test_specificity() {
+        assert_eq!(
+            specificity("src/**/[a-c]?*.rs"),
+            Specificity {
+                recursive_wildcards: 1,
+                literal_chars: 7,
+                wildcards: 1,
+                single_char_wildcards: 2,
+            }
+        );
+        assert_eq!(specificity("a[*]").literal_chars, 2);
+
+        let ordered = [
+            "**",
+            "**/*.rs",
+            "src/**/*.rs",
+            "*",
+            "*.rs",
+            "src/*.rs",
+            "src/?*.rs",
+            "src/??*.rs",
+            "src/m*.rs",
+            "src/m[a-z]in.rs",
+            "src/main.rs",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(specificity(pair[0]) < specificity(pair[1]), "{:?}", pair);
+        }
+
+        // as specific as the least specific alternative
+        assert_eq!(specificity("{main.rs,*}"), specificity("*"));
+        assert_eq!(specificity(""), Specificity::default());
+    }
+}
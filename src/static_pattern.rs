@@ -0,0 +1,216 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Once, RwLock};
+
+use {MatchOptions, Pattern, PatternError};
+
+/// A pattern that can be stored in a `static` or `const`, without wrapping
+/// it in something like `lazy_static!`.
+///
+/// The pattern is compiled into a `Pattern` the first time it's used, and
+/// then reused, so it supports the same syntax and matches the same way.
+///
+/// `StaticPattern`s are constructed with the `static_pattern!` macro. The
+/// pattern isn't checked when the program is built, since that would mean
+/// parsing it in a constant, which the Rust versions this crate supports
+/// can't do. A pattern that isn't valid matches nothing; `pattern` returns
+/// the error instead, which is worth asserting in a test.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate glob;
+///
+/// use glob::StaticPattern;
+///
+/// static RS_FILES: StaticPattern = static_pattern!("**/*.rs");
+///
+/// # fn main() {
+/// assert!(RS_FILES.pattern().is_ok());
+/// assert!(RS_FILES.matches("src/lib.rs"));
+/// assert!(!RS_FILES.matches("lib.c"));
+/// # }
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct StaticPattern {
+    #[doc(hidden)]
+    pub __pattern: &'static str,
+}
+
+/// Constructs a `StaticPattern` from a string literal, in a context where
+/// only constant expressions are allowed.
+#[macro_export]
+macro_rules! static_pattern {
+    ($pattern:expr) => {
+        $crate::StaticPattern {
+            __pattern: $pattern,
+        }
+    };
+}
+
+// The patterns compiled so far, or `None` for those that aren't valid. They
+// are never freed, so that `StaticPattern::pattern` can hand out references
+// to them; there's one for each distinct string, like there would be for a
+// `Pattern` in a `lazy_static!`.
+type Compiled = RwLock<HashMap<&'static str, Option<&'static Pattern>>>;
+
+// `Once::new` can only be called in a constant from Rust 1.32 on
+#[allow(deprecated)]
+static INIT: Once = ::std::sync::ONCE_INIT;
+static mut COMPILED: *const Compiled = 0 as *const Compiled;
+
+fn compiled() -> &'static Compiled {
+    INIT.call_once(|| unsafe {
+        COMPILED = Box::into_raw(Box::new(RwLock::new(HashMap::new())));
+    });
+    // only written once, by `call_once`, which the read waits for
+    unsafe { &*COMPILED }
+}
+
+impl StaticPattern {
+    /// Return the original glob pattern.
+    pub fn as_str(&self) -> &'static str {
+        self.__pattern
+    }
+
+    /// Return the compiled pattern, or the error compiling it. The pattern
+    /// is only compiled the first time this is called.
+    pub fn pattern(&self) -> Result<&'static Pattern, PatternError> {
+        match self.compiled() {
+            Some(pattern) => Ok(pattern),
+            None => Err(Pattern::new(self.__pattern).err().unwrap()),
+        }
+    }
+
+    fn compiled(&self) -> Option<&'static Pattern> {
+        let patterns = compiled();
+        if let Some(pattern) = patterns.read().unwrap().get(self.__pattern) {
+            return *pattern;
+        }
+        let mut patterns = patterns.write().unwrap();
+        // another thread may have got there first
+        *patterns.entry(self.__pattern).or_insert_with(|| {
+            Pattern::new(self.__pattern)
+                .ok()
+                .map(|pattern| unsafe { &*Box::into_raw(Box::new(pattern)) })
+        })
+    }
+
+    /// Return if the given `str` matches this `StaticPattern` using the
+    /// default match options (i.e. `MatchOptions::new()`). An invalid
+    /// pattern matches nothing.
+    pub fn matches(&self, str: &str) -> bool {
+        self.matches_with(str, MatchOptions::new())
+    }
+
+    /// Return if the given `Path` matches this `StaticPattern` using the
+    /// default match options, see `Pattern::matches_path`.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        self.matches_path_with(path, MatchOptions::new())
+    }
+
+    /// Return if the given `str` matches this `StaticPattern` using the
+    /// specified match options.
+    pub fn matches_with(&self, str: &str, options: MatchOptions) -> bool {
+        self.compiled()
+            .map_or(false, |pattern| pattern.matches_with(str, options))
+    }
+
+    /// Return if the given `Path` matches this `StaticPattern` using the
+    /// specified match options, see `Pattern::matches_path_with`.
+    pub fn matches_path_with(&self, path: &Path, options: MatchOptions) -> bool {
+        self.compiled()
+            .map_or(false, |pattern| pattern.matches_path_with(path, options))
+    }
+
+    /// Compiles this pattern into an equivalent `Pattern`.
+    pub fn to_pattern(&self) -> Result<Pattern, PatternError> {
+        Ok(self.pattern()?.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StaticPattern;
+    use std::ptr;
+    use MatchOptions;
+
+    static PATTERNS: &[StaticPattern] = &[
+        static_pattern!(""),
+        static_pattern!("a"),
+        static_pattern!("a*"),
+        static_pattern!("*a"),
+        static_pattern!("a?c"),
+        static_pattern!("*.rs"),
+        static_pattern!("*/*.rs"),
+        static_pattern!("[a-c]*"),
+        static_pattern!("[!a]?"),
+        static_pattern!("[]]"),
+        static_pattern!("[!]]"),
+        static_pattern!("[*?]"),
+        static_pattern!("[a-]"),
+        static_pattern!(".*"),
+        static_pattern!("*a*b*"),
+        static_pattern!("A*"),
+        static_pattern!("**/*.rs"),
+        static_pattern!("a/**"),
+    ];
+
+    #[test]
+    fn test_static_pattern() {
+        let strings = [
+            "", "a", "ab", "abc", "a.rs", "x/a.rs", ".rs", "b", ".a", "aXbYb", "]", "*", "-", "Ab",
+            "a/b",
+        ];
+        for &case_sensitive in &[false, true] {
+            for &literal_separator in &[false, true] {
+                for &literal_leading_dot in &[false, true] {
+                    let options = MatchOptions {
+                        case_sensitive,
+                        require_literal_separator: literal_separator,
+                        require_literal_leading_dot: literal_leading_dot,
+                        ..MatchOptions::new()
+                    };
+                    for p in PATTERNS {
+                        let pattern = p.to_pattern().unwrap();
+                        for s in &strings {
+                            assert_eq!(
+                                p.matches_with(s, options),
+                                pattern.matches_with(s, options),
+                                "{:?} {:?} {:?}",
+                                p,
+                                s,
+                                options
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        static INVALID: StaticPattern = static_pattern!("a[b");
+        assert_eq!(INVALID.pattern().unwrap_err().pos, 1);
+        assert!(!INVALID.matches("a[b"));
+        assert!(!INVALID.matches("ab"));
+
+        let pattern = static_pattern!("[a-c]?");
+        assert!(pattern.matches("bx"));
+        assert!(pattern.pattern().is_ok());
+        let copy = pattern;
+        assert!(ptr::eq(copy.pattern().unwrap(), pattern.pattern().unwrap()));
+    }
+}
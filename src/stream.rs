@@ -0,0 +1,137 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Globbing from asynchronous code on a `tokio` runtime.
+//!
+//! This module is only available with the `stream` feature. It offers
+//! `glob_stream`, which returns a `futures_core::Stream` of the results
+//! rather than an iterator, and `Paths::into_stream` for searches set up with
+//! a `GlobBuilder`.
+//!
+//! # Examples
+//!
+//! With `futures::StreamExt`, asynchronous code can simply loop over
+//! `stream.next().await`. Without it, the stream can be polled by hand:
+//!
+//! ```rust,no_run
+//! # extern crate futures_core;
+//! # extern crate glob;
+//! # extern crate tokio;
+//! use futures_core::Stream;
+//! use glob::stream::glob_stream;
+//! use std::future::poll_fn;
+//! use std::pin::Pin;
+//! use std::task::Poll;
+//!
+//! # fn main() {
+//! let runtime = tokio::runtime::Builder::new_current_thread()
+//!     .build()
+//!     .unwrap();
+//! let _entered = runtime.enter();
+//! let mut stream = glob_stream("**/*.rs").unwrap();
+//! runtime.block_on(poll_fn(|cx| loop {
+//!     match Pin::new(&mut stream).poll_next(cx) {
+//!         Poll::Ready(Some(result)) => println!("{}", result.unwrap().display()),
+//!         Poll::Ready(None) => return Poll::Ready(()),
+//!         Poll::Pending => return Poll::Pending,
+//!     }
+//! }));
+//! # }
+//! ```
+
+// unlike the rest of the crate, this needs a compiler with `std::future`
+#![allow(clippy::incompatible_msrv)]
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+use tokio::task;
+
+use {glob, GlobResult, Paths, PatternError};
+
+// How many results the search may get ahead of the consumer.
+const BUFFER: usize = 64;
+
+/// A stream that yields the results of a `Paths` iterator as they're found.
+///
+/// The search runs on `tokio`'s blocking thread pool, so reading directories
+/// never blocks the executor, and each result is passed on as soon as it's
+/// found rather than once the search is over. The search only gets a little
+/// ahead of the consumer, and stops once the stream is dropped.
+///
+/// Created by `glob_stream` or `Paths::into_stream`, which have to be called
+/// from within a `tokio` runtime.
+#[derive(Debug)]
+pub struct PathStream {
+    results: mpsc::Receiver<GlobResult>,
+}
+
+impl PathStream {
+    pub(crate) fn new(paths: Paths) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER);
+        task::spawn_blocking(move || {
+            for result in paths {
+                // the stream is gone, so nobody wants the rest
+                if tx.blocking_send(result).is_err() {
+                    break;
+                }
+            }
+        });
+        PathStream { results: rx }
+    }
+}
+
+impl Stream for PathStream {
+    type Item = GlobResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<GlobResult>> {
+        self.results.poll_recv(cx)
+    }
+}
+
+/// Like `glob`, but returns a stream whose search runs on `tokio`'s blocking
+/// thread pool.
+///
+/// # Panics
+///
+/// Panics if called outside of a `tokio` runtime.
+pub fn glob_stream(pattern: &str) -> Result<PathStream, PatternError> {
+    glob(pattern).map(PathStream::new)
+}
+
+#[cfg(test)]
+mod test {
+    use super::glob_stream;
+    use futures_core::Stream;
+    use glob;
+    use std::future::poll_fn;
+    use std::pin::Pin;
+    use std::task::Poll;
+    use tokio::runtime::Builder;
+
+    #[test]
+    fn test_glob_stream() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let _entered = runtime.enter();
+        let mut stream = glob_stream("src/*.rs").unwrap();
+        let mut streamed = Vec::new();
+        runtime.block_on(poll_fn(|cx| loop {
+            match Pin::new(&mut stream).poll_next(cx) {
+                Poll::Ready(Some(result)) => streamed.push(result.unwrap()),
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Pending => return Poll::Pending,
+            }
+        }));
+        let expected: Vec<_> = glob("src/*.rs").unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(streamed, expected);
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::path::PathBuf;
+
+use {GlobError, GlobResult, Paths};
+
+/// An entry visited by `TaggedPaths`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Visit {
+    /// The path matched the pattern, and would have been yielded by `Paths`.
+    Matched(PathBuf),
+
+    /// The path was considered but not yielded, for the given reason.
+    Skipped(PathBuf, SkipReason),
+}
+
+impl Visit {
+    /// The path that was visited.
+    pub fn path(&self) -> &PathBuf {
+        match *self {
+            Visit::Matched(ref path) | Visit::Skipped(ref path, _) => path,
+        }
+    }
+}
+
+/// The reason an entry visited by `TaggedPaths` was not yielded as a match.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SkipReason {
+    /// The entry's name starts with a `.` and `require_literal_leading_dot`
+    /// is set.
+    Hidden,
+
+    /// The entry was excluded by the configuration of the `GlobBuilder`,
+    /// e.g. a symbolic link that leads outside of a confined scope.
+    Excluded,
+
+    /// The entry's name didn't match the pattern component at the given
+    /// index, counting from the first component after any root.
+    Mismatch(usize),
+
+    /// The entry matched, but the pattern ends in a separator and the entry
+    /// is not a directory.
+    NotADirectory,
+
+    /// The entry matched, but it's a symbolic link whose target doesn't
+    /// exist and `BrokenSymlinks::Exclude` is in effect.
+    BrokenSymlink,
+
+    /// The entry is ignored by a `.gitignore` or `.ignore` file, see
+    /// `GlobBuilder::ignore_files`.
+    Ignored,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// An iterator that yields every entry visited by a `Paths` iterator, tagged
+/// with whether it matched or why it was skipped.
+///
+/// This is intended for auditing: it explains why a file that exists was not
+/// part of the results. Entries are reported in the order they are visited,
+/// which differs from the order matches are yielded in. Directories that are
+/// never read, such as those beneath a pattern component that failed to
+/// match, don't produce any entries.
+///
+/// Created by `Paths::tagged`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::{glob, Visit};
+///
+/// for entry in glob("backup/**/*.db").unwrap().tagged() {
+///     match entry {
+///         Ok(Visit::Matched(path)) => println!("+ {}", path.display()),
+///         Ok(Visit::Skipped(path, reason)) => println!("- {} ({:?})", path.display(), reason),
+///         Err(e) => println!("{:?}", e),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TaggedPaths {
+    paths: Paths,
+    pending: Option<GlobResult>,
+}
+
+impl TaggedPaths {
+    pub(crate) fn new(paths: Paths) -> Self {
+        Self {
+            paths,
+            pending: None,
+        }
+    }
+}
+
+impl Iterator for TaggedPaths {
+    type Item = Result<Visit, GlobError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_none() {
+            self.pending = self.paths.next();
+        }
+
+        // entries skipped while looking for the pending result were visited
+        // before it
+        let skipped = self
+            .paths
+            .state
+            .skipped
+            .as_mut()
+            .and_then(|skipped| skipped.pop_front());
+        if let Some((path, reason)) = skipped {
+            return Some(Ok(Visit::Skipped(path, reason)));
+        }
+
+        self.pending.take().map(|result| result.map(Visit::Matched))
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for testing code that globs, without touching the disk.
+//!
+//! This module is only available with the `testing` feature. It provides
+//! `MemoryFs`, an in-memory `FileSystem` that can be passed to
+//! `GlobBuilder::filesystem`, and the `fixture!` macro to populate one.
+//!
+//! # Examples
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate glob;
+//!
+//! use glob::GlobBuilder;
+//! use std::path::PathBuf;
+//!
+//! # fn main() {
+//! let fs = fixture!{"a/b/c.txt", "a/.hidden", "a/empty/"};
+//! let matches: Vec<_> = GlobBuilder::new()
+//!     .filesystem(fs)
+//!     .glob("a/*")
+//!     .unwrap()
+//!     .map(|r| r.unwrap())
+//!     .collect();
+//! assert_eq!(
+//!     matches,
+//!     vec![
+//!         PathBuf::from("a/.hidden"),
+//!         PathBuf::from("a/b"),
+//!         PathBuf::from("a/empty")
+//!     ]
+//! );
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use filesystem::{FileKind, FileSystem};
+
+// Symbolic links are followed at most this many times while resolving a
+// single path, like Linux's MAXSYMLINKS.
+const MAX_SYMLINKS: usize = 40;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Node {
+    File,
+    Dir,
+    Symlink(PathBuf),
+}
+
+/// An in-memory filesystem made up of files, directories and symbolic
+/// links.
+///
+/// There is a single root, which absolute paths and relative paths alike
+/// are resolved against, so `/a`, `a` and `./a` all name the same entry.
+/// Every directory can be read, and the target of a symbolic link is
+/// resolved relative to the directory containing the link, unless it's
+/// absolute.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MemoryFs {
+    // keyed by the names of the components of each path
+    nodes: BTreeMap<Vec<OsString>, Node>,
+}
+
+impl MemoryFs {
+    /// Constructs a `MemoryFs` containing only the root directory.
+    pub fn new() -> Self {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(Vec::new(), Node::Dir);
+        Self { nodes }
+    }
+
+    /// Adds a file, or a directory if `path` ends in a separator, along with
+    /// any missing parent directories.
+    pub fn add(&mut self, path: &str) -> &mut Self {
+        if path.ends_with('/') || path.ends_with(::std::path::MAIN_SEPARATOR) {
+            self.dir(path)
+        } else {
+            self.file(path)
+        }
+    }
+
+    /// Adds a file, along with any missing parent directories.
+    pub fn file<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.insert(path.as_ref(), Node::File)
+    }
+
+    /// Adds a directory, along with any missing parent directories.
+    pub fn dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.insert(path.as_ref(), Node::Dir)
+    }
+
+    /// Adds a symbolic link pointing at `target`, along with any missing
+    /// parent directories. The target doesn't need to exist.
+    pub fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, path: P, target: Q) -> &mut Self {
+        self.insert(path.as_ref(), Node::Symlink(target.as_ref().to_path_buf()))
+    }
+
+    fn insert(&mut self, path: &Path, node: Node) -> &mut Self {
+        let key = normalize(Vec::new(), path);
+        for i in 0..key.len() {
+            self.nodes.entry(key[..i].to_vec()).or_insert(Node::Dir);
+        }
+        self.nodes.insert(key, node);
+        self
+    }
+
+    // Resolves `path` to the key of the entry it names, following symbolic
+    // links in every component except possibly the last one.
+    fn resolve(&self, path: &Path, follow: bool) -> io::Result<Vec<OsString>> {
+        let mut links = 0;
+        let mut resolved = Vec::new();
+        let mut pending: Vec<OsString> = normalize(Vec::new(), path).into_iter().rev().collect();
+        while let Some(name) = pending.pop() {
+            if name.as_os_str() == OsStr::new("..") {
+                resolved.pop();
+                continue;
+            }
+            resolved.push(name);
+            let target = match self.nodes.get(&resolved) {
+                Some(node) => match *node {
+                    Node::Symlink(ref target) if follow || !pending.is_empty() => target,
+                    _ => continue,
+                },
+                None => return Err(not_found()),
+            };
+
+            links += 1;
+            if links > MAX_SYMLINKS {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "too many levels of symbolic links",
+                ));
+            }
+            resolved.pop();
+            if target.has_root() {
+                resolved.clear();
+            }
+            // the target is resolved first, then the rest of the path
+            pending.extend(components(target).into_iter().rev());
+        }
+        Ok(resolved)
+    }
+
+    fn kind(&self, key: &[OsString]) -> FileKind {
+        match self.nodes.get(key) {
+            Some(&Node::Dir) => FileKind::Dir,
+            Some(&Node::Symlink(_)) => FileKind::Symlink,
+            _ => FileKind::File,
+        }
+    }
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn metadata(&self, path: &Path) -> io::Result<FileKind> {
+        self.resolve(path, true).map(|key| self.kind(&key))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FileKind> {
+        self.resolve(path, false).map(|key| self.kind(&key))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(OsString, FileKind)>> {
+        let key = self.resolve(path, true)?;
+        if self.kind(&key) != FileKind::Dir {
+            return Err(io::Error::new(io::ErrorKind::Other, "not a directory"));
+        }
+        Ok(self
+            .nodes
+            .range(key.clone()..)
+            .skip(1)
+            .take_while(|&(k, _)| k.starts_with(&key))
+            .filter(|&(k, _)| k.len() == key.len() + 1)
+            .map(|(k, _)| (k[key.len()].clone(), self.kind(k)))
+            .collect())
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let key = self.resolve(path, true)?;
+        let mut canonical = PathBuf::from("/");
+        for name in key {
+            canonical.push(name);
+        }
+        Ok(canonical)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        let key = self.resolve(path, false)?;
+        match self.nodes.get(&key) {
+            Some(node) => match *node {
+                Node::Symlink(ref target) => Ok(target.clone()),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "not a symbolic link",
+                )),
+            },
+            None => Err(not_found()),
+        }
+    }
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "no such file or directory")
+}
+
+// The names of the components of `path`, ignoring any root and `.`.
+fn components(path: &Path) -> Vec<OsString> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            _ => None,
+        })
+        .collect()
+}
+
+// Appends the components of `path` to `key`, resolving `..` lexically.
+fn normalize(mut key: Vec<OsString>, path: &Path) -> Vec<OsString> {
+    for name in components(path) {
+        if name.as_os_str() == OsStr::new("..") {
+            key.pop();
+        } else {
+            key.push(name);
+        }
+    }
+    key
+}
+
+/// Constructs a `testing::MemoryFs` from a list of paths. Paths that end in a
+/// separator are added as directories, the others as files, see
+/// `MemoryFs::add`.
+///
+/// This macro is only available with the `testing` feature.
+#[macro_export]
+macro_rules! fixture {
+    ($($path:expr),* $(,)*) => {{
+        let mut fs = $crate::testing::MemoryFs::new();
+        $(fs.add($path);)*
+        fs
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::MemoryFs;
+    use filesystem::{FileKind, FileSystem};
+    use std::path::{Path, PathBuf};
+    use GlobBuilder;
+
+    fn glob(fs: &MemoryFs, builder: &mut GlobBuilder, pattern: &str) -> Vec<PathBuf> {
+        builder
+            .filesystem(fs.clone())
+            .glob(pattern)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_memory_fs() {
+        let mut fs = fixture! {"a/b/c.txt", "a/d/"};
+        fs.symlink("a/up", "..").symlink("a/loop", "loop");
+
+        assert_eq!(fs.metadata(Path::new("a")).unwrap(), FileKind::Dir);
+        assert_eq!(fs.metadata(Path::new("/a/d")).unwrap(), FileKind::Dir);
+        assert_eq!(
+            fs.metadata(Path::new("./a/b/c.txt")).unwrap(),
+            FileKind::File
+        );
+        assert_eq!(fs.metadata(Path::new("a/up/a/d")).unwrap(), FileKind::Dir);
+        assert_eq!(
+            fs.symlink_metadata(Path::new("a/up")).unwrap(),
+            FileKind::Symlink
+        );
+        assert_eq!(
+            fs.read_link(Path::new("a/up")).unwrap(),
+            PathBuf::from("..")
+        );
+        assert!(fs.read_link(Path::new("a/d")).is_err());
+        assert!(fs.metadata(Path::new("a/loop")).is_err());
+        assert!(fs.metadata(Path::new("a/x")).is_err());
+        assert!(fs.read_dir(Path::new("a/b/c.txt")).is_err());
+        assert_eq!(
+            fs.canonicalize(Path::new("a/up/a/b")).unwrap(),
+            PathBuf::from("/a/b")
+        );
+
+        let mut names = fs.read_dir(Path::new("a")).unwrap();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        let names: Vec<_> = names.into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["b", "d", "loop", "up"]);
+    }
+
+    #[test]
+    fn test_glob_memory_fs() {
+        let mut fs = fixture! {"a/b/c.txt", "a/.hidden", "a/d/", "x.txt"};
+        fs.symlink("a/link", "b");
+
+        assert_eq!(
+            glob(&fs, &mut GlobBuilder::new(), "a/*/"),
+            vec![
+                PathBuf::from("a/b"),
+                PathBuf::from("a/d"),
+                PathBuf::from("a/link")
+            ]
+        );
+        assert_eq!(
+            glob(&fs, &mut GlobBuilder::new(), "**/*.txt"),
+            vec![
+                PathBuf::from("a/b/c.txt"),
+                PathBuf::from("a/link/c.txt"),
+                PathBuf::from("x.txt")
+            ]
+        );
+        assert_eq!(
+            glob(&fs, &mut GlobBuilder::new(), "/a/b/c.txt"),
+            vec![PathBuf::from("/a/b/c.txt")]
+        );
+        assert_eq!(
+            glob(
+                &fs,
+                GlobBuilder::new().recursive_follow_links(false),
+                "a/**/c.txt"
+            ),
+            vec![PathBuf::from("a/b/c.txt")]
+        );
+        assert_eq!(
+            glob(&fs, &mut GlobBuilder::hardened(), "a/*"),
+            vec![
+                PathBuf::from("a/b"),
+                PathBuf::from("a/d"),
+                PathBuf::from("a/link")
+            ]
+        );
+    }
+}
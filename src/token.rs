@@ -0,0 +1,162 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use {CharSpecifier, PatternToken};
+
+/// A token of a compiled pattern, see `Pattern::tokens`.
+///
+/// # Examples
+///
+/// ```rust
+/// use glob::{ClassMember, Pattern, Token};
+///
+/// let pattern = Pattern::new("**/[a-c]?.rs").unwrap();
+/// assert_eq!(
+///     pattern.tokens(),
+///     vec![
+///         Token::AnyRecursiveSequence,
+///         Token::AnyWithin(vec![ClassMember::Range('a', 'c')]),
+///         Token::AnyChar,
+///         Token::Char('.'),
+///         Token::Char('r'),
+///         Token::Char('s'),
+///     ]
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Token {
+    /// A literal character, possibly written as a character class like
+    /// `[*]`.
+    Char(char),
+
+    /// `?`, any single character.
+    AnyChar,
+
+    /// `*`, any sequence of characters.
+    AnySequence,
+
+    /// `**`, any sequence of path components. The separator that follows it
+    /// in the pattern, if any, is part of the token.
+    AnyRecursiveSequence,
+
+    /// A character class like `[a-z]`, any of the given characters.
+    AnyWithin(Vec<ClassMember>),
+
+    /// A negated character class like `[!a-z]`, any character except the
+    /// given ones.
+    AnyExcept(Vec<ClassMember>),
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// A member of a character class, see `Token::AnyWithin`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ClassMember {
+    /// A single character.
+    Char(char),
+
+    /// An inclusive range of characters, like `a-z`.
+    Range(char, char),
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+pub(crate) fn from_pattern_token(token: &PatternToken) -> Token {
+    match *token {
+        PatternToken::Char(c) => Token::Char(c),
+        PatternToken::AnyChar => Token::AnyChar,
+        PatternToken::AnySequence => Token::AnySequence,
+        PatternToken::AnyRecursiveSequence => Token::AnyRecursiveSequence,
+        PatternToken::AnyWithin(ref specifiers) => Token::AnyWithin(members(specifiers)),
+        PatternToken::AnyExcept(ref specifiers) => Token::AnyExcept(members(specifiers)),
+    }
+}
+
+fn members(specifiers: &[CharSpecifier]) -> Vec<ClassMember> {
+    specifiers
+        .iter()
+        .map(|specifier| match *specifier {
+            CharSpecifier::SingleChar(c) => ClassMember::Char(c),
+            CharSpecifier::CharRange(start, end) => ClassMember::Range(start, end),
+        })
+        .collect()
+}
+
+pub(crate) fn to_pattern_token(token: &Token) -> PatternToken {
+    match *token {
+        Token::Char(c) => PatternToken::Char(c),
+        Token::AnyChar => PatternToken::AnyChar,
+        Token::AnySequence => PatternToken::AnySequence,
+        Token::AnyRecursiveSequence => PatternToken::AnyRecursiveSequence,
+        Token::AnyWithin(ref members) => PatternToken::AnyWithin(specifiers(members)),
+        Token::AnyExcept(ref members) => PatternToken::AnyExcept(specifiers(members)),
+        Token::__Nonexhaustive => unreachable!(),
+    }
+}
+
+pub(crate) fn specifiers(members: &[ClassMember]) -> Vec<CharSpecifier> {
+    members
+        .iter()
+        .map(|member| match *member {
+            ClassMember::Char(c) => CharSpecifier::SingleChar(c),
+            ClassMember::Range(start, end) => CharSpecifier::CharRange(start, end),
+            ClassMember::__Nonexhaustive => unreachable!(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClassMember, Token};
+    use {ParseOptions, Pattern};
+
+    #[test]
+    fn test_tokens() {
+        let tokens = Pattern::new("a*[!]x-z]/**/b").unwrap().tokens();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Char('a'),
+                Token::AnySequence,
+                Token::AnyExcept(vec![ClassMember::Char(']'), ClassMember::Range('x', 'z')]),
+                Token::Char('/'),
+                Token::AnyRecursiveSequence,
+                Token::Char('b'),
+            ]
+        );
+
+        let options = ParseOptions::new().braces(true);
+        let pattern = Pattern::new_with("*.{rs,md}", options).unwrap();
+        assert!(pattern.tokens().is_empty());
+        assert_eq!(pattern.alternatives().len(), 2);
+        assert_eq!(pattern.alternatives()[1].tokens().len(), 4);
+        assert!(Pattern::new("*.rs").unwrap().alternatives().is_empty());
+    }
+
+    #[test]
+    fn test_from_tokens() {
+        for p in &["a*[!]x-z]/**/b", "[*][?]x", "**", "[!!]", "a[-]"] {
+            let pattern = Pattern::new(p).unwrap();
+            let rebuilt = Pattern::from_tokens(&pattern.tokens()).unwrap();
+            assert_eq!(rebuilt.tokens(), pattern.tokens(), "{:?}", p);
+        }
+
+        let pattern = Pattern::from_tokens(&[Token::AnySequence, Token::Char('*')]).unwrap();
+        assert_eq!(pattern.as_str(), "*[*]");
+        assert!(pattern.matches("a*"));
+        assert!(!pattern.matches("a"));
+
+        assert!(Pattern::from_tokens(&[Token::Char('a'), Token::AnyRecursiveSequence]).is_err());
+        let pattern = Pattern::from_tokens(&[Token::AnyWithin(vec![])]).unwrap();
+        assert!(!pattern.matches("a"));
+    }
+}
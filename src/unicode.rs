@@ -0,0 +1,119 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Unicode property classes, see `ParseOptions::unicode_classes`.
+//!
+//! The properties are looked up in the tables of `regex-syntax`, so a class
+//! accepts the same names as `\p{...}` in the `regex` crate.
+
+use CharSpecifier;
+use {PatternError, PatternErrorKind};
+
+#[cfg(feature = "unicode")]
+const ERROR_UNKNOWN_PROPERTY: &str = "unknown Unicode property";
+#[cfg(not(feature = "unicode"))]
+const ERROR_UNICODE_FEATURE: &str = "Unicode properties need the `unicode` feature";
+const ERROR_UNCLOSED_PROPERTY: &str = "unclosed Unicode property";
+
+// Parses the property class that starts with the `\` at `chars[i]`, like
+// `\pL`, `\p{Greek}` or the negated `\P{L}`, into the characters it matches
+// and the number of `chars` it takes up. Returns `None` if there is no class
+// there, in which case the `\` is an ordinary character.
+pub(crate) fn property_class(
+    chars: &[char],
+    i: usize,
+) -> Result<Option<(Vec<CharSpecifier>, usize)>, PatternError> {
+    let negated = match chars.get(i + 1) {
+        Some(&'p') => false,
+        Some(&'P') => true,
+        _ => return Ok(None),
+    };
+    let (name, len): (String, usize) = match chars.get(i + 2) {
+        Some(&'{') => match chars[i + 3..].iter().position(|&c| c == '}') {
+            Some(j) => (chars[i + 3..i + 3 + j].iter().collect(), j + 4),
+            None => {
+                return Err(PatternError {
+                    pos: i,
+                    msg: ERROR_UNCLOSED_PROPERTY,
+                    kind: PatternErrorKind::Syntax,
+                })
+            }
+        },
+        Some(&c) => (c.to_string(), 3),
+        None => return Ok(None),
+    };
+    property(&name, negated, i).map(|specifiers| Some((specifiers, len)))
+}
+
+#[cfg(feature = "unicode")]
+fn property(name: &str, negated: bool, pos: usize) -> Result<Vec<CharSpecifier>, PatternError> {
+    use regex_syntax::hir::{Class, HirKind};
+    use CharSpecifier::CharRange;
+
+    let regex = format!("\\{}{{{}}}", if negated { 'P' } else { 'p' }, name);
+    let hir = regex_syntax::Parser::new().parse(&regex).ok();
+    match hir.as_ref().map(|hir| hir.kind()) {
+        Some(&HirKind::Class(Class::Unicode(ref class))) => Ok(class
+            .ranges()
+            .iter()
+            .map(|range| CharRange(range.start(), range.end()))
+            .collect()),
+        _ => Err(PatternError {
+            pos,
+            msg: ERROR_UNKNOWN_PROPERTY,
+            kind: PatternErrorKind::Syntax,
+        }),
+    }
+}
+
+#[cfg(not(feature = "unicode"))]
+fn property(_name: &str, _negated: bool, pos: usize) -> Result<Vec<CharSpecifier>, PatternError> {
+    Err(PatternError {
+        pos,
+        msg: ERROR_UNICODE_FEATURE,
+        kind: PatternErrorKind::Syntax,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {ParseOptions, Pattern};
+
+    #[test]
+    fn test_unicode_classes() {
+        let options = ParseOptions::new().unicode_classes(true);
+        let new = |p| Pattern::new_with(p, options);
+        assert!(new("\\p{L").is_err());
+        assert!(Pattern::new("\\p{L}").unwrap().matches("\\p{L}"));
+        assert!(new("a\\").unwrap().matches("a\\"));
+        assert!(new("\\x").unwrap().matches("\\x"));
+        if cfg!(not(feature = "unicode")) {
+            assert!(new("\\pL").is_err());
+            return;
+        }
+
+        let pattern = new("\\p{Greek}*.txt").unwrap();
+        assert!(pattern.matches("λόγος.txt"));
+        assert!(!pattern.matches("logos.txt"));
+        let pattern = new("[\\p{Lu}\\p{Nd}]\\pL*").unwrap();
+        assert!(pattern.matches("Ärger"));
+        assert!(pattern.matches("1é"));
+        assert!(!pattern.matches("ärger"));
+        let pattern = new("\\P{L}[!\\p{Nd}]").unwrap();
+        assert!(pattern.matches("_x"));
+        assert!(!pattern.matches("ax"));
+        assert!(!pattern.matches("_٣"));
+        assert!(new("\\p{Klingon}").is_err());
+        assert!(new("[\\p{Klingon}]").is_err());
+
+        let escaped = Pattern::escape_with("\\pL", options);
+        assert!(new(&escaped).unwrap().matches("\\pL"));
+    }
+}
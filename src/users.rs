@@ -0,0 +1,169 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Looking up the home directories of users by name, for `~name` in
+//! `glob::shell`.
+
+// `libc` and `windows-sys` need a newer compiler than the rest of the crate
+#![allow(clippy::incompatible_msrv)]
+
+// The home directory of the user `name` according to the password database.
+#[cfg(unix)]
+pub(crate) fn home_dir(name: &str) -> Option<String> {
+    use libc;
+    use std::ffi::{CStr, CString};
+    use std::mem;
+    use std::ptr;
+
+    let name = CString::new(name).ok()?;
+    let mut buf: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut found = ptr::null_mut();
+        let error = unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                &mut passwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut found,
+            )
+        };
+        // the strings of the entry didn't fit into `buf`
+        if error == libc::ERANGE && buf.len() < 1 << 20 {
+            let len = buf.len() * 2;
+            buf.resize(len, 0);
+            continue;
+        }
+        if error != 0 || found.is_null() || passwd.pw_dir.is_null() {
+            return None;
+        }
+        let dir = unsafe { CStr::from_ptr(passwd.pw_dir) };
+        return dir.to_str().ok().map(str::to_string);
+    }
+}
+
+// The profile directory of the user `name`, which the registry lists by the
+// user's security identifier.
+#[cfg(windows)]
+pub(crate) fn home_dir(name: &str) -> Option<String> {
+    use std::ffi::{OsStr, OsString};
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::ptr;
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::Authorization::ConvertSidToStringSidW;
+    use windows_sys::Win32::Security::LookupAccountNameW;
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+
+    fn wide(s: &str) -> Vec<u16> {
+        OsStr::new(s).encode_wide().chain(Some(0)).collect()
+    }
+    // the string up to the terminating nul
+    fn from_wide(s: &[u16]) -> OsString {
+        let len = s.iter().position(|&c| c == 0).unwrap_or(s.len());
+        OsString::from_wide(&s[..len])
+    }
+
+    let name = wide(name);
+    let (mut sid_len, mut domain_len, mut kind) = (0, 0, 0);
+    unsafe {
+        LookupAccountNameW(
+            ptr::null(),
+            name.as_ptr(),
+            ptr::null_mut(),
+            &mut sid_len,
+            ptr::null_mut(),
+            &mut domain_len,
+            &mut kind,
+        );
+    }
+    if sid_len == 0 {
+        return None;
+    }
+    let mut sid = vec![0u8; sid_len as usize];
+    let mut domain = vec![0u16; domain_len as usize];
+    let found = unsafe {
+        LookupAccountNameW(
+            ptr::null(),
+            name.as_ptr(),
+            sid.as_mut_ptr() as *mut _,
+            &mut sid_len,
+            domain.as_mut_ptr(),
+            &mut domain_len,
+            &mut kind,
+        )
+    };
+    if found == 0 {
+        return None;
+    }
+
+    let mut string_sid = ptr::null_mut();
+    if unsafe { ConvertSidToStringSidW(sid.as_mut_ptr() as *mut _, &mut string_sid) } == 0 {
+        return None;
+    }
+    let mut len = 0;
+    while unsafe { *string_sid.offset(len) } != 0 {
+        len += 1;
+    }
+    let sid = unsafe { ::std::slice::from_raw_parts(string_sid, len as usize) };
+    let key = format!(
+        r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\ProfileList\{}",
+        from_wide(sid).to_string_lossy()
+    );
+    unsafe { LocalFree(string_sid as *mut _) };
+
+    // `RRF_RT_REG_SZ` also expands the variables of a `REG_EXPAND_SZ`
+    let (key, value) = (wide(&key), wide("ProfileImagePath"));
+    let mut size = 0;
+    let mut path: Vec<u16> = Vec::new();
+    loop {
+        let error = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                key.as_ptr(),
+                value.as_ptr(),
+                RRF_RT_REG_SZ,
+                ptr::null_mut(),
+                if path.is_empty() {
+                    ptr::null_mut()
+                } else {
+                    path.as_mut_ptr() as *mut _
+                },
+                &mut size,
+            )
+        };
+        if error != ERROR_SUCCESS {
+            return None;
+        }
+        let len = (size as usize + 1) / 2;
+        if path.len() >= len {
+            return from_wide(&path).into_string().ok();
+        }
+        path = vec![0; len];
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn home_dir(_: &str) -> Option<String> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::home_dir;
+
+    #[test]
+    fn test_home_dir() {
+        // every Unix system has a superuser
+        assert!(home_dir("root").is_some());
+        assert_eq!(home_dir("no-such-user-glob"), None);
+        assert_eq!(home_dir("a\0b"), None);
+    }
+}
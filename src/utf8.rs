@@ -0,0 +1,146 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Globbing with `camino`'s UTF-8 paths.
+//!
+//! This module is only available with the `camino` feature. It offers
+//! `glob_utf8`, which yields `Utf8PathBuf`s rather than `PathBuf`s, for code
+//! bases that only deal in UTF-8 paths. See also
+//! `Pattern::matches_utf8_path`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use glob::utf8::glob_utf8;
+//!
+//! for path in glob_utf8("src/**/*.rs").unwrap() {
+//!     let path = path.unwrap();
+//!     println!("{} has extension {:?}", path, path.extension());
+//! }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use camino::Utf8PathBuf;
+
+use {glob, GlobError, Paths, PatternError};
+
+/// An iterator that yields the matches of a `Paths` iterator as
+/// `Utf8PathBuf`s.
+///
+/// A match that isn't valid UTF-8 is yielded as a `Utf8GlobError::NotUtf8`
+/// rather than being skipped, and iteration carries on after it.
+///
+/// Created by `glob_utf8` or `Paths::utf8`.
+#[derive(Debug)]
+pub struct Utf8Paths {
+    paths: Paths,
+}
+
+impl Utf8Paths {
+    pub(crate) fn new(paths: Paths) -> Self {
+        Self { paths }
+    }
+}
+
+/// An error yielded by `Utf8Paths`.
+#[derive(Debug)]
+pub enum Utf8GlobError {
+    /// An error yielded by the underlying `Paths` iterator.
+    Glob(GlobError),
+
+    /// The given path matched, but it isn't valid UTF-8.
+    NotUtf8(PathBuf),
+}
+
+impl Error for Utf8GlobError {
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        match *self {
+            Utf8GlobError::Glob(ref e) => e.description(),
+            Utf8GlobError::NotUtf8(_) => "path is not valid UTF-8",
+        }
+    }
+
+    #[allow(unknown_lints, bare_trait_objects)]
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            Utf8GlobError::Glob(ref e) => Some(e),
+            Utf8GlobError::NotUtf8(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Utf8GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Utf8GlobError::Glob(ref e) => e.fmt(f),
+            Utf8GlobError::NotUtf8(ref path) => {
+                write!(f, "path {} is not valid UTF-8", path.display())
+            }
+        }
+    }
+}
+
+/// Return an iterator that produces all the `Utf8PathBuf`s that match the
+/// given pattern using default match options, like `glob` does for
+/// `PathBuf`s.
+///
+/// This may return an error if the pattern is invalid.
+pub fn glob_utf8(pattern: &str) -> Result<Utf8Paths, PatternError> {
+    glob(pattern).map(Utf8Paths::new)
+}
+
+impl Iterator for Utf8Paths {
+    type Item = Result<Utf8PathBuf, Utf8GlobError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.paths.next().map(|result| match result {
+            Ok(path) => Utf8PathBuf::from_path_buf(path).map_err(Utf8GlobError::NotUtf8),
+            Err(error) => Err(Utf8GlobError::Glob(error)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::glob_utf8;
+    use camino::{Utf8Path, Utf8PathBuf};
+    use Pattern;
+
+    #[test]
+    fn test_glob_utf8() {
+        let paths: Vec<Utf8PathBuf> = glob_utf8("src/utf8.r[s]")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(paths, vec![Utf8Path::new("src").join("utf8.rs")]);
+
+        let pattern = Pattern::new("src/*.rs").unwrap();
+        assert!(pattern.matches_utf8_path(Utf8Path::new("src/utf8.rs")));
+        assert!(!pattern.matches_utf8_path(Utf8Path::new("src/utf8.md")));
+        assert!(pattern.matches_utf8_path(Utf8Path::new("src/./utf8.rs")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_not_utf8() {
+        use super::Utf8GlobError;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::PathBuf;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"caf\xe9"));
+        let error = Utf8GlobError::NotUtf8(path);
+        assert_eq!(error.to_string(), "path caf\u{fffd} is not valid UTF-8");
+    }
+}
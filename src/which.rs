@@ -0,0 +1,211 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::vec;
+
+use {MatchOptions, Pattern, PatternError};
+
+/// An iterator that yields the executables in a search path whose names
+/// match a pattern.
+///
+/// The directories are searched in order, and the matches in each directory
+/// are yielded in alphabetical order. An executable is only yielded the first
+/// time its command name is seen, since later ones are shadowed by it.
+///
+/// See the `which_glob` function for more details.
+#[derive(Debug)]
+pub struct Executables {
+    pattern: Pattern,
+    options: MatchOptions,
+    dirs: vec::IntoIter<PathBuf>,
+    // the matches in the directory being read, with their command names
+    current: vec::IntoIter<(PathBuf, String)>,
+    seen: HashSet<String>,
+    // the extensions of executables, on Windows
+    extensions: Option<Vec<String>>,
+}
+
+/// Return an iterator that produces the executables in the directories of
+/// `$PATH` whose names match the given pattern, e.g. to discover plugins
+/// named like `cargo-*`.
+///
+/// This may return an error if the pattern is invalid.
+///
+/// On Unix, only files with an execute permission bit set are yielded. On
+/// Windows, only files with one of the extensions listed in `%PATHEXT%` are
+/// yielded, and the pattern is matched case-insensitively against their names
+/// both with and without the extension, so `git-*` finds `git-lfs.exe`.
+/// Empty entries of `$PATH` are ignored rather than searching the current
+/// directory, and directories that can't be read are skipped.
+///
+/// See the `Executables` documentation for more information.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use glob::which_glob;
+///
+/// for plugin in which_glob("cargo-*").unwrap() {
+///     println!("{}", plugin.display());
+/// }
+/// ```
+pub fn which_glob(pattern: &str) -> Result<Executables, PatternError> {
+    which_glob_in(pattern, env::var_os("PATH").unwrap_or_default())
+}
+
+/// Return an iterator that produces the executables in the directories of
+/// `paths`, in the format of `$PATH`, whose names match the given pattern.
+///
+/// See `which_glob` for details.
+pub fn which_glob_in<T: AsRef<OsStr>>(
+    pattern: &str,
+    paths: T,
+) -> Result<Executables, PatternError> {
+    let pattern = Pattern::new(pattern)?;
+    Ok(Executables {
+        pattern,
+        options: MatchOptions {
+            case_sensitive: !cfg!(windows),
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        },
+        dirs: env::split_paths(&paths)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect::<Vec<_>>()
+            .into_iter(),
+        current: Vec::new().into_iter(),
+        seen: HashSet::new(),
+        extensions: extensions(),
+    })
+}
+
+impl Executables {
+    // Return the name `name` is run by, if it has the extension of an
+    // executable.
+    fn command_name(&self, name: &str) -> Option<String> {
+        let extensions = match self.extensions {
+            Some(ref extensions) => extensions,
+            None => return Some(name.to_string()),
+        };
+        let name = name.to_lowercase();
+        extensions
+            .iter()
+            .find(|ext| name.len() > ext.len() && name.ends_with(ext.as_str()))
+            .map(|ext| name[..name.len() - ext.len()].to_string())
+    }
+
+    fn read(&self, dir: &Path) -> Vec<(PathBuf, String)> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut found: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let path = e.path();
+                let command = {
+                    // FIXME: non-UTF-8 names are skipped, as in `glob`
+                    let name = path.file_name()?.to_str()?;
+                    let command = self.command_name(name)?;
+                    if !self.pattern.matches_with(name, self.options)
+                        && !self.pattern.matches_with(&command, self.options)
+                    {
+                        return None;
+                    }
+                    command
+                };
+                if is_executable(&path) {
+                    Some((path, command))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        found.sort();
+        found
+    }
+}
+
+impl Iterator for Executables {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            if let Some((path, command)) = self.current.next() {
+                if self.seen.insert(command) {
+                    return Some(path);
+                }
+                continue;
+            }
+            let dir = self.dirs.next()?;
+            self.current = self.read(&dir).into_iter();
+        }
+    }
+}
+
+#[cfg(windows)]
+fn extensions() -> Option<Vec<String>> {
+    let pathext = env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    Some(
+        pathext
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| ext.to_lowercase())
+            .collect(),
+    )
+}
+
+#[cfg(not(windows))]
+fn extensions() -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).map(|m| m.is_file()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::which_glob_in;
+
+    #[test]
+    fn test_command_name() {
+        let mut executables = which_glob_in("*", "").unwrap();
+        assert_eq!(executables.next(), None);
+        assert_eq!(
+            executables.command_name("cargo-fmt"),
+            Some("cargo-fmt".to_string())
+        );
+
+        executables.extensions = Some(vec![".exe".to_string(), ".bat".to_string()]);
+        assert_eq!(
+            executables.command_name("Git-LFS.EXE"),
+            Some("git-lfs".to_string())
+        );
+        assert_eq!(executables.command_name("run.bat"), Some("run".to_string()));
+        assert_eq!(executables.command_name(".exe"), None);
+        assert_eq!(executables.command_name("README.md"), None);
+    }
+}
@@ -15,10 +15,19 @@
 extern crate glob;
 extern crate tempdir;
 
-use glob::{glob, glob_with};
+use glob::{
+    glob, glob_in, glob_many, glob_with, BrokenSymlinks, GlobBuilder, GlobErrorKind, RelativeTo,
+    SkipReason, SortOrder, Visit,
+};
+#[cfg(unix)]
+use glob::{which_glob_in, FileKind};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tempdir::TempDir;
 
 #[test]
@@ -69,7 +78,7 @@ fn main() {
     }
 
     let root = TempDir::new("glob-tests");
-    let root = root.ok().expect("Should have created a temp directory");
+    let root = root.expect("Should have created a temp directory");
     assert!(env::set_current_dir(root.path()).is_ok());
 
     mk_file("aaa", true);
@@ -131,6 +140,35 @@ fn main() {
         )
     );
 
+    // `**` can be told not to descend into symlinked directories it finds,
+    // while links named by other components are still followed
+    let mut no_recursive_links = GlobBuilder::new();
+    no_recursive_links.recursive_follow_links(false);
+    let glob_no_links = |pattern| -> Vec<PathBuf> {
+        no_recursive_links
+            .glob(pattern)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    };
+    assert_eq!(glob_no_links("dirsym/**/*.md"), Vec::<PathBuf>::new());
+    assert_eq!(
+        glob_no_links("dirsym/**"),
+        vec!(PathBuf::from("dirsym/link"))
+    );
+    assert_eq!(
+        glob_no_links("dirsym/*/*.md"),
+        vec!(PathBuf::from("dirsym/link/current_dir.md"))
+    );
+    assert_eq!(
+        glob_no_links("dirsym/link/**/a.md"),
+        vec!(
+            PathBuf::from("dirsym/link/another/a.md"),
+            PathBuf::from("dirsym/link/one/a.md"),
+            PathBuf::from("dirsym/link/one/another/a.md")
+        )
+    );
+
     // all recursive entities
     assert_eq!(
         glob_vec("r/**"),
@@ -144,11 +182,34 @@ fn main() {
         )
     );
 
+    // a trailing `**` can be asked to yield files too
+    let options = glob::MatchOptions {
+        trailing_recursive_matches_files: true,
+        ..glob::MatchOptions::new()
+    };
+    assert_eq!(
+        glob_with_vec("r/one/**", options),
+        vec!(
+            PathBuf::from("r/one/a.md"),
+            PathBuf::from("r/one/another"),
+            PathBuf::from("r/one/another/a.md"),
+            PathBuf::from("r/one/another/deep"),
+            PathBuf::from("r/one/another/deep/spelunking.md")
+        )
+    );
+    assert_eq!(
+        glob_with_vec("r/one/**/", options),
+        vec!(
+            PathBuf::from("r/one/another"),
+            PathBuf::from("r/one/another/deep")
+        )
+    );
+
     // std-canonicalized windows verbatim disk paths should work
     if env::consts::FAMILY == "windows" {
         let r_verbatim = PathBuf::from("r").canonicalize().unwrap();
         assert_eq!(
-            glob_vec(&format!("{}\\**", r_verbatim.display().to_string()))
+            glob_vec(&format!("{}\\**", r_verbatim.display()))
                 .into_iter()
                 .map(|p| p.strip_prefix(&r_verbatim).unwrap().to_owned())
                 .collect::<Vec<_>>(),
@@ -379,6 +440,7 @@ fn main() {
         case_sensitive: false,
         require_literal_separator: true,
         require_literal_leading_dot: true,
+        ..glob::MatchOptions::new()
     };
     assert_eq!(glob_with_vec("i/**/*a*", options), Vec::<PathBuf>::new());
     assert_eq!(glob_with_vec("i/**/*c*", options), Vec::<PathBuf>::new());
@@ -474,4 +536,929 @@ fn main() {
             )
         );
     }
+
+    // a hardened glob stays within the current directory
+    mk_file("confine", true);
+    mk_symlink_dir(root.path().join("r").to_str().unwrap(), "confine/inside");
+    mk_symlink_dir(
+        root.path().parent().unwrap().to_str().unwrap(),
+        "confine/outside",
+    );
+    let hardened = GlobBuilder::hardened();
+    assert_eq!(
+        hardened.glob("../*").err().unwrap().kind(),
+        glob::PatternErrorKind::EscapesRoot
+    );
+    assert_eq!(
+        hardened
+            .glob(root.path().join("*").to_str().unwrap())
+            .err()
+            .unwrap()
+            .kind(),
+        glob::PatternErrorKind::EscapesRoot
+    );
+    assert_eq!(
+        hardened
+            .glob("confine/*/*")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("confine/inside/another"),
+            PathBuf::from("confine/inside/current_dir.md"),
+            PathBuf::from("confine/inside/one"),
+            PathBuf::from("confine/inside/three"),
+            PathBuf::from("confine/inside/two")
+        )
+    );
+
+    // a traversal budget stops the iterator with an error
+    let mut paths = GlobBuilder::new().max_entries(Some(2)).glob("r/*").unwrap();
+    assert_eq!(
+        paths.next().unwrap().unwrap_err().kind(),
+        GlobErrorKind::TooManyResults
+    );
+    assert!(paths.next().is_none());
+
+    // while a limit on the matches just stops it
+    assert_eq!(
+        GlobBuilder::new()
+            .max_results(Some(2))
+            .glob("r/**/*.md")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("r/another/a.md"),
+            PathBuf::from("r/current_dir.md")
+        )
+    );
+    assert!(GlobBuilder::new()
+        .max_results(Some(0))
+        .glob("r/*")
+        .unwrap()
+        .next()
+        .is_none());
+
+    // cancelling it ends it with an error
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut paths = GlobBuilder::new()
+        .cancel_flag(Some(cancel.clone()))
+        .glob("r/**/*.md")
+        .unwrap();
+    assert_eq!(
+        paths.next().unwrap().unwrap(),
+        PathBuf::from("r/another/a.md")
+    );
+    cancel.store(true, Ordering::Relaxed);
+    assert_eq!(
+        paths.next().unwrap().unwrap_err().kind(),
+        GlobErrorKind::Cancelled
+    );
+    assert!(paths.next().is_none());
+
+    // excluded directories are pruned along with their contents
+    let excluding = || {
+        let mut builder = GlobBuilder::new();
+        builder.exclude(glob::Pattern::new("**/another").unwrap());
+        builder
+    };
+    assert_eq!(
+        excluding()
+            .glob("r/**/*.md")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("r/current_dir.md"),
+            PathBuf::from("r/one/a.md"),
+            PathBuf::from("r/three/c.md"),
+            PathBuf::from("r/two/b.md")
+        )
+    );
+    let skipped = excluding()
+        .glob("r/**/*.md")
+        .unwrap()
+        .tagged()
+        .filter_map(|r| match r.unwrap() {
+            Visit::Skipped(path, SkipReason::Excluded) => Some(path),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        skipped,
+        vec!(PathBuf::from("r/another"), PathBuf::from("r/one/another"))
+    );
+
+    // searching hidden directories is separate from matching hidden entries
+    mk_file("dots", true);
+    mk_file("dots/.git", true);
+    mk_file("dots/.git/config.rs", false);
+    mk_file("dots/.hidden.rs", false);
+    mk_file("dots/src", true);
+    mk_file("dots/src/a.rs", false);
+    let dots = |literal_dot: bool, descend: Option<bool>| {
+        GlobBuilder::new()
+            .match_options(glob::MatchOptions {
+                require_literal_leading_dot: literal_dot,
+                ..glob::MatchOptions::new()
+            })
+            .descend_hidden(descend)
+            .glob("dots/**/*.rs")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        dots(false, None),
+        vec!(
+            PathBuf::from("dots/.git/config.rs"),
+            PathBuf::from("dots/.hidden.rs"),
+            PathBuf::from("dots/src/a.rs")
+        )
+    );
+    assert_eq!(
+        dots(false, Some(false)),
+        vec!(
+            PathBuf::from("dots/.hidden.rs"),
+            PathBuf::from("dots/src/a.rs")
+        )
+    );
+    assert_eq!(dots(true, None), vec!(PathBuf::from("dots/src/a.rs")));
+    assert_eq!(
+        dots(true, Some(true)),
+        vec!(
+            PathBuf::from("dots/.git/config.rs"),
+            PathBuf::from("dots/src/a.rs")
+        )
+    );
+    assert_eq!(
+        GlobBuilder::new()
+            .match_options(glob::MatchOptions {
+                require_literal_leading_dot: true,
+                ..glob::MatchOptions::new()
+            })
+            .descend_hidden(Some(true))
+            .glob("dots/**")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(PathBuf::from("dots/src"))
+    );
+
+    // ignore files prune what they ignore
+    mk_file("ignoring", true);
+    mk_file("ignoring/build", true);
+    mk_file("ignoring/build/x.rs", false);
+    mk_file("ignoring/sub", true);
+    for &(path, contents) in &[
+        ("ignoring/.gitignore", "*.log\nbuild/\n"),
+        ("ignoring/sub/.ignore", "!keep.log\n"),
+        ("ignoring/a.rs", ""),
+        ("ignoring/a.log", ""),
+        ("ignoring/sub/keep.log", ""),
+        ("ignoring/sub/b.log", ""),
+    ] {
+        fs::File::create(path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+    }
+    let mut ignoring = GlobBuilder::new()
+        .ignore_files(true)
+        .glob("ignoring/**/*")
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect::<Vec<_>>();
+    ignoring.sort();
+    assert_eq!(
+        ignoring,
+        vec!(
+            PathBuf::from("ignoring/.gitignore"),
+            PathBuf::from("ignoring/a.rs"),
+            PathBuf::from("ignoring/sub"),
+            PathBuf::from("ignoring/sub/.ignore"),
+            PathBuf::from("ignoring/sub/keep.log")
+        )
+    );
+    assert_eq!(glob("ignoring/**/*").unwrap().count(), 9);
+
+    // the inverse of a glob yields what's left over, children first
+    assert_eq!(
+        glob::glob_not("r/**/a.md")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("r/current_dir.md"),
+            PathBuf::from("r/one/another/deep/spelunking.md"),
+            PathBuf::from("r/one/another/deep"),
+            PathBuf::from("r/three/c.md"),
+            PathBuf::from("r/three"),
+            PathBuf::from("r/two/b.md"),
+            PathBuf::from("r/two")
+        )
+    );
+    assert_eq!(
+        glob::glob_not("xyz/[xy]")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(PathBuf::from("xyz/z"))
+    );
+
+    // tagged iteration explains why entries were left out
+    let options = glob::MatchOptions {
+        require_literal_leading_dot: true,
+        ..glob::MatchOptions::new()
+    };
+    assert_eq!(
+        glob_with("i/qwe/*e*/", options)
+            .unwrap()
+            .tagged()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            Visit::Skipped(PathBuf::from("i/qwe/.aaa"), SkipReason::Hidden),
+            Visit::Skipped(PathBuf::from("i/qwe/.bbb"), SkipReason::Hidden),
+            Visit::Skipped(PathBuf::from("i/qwe/eee"), SkipReason::NotADirectory)
+        )
+    );
+    assert_eq!(
+        glob("xyz/[xz]")
+            .unwrap()
+            .tagged()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            Visit::Matched(PathBuf::from("xyz/x")),
+            Visit::Skipped(PathBuf::from("xyz/y"), SkipReason::Mismatch(1)),
+            Visit::Matched(PathBuf::from("xyz/z"))
+        )
+    );
+    // buffer tuning doesn't change what is yielded
+    assert_eq!(
+        GlobBuilder::new()
+            .todo_capacity(64)
+            .read_batch_size(1)
+            .shrink_threshold(Some(0))
+            .glob("r/**/*.md")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        glob("r/**/*.md")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+    );
+
+    // the fingerprint of a file set changes along with the files
+    let fingerprint = |pattern: &str| glob(pattern).unwrap().fingerprinted().finish();
+    let before = fingerprint("xyz/*");
+    assert_eq!(fingerprint("xyz/*"), before);
+    assert!(fingerprint("xyz/[xy]") != before);
+    fs::write("xyz/x", "changed").unwrap();
+    assert!(fingerprint("xyz/*") != before);
+    fs::write("xyz/x", "").unwrap();
+
+    // the usual filesystems on Linux are case sensitive
+    if cfg!(target_os = "linux") {
+        assert!(glob::is_case_sensitive(".").unwrap());
+        assert!(glob::is_case_sensitive("bbb/specials").unwrap());
+        assert!(GlobBuilder::new()
+            .match_options(glob::MatchOptions {
+                case_sensitive: false,
+                ..glob::MatchOptions::new()
+            })
+            .auto_case_sensitive(true)
+            .glob("xyz/[X]")
+            .unwrap()
+            .next()
+            .is_none());
+    }
+
+    // resolving symlinks yields real locations and reports broken links
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink("does-not-exist", "r/three/broken").unwrap();
+        let cwd = env::current_dir().unwrap().canonicalize().unwrap();
+        let mut paths = GlobBuilder::new()
+            .resolve_symlinks(true)
+            .glob("r/three/*")
+            .unwrap();
+        let broken = paths.next().unwrap().err().unwrap();
+        assert_eq!(broken.path(), Path::new("r/three/broken"));
+        assert_eq!(paths.next().unwrap().unwrap(), cwd.join("r/three/c.md"));
+        assert!(paths.next().is_none());
+        fs::remove_file("r/three/broken").unwrap();
+        assert_eq!(
+            GlobBuilder::new()
+                .resolve_symlinks(true)
+                .glob("dirsym/*/three/*")
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect::<Vec<_>>(),
+            vec!(cwd.join("r/three/c.md"))
+        );
+    }
+
+    // symlink targets are reported along with matches
+    assert_eq!(
+        glob("dirsym/*")
+            .unwrap()
+            .with_link_targets()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!((PathBuf::from("dirsym/link"), Some(root.path().join("r"))))
+    );
+    assert_eq!(
+        glob("xyz/x")
+            .unwrap()
+            .with_link_targets()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!((PathBuf::from("xyz/x"), None))
+    );
+
+    // symlink targets are reported along with matches
+    assert_eq!(
+        glob("dirsym/*")
+            .unwrap()
+            .with_link_targets()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!((PathBuf::from("dirsym/link"), Some(root.path().join("r"))))
+    );
+    assert_eq!(
+        glob("xyz/x")
+            .unwrap()
+            .with_link_targets()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!((PathBuf::from("xyz/x"), None))
+    );
+
+    // broken symlinks can be included, excluded or reported
+    #[cfg(unix)]
+    {
+        let broken = |policy| {
+            GlobBuilder::new()
+                .broken_symlinks(policy)
+                .glob("aaa/*a*")
+                .unwrap()
+                .map(|r| r.map_err(|e| e.path().to_path_buf()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            broken(BrokenSymlinks::Include),
+            vec!(
+                Ok(PathBuf::from("aaa/apple")),
+                Ok(PathBuf::from("aaa/green_apple")),
+                Ok(PathBuf::from("aaa/kazusa")),
+                Ok(PathBuf::from("aaa/orange")),
+                Ok(PathBuf::from("aaa/tomato"))
+            )
+        );
+        assert_eq!(
+            broken(BrokenSymlinks::Exclude),
+            vec!(
+                Ok(PathBuf::from("aaa/apple")),
+                Ok(PathBuf::from("aaa/orange")),
+                Ok(PathBuf::from("aaa/tomato"))
+            )
+        );
+        assert_eq!(
+            broken(BrokenSymlinks::Error),
+            vec!(
+                Ok(PathBuf::from("aaa/apple")),
+                Err(PathBuf::from("aaa/green_apple")),
+                Err(PathBuf::from("aaa/kazusa")),
+                Ok(PathBuf::from("aaa/orange")),
+                Ok(PathBuf::from("aaa/tomato"))
+            )
+        );
+        assert_eq!(
+            GlobBuilder::new()
+                .broken_symlinks(BrokenSymlinks::Error)
+                .glob("aaa/k*")
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .kind(),
+            GlobErrorKind::BrokenSymlink
+        );
+        assert_eq!(
+            GlobBuilder::new()
+                .broken_symlinks(BrokenSymlinks::Exclude)
+                .glob("aaa/k*")
+                .unwrap()
+                .tagged()
+                .map(|r| r.unwrap())
+                .filter(|v| v.path() == Path::new("aaa/kazusa"))
+                .collect::<Vec<_>>(),
+            vec!(Visit::Skipped(
+                PathBuf::from("aaa/kazusa"),
+                SkipReason::BrokenSymlink
+            ))
+        );
+    }
+
+    // matches and errors can be separated
+    let (paths, errors) = GlobBuilder::new()
+        .broken_symlinks(BrokenSymlinks::Error)
+        .glob("aaa/*")
+        .unwrap()
+        .partition_results();
+    assert_eq!(paths.len(), if cfg!(unix) { 3 } else { 5 });
+    assert_eq!(errors.len(), if cfg!(unix) { 2 } else { 0 });
+
+    // matches can be filtered by size and modification time
+    mk_file("sizes", true);
+    fs::write("sizes/small.log", b"x").unwrap();
+    fs::write("sizes/large.log", vec![0; 1000]).unwrap();
+    let sized = |min: Option<u64>, max: Option<u64>| {
+        GlobBuilder::new()
+            .min_size(min)
+            .max_size(max)
+            .glob("sizes/*.log")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sized(None, None).len(), 2);
+    assert_eq!(
+        sized(Some(100), None),
+        vec!(PathBuf::from("sizes/large.log"))
+    );
+    assert_eq!(
+        sized(None, Some(100)),
+        vec!(PathBuf::from("sizes/small.log"))
+    );
+    assert_eq!(sized(Some(1), Some(1000)).len(), 2);
+    assert!(sized(Some(2), Some(999)).is_empty());
+    let modified = |since: SystemTime| {
+        GlobBuilder::new()
+            .modified_since(Some(since))
+            .glob("sizes/*.log")
+            .unwrap()
+            .count()
+    };
+    assert_eq!(modified(UNIX_EPOCH), 2);
+    assert_eq!(modified(SystemTime::now() + Duration::from_secs(3600)), 0);
+    assert_eq!(
+        GlobBuilder::new()
+            .min_size(Some(100))
+            .glob("sizes/small.log")
+            .unwrap()
+            .tagged()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(Visit::Skipped(
+            PathBuf::from("sizes/small.log"),
+            SkipReason::Excluded
+        ))
+    );
+
+    // paths matched by several patterns are yielded once, unless asked for
+    // one item per pattern
+    assert_eq!(
+        glob_many(&["aaa/*", "aaa/apple", "bbb/*"])
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("aaa/apple"),
+            PathBuf::from("aaa/green_apple"),
+            PathBuf::from("aaa/kazusa"),
+            PathBuf::from("aaa/orange"),
+            PathBuf::from("aaa/tomato"),
+            PathBuf::from("bbb/specials")
+        )
+    );
+    assert_eq!(
+        glob_many(vec!["aaa/a*", "aaa/*le"])
+            .unwrap()
+            .per_pattern()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            (PathBuf::from("aaa/apple"), 0),
+            (PathBuf::from("aaa/apple"), 1),
+            (PathBuf::from("aaa/green_apple"), 1)
+        )
+    );
+    assert!(glob_many(&["aaa/*", "[a"]).is_err());
+
+    // the matches of several patterns can be merged in order
+    assert_eq!(
+        glob_many(&["bbb/*", "aaa/*e", "aaa/a*"])
+            .unwrap()
+            .sorted()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("aaa/apple"),
+            PathBuf::from("aaa/green_apple"),
+            PathBuf::from("aaa/orange"),
+            PathBuf::from("bbb/specials")
+        )
+    );
+    assert_eq!(
+        glob_many(&["aaa/*e", "aaa/a*"])
+            .unwrap()
+            .sorted()
+            .per_pattern()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            (PathBuf::from("aaa/apple"), 0),
+            (PathBuf::from("aaa/apple"), 1),
+            (PathBuf::from("aaa/green_apple"), 0),
+            (PathBuf::from("aaa/orange"), 0)
+        )
+    );
+
+    // the order of the entries of each directory can be customized
+    assert_eq!(
+        GlobBuilder::new()
+            .sort_by(|a, b| b.cmp(a))
+            .glob("aaa/*[ae]")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("aaa/orange"),
+            PathBuf::from("aaa/kazusa"),
+            PathBuf::from("aaa/green_apple"),
+            PathBuf::from("aaa/apple")
+        )
+    );
+    assert_eq!(
+        GlobBuilder::new()
+            .sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)))
+            .glob("aaa/*")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>(),
+        vec!(
+            PathBuf::from("aaa/apple"),
+            PathBuf::from("aaa/kazusa"),
+            PathBuf::from("aaa/orange"),
+            PathBuf::from("aaa/tomato"),
+            PathBuf::from("aaa/green_apple")
+        )
+    );
+
+    // chains of symbolic links are only followed so far by `**`
+    #[cfg(unix)]
+    {
+        mk_file("chain", true);
+        mk_file("chain/a", true);
+        mk_file("chain/a/x", false);
+        mk_symlink_dir("../a", "chain/a/b");
+
+        let results: Vec<_> = GlobBuilder::new()
+            .max_link_depth(Some(2))
+            .glob("chain/**/x")
+            .unwrap()
+            .collect();
+        let paths: Vec<_> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok().cloned())
+            .collect();
+        assert_eq!(
+            paths,
+            vec!(
+                PathBuf::from("chain/a/b/b/x"),
+                PathBuf::from("chain/a/b/x"),
+                PathBuf::from("chain/a/x")
+            )
+        );
+        let errors: Vec<_> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path(), Path::new("chain/a/b/b/b"));
+        assert_eq!(errors[0].kind(), GlobErrorKind::TooManyLinks);
+
+        // a link back to a directory above it isn't searched by `**`
+        let results: Vec<_> = GlobBuilder::new()
+            .detect_link_cycles(true)
+            .glob("chain/**/x")
+            .unwrap()
+            .collect();
+        let paths: Vec<_> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok().cloned())
+            .collect();
+        assert_eq!(paths, vec!(PathBuf::from("chain/a/x")));
+        let errors: Vec<_> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path(), Path::new("chain/a/b"));
+        assert_eq!(errors[0].kind(), GlobErrorKind::Loop);
+        let cycles = |pattern: &str| {
+            GlobBuilder::new()
+                .detect_link_cycles(true)
+                .glob(pattern)
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(cycles("chain/a/b/x"), vec!(PathBuf::from("chain/a/b/x")));
+        assert_eq!(cycles("chain/*/*/x"), vec!(PathBuf::from("chain/a/b/x")));
+
+        // or links may not be followed at all
+        let unfollowed = |pattern: &str| {
+            GlobBuilder::new()
+                .follow_symlinks(false)
+                .glob(pattern)
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect::<Vec<_>>()
+        };
+        // matches can be restricted to a kind of entry
+        let kinds = |kind: FileKind| {
+            GlobBuilder::new()
+                .file_kind(Some(kind))
+                .glob("chain/a/*")
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kinds(FileKind::Dir), vec!(PathBuf::from("chain/a/b")));
+        assert_eq!(kinds(FileKind::File), vec!(PathBuf::from("chain/a/x")));
+        assert_eq!(kinds(FileKind::Symlink), vec!(PathBuf::from("chain/a/b")));
+        // or yielded with their kind
+        assert_eq!(
+            glob("chain/a/*")
+                .unwrap()
+                .with_file_kinds()
+                .map(|r| r.unwrap())
+                .collect::<Vec<_>>(),
+            vec!(
+                (PathBuf::from("chain/a/b"), FileKind::Symlink),
+                (PathBuf::from("chain/a/x"), FileKind::File)
+            )
+        );
+        assert_eq!(
+            glob("chain")
+                .unwrap()
+                .with_file_kinds()
+                .map(|r| r.unwrap())
+                .collect::<Vec<_>>(),
+            vec!((PathBuf::from("chain"), FileKind::Dir))
+        );
+
+        assert!(unfollowed("chain/a/b/*").is_empty());
+        assert!(unfollowed("chain/*/*/x").is_empty());
+        assert_eq!(
+            unfollowed("chain/**/*"),
+            vec!(
+                PathBuf::from("chain/a"),
+                PathBuf::from("chain/a/b"),
+                PathBuf::from("chain/a/x")
+            )
+        );
+    }
+
+    // executables in a search path are found by name
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        mk_file("bin1", true);
+        mk_file("bin2", true);
+        for path in &[
+            "bin1/cargo-foo",
+            "bin1/cargo-bar",
+            "bin2/cargo-foo",
+            "bin2/cargo-baz",
+        ] {
+            mk_file(path, false);
+            if *path != "bin1/cargo-bar" {
+                fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+            }
+        }
+        mk_file("bin2/cargo-dir", true);
+
+        let search_path = env::join_paths(&["bin1", "", "bin2", "missing"]).unwrap();
+        assert_eq!(
+            which_glob_in("cargo-*", &search_path)
+                .unwrap()
+                .collect::<Vec<_>>(),
+            vec!(
+                PathBuf::from("bin1/cargo-foo"),
+                PathBuf::from("bin2/cargo-baz")
+            )
+        );
+        assert_eq!(
+            which_glob_in("cargo-ba?", &search_path)
+                .unwrap()
+                .collect::<Vec<_>>(),
+            vec!(PathBuf::from("bin2/cargo-baz"))
+        );
+    }
+
+    // braces are expanded when enabled, and matches stay in order
+    mk_file("braces", true);
+    for path in &[
+        "braces/b.toml",
+        "braces/a.rs",
+        "braces/c.md",
+        "braces/ab.rs",
+    ] {
+        mk_file(path, false);
+    }
+    let mut builder = GlobBuilder::new();
+    builder.parse_options(glob::ParseOptions::new().braces(true));
+    let braced = |pattern: &str| {
+        builder
+            .glob(pattern)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        braced("braces/{b,a}*.{toml,rs}"),
+        vec!(
+            PathBuf::from("braces/a.rs"),
+            PathBuf::from("braces/ab.rs"),
+            PathBuf::from("braces/b.toml")
+        )
+    );
+    assert_eq!(
+        braced("{missing,braces}/{c,c}.md"),
+        vec!(PathBuf::from("braces/c.md"))
+    );
+    assert!(glob_vec("braces/{a,b}.rs").is_empty());
+
+    // `**` can be required to match at least one directory
+    mk_file("levels", true);
+    mk_file("levels/x", true);
+    mk_file("levels/x/y", true);
+    for path in &["levels/b", "levels/x/b", "levels/x/y/b"] {
+        mk_file(path, false);
+    }
+    let options = glob::MatchOptions {
+        require_recursive_component: true,
+        ..glob::MatchOptions::new()
+    };
+    assert_eq!(
+        glob_with_vec("levels/**/b", options),
+        vec!(PathBuf::from("levels/x/b"), PathBuf::from("levels/x/y/b"))
+    );
+    assert_eq!(glob_vec("levels/**/b").len(), 3);
+    assert_eq!(glob_with_vec("levels/**", options), glob_vec("levels/**"));
+
+    // the depth of matches can be limited
+    let depths = |min: Option<usize>, max: Option<usize>, pattern: &str| {
+        GlobBuilder::new()
+            .min_depth(min)
+            .max_depth(max)
+            .glob(pattern)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        depths(None, Some(2), "levels/**/b"),
+        vec!(PathBuf::from("levels/b"))
+    );
+    assert_eq!(
+        depths(Some(3), None, "levels/**/b"),
+        vec!(PathBuf::from("levels/x/b"), PathBuf::from("levels/x/y/b"))
+    );
+    assert_eq!(
+        depths(Some(3), Some(3), "levels/**/b"),
+        vec!(PathBuf::from("levels/x/b"))
+    );
+    assert_eq!(
+        depths(None, Some(2), "levels/**"),
+        vec!(PathBuf::from("levels/x"))
+    );
+    assert!(depths(None, Some(1), "levels/*").is_empty());
+    assert_eq!(depths(Some(4), None, "levels/*/*/b").len(), 1);
+
+    // relative patterns can be resolved against a directory of their own,
+    // which is taken literally
+    mk_file("base[1]", true);
+    mk_file("base[1]/a.rs", false);
+    mk_file("base[1]/sub", true);
+    mk_file("base[1]/sub/b.rs", false);
+    let glob_in_vec = |base: &Path, pattern: &str| -> Vec<PathBuf> {
+        glob_in(base, pattern)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    };
+    assert_eq!(
+        glob_in_vec(Path::new("base[1]"), "**/*.rs"),
+        vec!(
+            PathBuf::from("base[1]/a.rs"),
+            PathBuf::from("base[1]/sub/b.rs")
+        )
+    );
+    let base = root.path().join("base[1]");
+    assert_eq!(glob_in_vec(&base, "sub/*"), vec!(base.join("sub/b.rs")));
+    assert_eq!(
+        glob_in_vec(Path::new(""), "base[[]1]/a.*"),
+        glob_vec("base[[]1]/a.*")
+    );
+    let absolute = format!("{}/*.rs", glob::Pattern::escape(base.to_str().unwrap()));
+    assert_eq!(
+        glob_in_vec(Path::new("missing"), &absolute),
+        vec!(base.join("a.rs"))
+    );
+    assert!(GlobBuilder::new()
+        .confine(true)
+        .glob_in(&base, "../*")
+        .is_err());
+
+    // matches can be yielded relative to where the search starts, or to the
+    // literal base of the pattern
+    let relative = |relative_to: RelativeTo, base: Option<&Path>, pattern: &str| {
+        let mut builder = GlobBuilder::new();
+        builder.relative_to(relative_to);
+        match base {
+            Some(base) => builder.glob_in(base, pattern),
+            None => builder.glob(pattern),
+        }
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        relative(RelativeTo::Root, None, "./base[[]1]/*.rs"),
+        vec!(PathBuf::from("base[1]/a.rs"))
+    );
+    assert_eq!(
+        relative(RelativeTo::LiteralBase, Some(&base), "./**/*.rs"),
+        vec!(PathBuf::from("a.rs"), PathBuf::from("sub/b.rs"))
+    );
+    assert_eq!(
+        relative(RelativeTo::Root, Some(&base), "sub/*"),
+        vec!(PathBuf::from("sub/b.rs"))
+    );
+    assert_eq!(
+        relative(RelativeTo::LiteralBase, Some(&base), "sub/*"),
+        vec!(PathBuf::from("b.rs"))
+    );
+    assert_eq!(
+        relative(RelativeTo::Root, Some(&base), "."),
+        vec!(PathBuf::from("."))
+    );
+    assert_eq!(
+        relative(RelativeTo::Unchanged, Some(&base), "sub/*"),
+        vec!(base.join("sub/b.rs"))
+    );
+
+    // ordering by size or not at all
+    mk_file("ordered", true);
+    for &(name, len) in &[("a", 3), ("b", 1), ("c", 2)] {
+        fs::File::create(format!("ordered/{}", name))
+            .unwrap()
+            .set_len(len)
+            .unwrap();
+    }
+    let sorted = |order: SortOrder| {
+        GlobBuilder::new()
+            .sort_order(order)
+            .glob("ordered/*")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        sorted(SortOrder::Size),
+        vec!(
+            PathBuf::from("ordered/b"),
+            PathBuf::from("ordered/c"),
+            PathBuf::from("ordered/a")
+        )
+    );
+    let mut unsorted = sorted(SortOrder::Unsorted);
+    unsorted.sort();
+    assert_eq!(unsorted, sorted(SortOrder::Name));
+
+    // a directory cache doesn't change what is yielded, whether it's cold or
+    // warm
+    #[cfg(feature = "cache")]
+    {
+        let file = root.path().join("dir-cache");
+        for _ in 0..2 {
+            let cache = glob::cache::DirCache::open(&file).unwrap();
+            assert_eq!(
+                GlobBuilder::new()
+                    .dir_cache(Some(cache.clone()))
+                    .glob("r/**/*.md")
+                    .unwrap()
+                    .map(|r| r.unwrap())
+                    .collect::<Vec<_>>(),
+                glob("r/**/*.md")
+                    .unwrap()
+                    .map(|r| r.unwrap())
+                    .collect::<Vec<_>>()
+            );
+            cache.save().unwrap();
+        }
+    }
 }